@@ -0,0 +1,12 @@
+INSERT INTO
+    podcast (id, title, description)
+VALUES
+    (11, 'Podcast One', 'The first podcast'),
+    (12, 'Podcast Two', NULL);
+
+INSERT INTO
+    podcast_feed (podcast_id, feed_url, created)
+VALUES
+    (11, 'http://one.example.com/feed.rss', (DATETIME('now', '-7 days'))),
+    (12, 'http://two-old.example.com/feed.rss', (DATETIME('now', '-7 days'))),
+    (12, 'http://two-new.example.com/feed.rss', (DATETIME('now')));
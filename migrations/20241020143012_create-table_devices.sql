@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS devices (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    identifier TEXT NOT NULL, -- the id chosen by the client
+    caption TEXT NOT NULL DEFAULT '',
+    kind TEXT NOT NULL DEFAULT 'other',
+    created TIMESTAMP NOT NULL DEFAULT (DATETIME('now')),
+    updated TIMESTAMP,
+    deleted TIMESTAMP,
+    UNIQUE (user_id, identifier),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON UPDATE CASCADE ON DELETE CASCADE
+);
@@ -0,0 +1,20 @@
+CREATE TABLE IF NOT EXISTS episode_actions (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    subscription_id INTEGER,
+    device_id INTEGER,
+    podcast TEXT NOT NULL,
+    episode TEXT NOT NULL,
+    guid TEXT,
+    action TEXT NOT NULL,
+    timestamp TIMESTAMP NOT NULL,
+    started INTEGER,
+    position INTEGER,
+    total INTEGER,
+    created TIMESTAMP NOT NULL DEFAULT (DATETIME('now')),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON UPDATE CASCADE ON DELETE CASCADE,
+    FOREIGN KEY (subscription_id) REFERENCES subscriptions (id) ON UPDATE CASCADE ON DELETE SET NULL,
+    FOREIGN KEY (device_id) REFERENCES devices (id) ON UPDATE CASCADE ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS episode_actions_user_id_created ON episode_actions (user_id, created);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS login_flows (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    poll_token TEXT NOT NULL UNIQUE,
+    login_token TEXT NOT NULL UNIQUE,
+    user_id INTEGER,
+    app_password TEXT,
+    created TIMESTAMP NOT NULL DEFAULT (DATETIME('now')),
+    expires TIMESTAMP NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON UPDATE CASCADE ON DELETE CASCADE
+);
@@ -0,0 +1,4 @@
+ALTER TABLE devices ADD COLUMN last_seen TIMESTAMP;
+
+-- the device that made the latest change to the subscription, NULL when it wasn't made by a device
+ALTER TABLE user_subscriptions ADD COLUMN device_id INTEGER REFERENCES devices (id) ON UPDATE CASCADE ON DELETE SET NULL;
@@ -0,0 +1,4 @@
+-- user created tags, podcast tags (categories from the feed) don't belong to anyone
+ALTER TABLE tag ADD COLUMN user_id INTEGER REFERENCES users (id) ON UPDATE CASCADE ON DELETE CASCADE;
+
+CREATE UNIQUE INDEX IF NOT EXISTS tag_user_id_kind_name ON tag (user_id, kind, name);
@@ -0,0 +1,40 @@
+-- Replaces `subscription_tag`, which points at the old per user `subscription` table whose ids
+-- aren't those of `subscriptions`. Subscriptions are shared between users while tags are not, so
+-- the tag has to be keyed by the user as well.
+CREATE TABLE IF NOT EXISTS user_subscription_tags (
+    user_id INTEGER NOT NULL,
+    subscription_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+    created TIMESTAMP NOT NULL DEFAULT (DATETIME('now')),
+    PRIMARY KEY (user_id, subscription_id, tag_id),
+    FOREIGN KEY (user_id, subscription_id) REFERENCES user_subscriptions (user_id, subscription_id) ON UPDATE CASCADE ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tag (id) ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS user_subscription_tags_tag_id ON user_subscription_tags (tag_id);
+
+-- Carry over the tags of the old table, matching users by name and subscriptions by feed. Each
+-- user gets their own copy of the tag, as the API only lists the tags a user owns.
+INSERT OR IGNORE INTO tag (user_id, kind, name)
+SELECT DISTINCT u.id, 'user', t.name
+FROM subscription_tag st
+INNER JOIN tag t ON t.id = st.tag_id
+INNER JOIN subscription s ON s.id = st.subscription_id
+INNER JOIN user lu ON lu.id = s.user_id
+INNER JOIN users u ON u.username = lu.username
+WHERE st.deleted IS NULL AND t.deleted IS NULL;
+
+INSERT OR IGNORE INTO user_subscription_tags (user_id, subscription_id, tag_id, created)
+SELECT us.user_id, us.subscription_id, ut.id, st.created
+FROM subscription_tag st
+INNER JOIN tag t ON t.id = st.tag_id
+INNER JOIN subscription s ON s.id = st.subscription_id
+INNER JOIN user lu ON lu.id = s.user_id
+INNER JOIN users u ON u.username = lu.username
+INNER JOIN podcast_feed pf ON pf.podcast_id = s.podcast_id
+INNER JOIN subscription_feeds sf ON sf.feed = pf.feed_url
+INNER JOIN user_subscriptions us ON us.user_id = u.id AND us.subscription_id = sf.subscription_id
+INNER JOIN tag ut ON ut.user_id = u.id AND ut.kind = 'user' AND ut.name = t.name
+WHERE st.deleted IS NULL AND t.deleted IS NULL;
+
+DROP TABLE subscription_tag;
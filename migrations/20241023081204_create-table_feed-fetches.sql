@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS feed_fetches (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    feed TEXT NOT NULL UNIQUE,
+    etag TEXT,
+    last_modified TEXT,
+    interval INTEGER NOT NULL, -- seconds between fetches
+    next_fetch TIMESTAMP NOT NULL,
+    last_fetch TIMESTAMP,
+    created TIMESTAMP NOT NULL DEFAULT (DATETIME('now')),
+    updated TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS feed_fetches_next_fetch ON feed_fetches (next_fetch);
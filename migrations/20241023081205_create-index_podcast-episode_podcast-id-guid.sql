@@ -0,0 +1,14 @@
+-- Refreshed episodes are upserted by their GUID, so each podcast can only have one episode per GUID.
+--
+-- Databases that already have duplicates keep the most recently added copy of each episode.
+DELETE FROM podcast_episode
+WHERE
+    guid IS NOT NULL
+    AND id NOT IN (
+        SELECT MAX(id)
+        FROM podcast_episode
+        WHERE guid IS NOT NULL
+        GROUP BY podcast_id, guid
+    );
+
+CREATE UNIQUE INDEX IF NOT EXISTS podcast_episode_podcast_id_guid ON podcast_episode (podcast_id, guid);
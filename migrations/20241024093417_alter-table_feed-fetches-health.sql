@@ -0,0 +1,6 @@
+ALTER TABLE feed_fetches ADD COLUMN status TEXT; -- success, not_modified or failure, NULL until the first fetch
+ALTER TABLE feed_fetches ADD COLUMN http_code INTEGER;
+ALTER TABLE feed_fetches ADD COLUMN error TEXT;
+ALTER TABLE feed_fetches ADD COLUMN consecutive_failures INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE feed_fetches ADD COLUMN last_success TIMESTAMP;
+ALTER TABLE feed_fetches ADD COLUMN suspended TIMESTAMP;
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS websub_subscriptions (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    feed TEXT NOT NULL UNIQUE,
+    hub TEXT NOT NULL,
+    topic TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    status TEXT NOT NULL, -- pending, verified or denied
+    lease_expires TIMESTAMP,
+    created TIMESTAMP NOT NULL DEFAULT (DATETIME('now')),
+    updated TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS websub_subscriptions_lease_expires ON websub_subscriptions (lease_expires);
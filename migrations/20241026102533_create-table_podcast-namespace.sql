@@ -0,0 +1,78 @@
+ALTER TABLE podcast ADD COLUMN podcast_locked INTEGER;
+ALTER TABLE podcast ADD COLUMN podcast_locked_owner TEXT;
+
+ALTER TABLE podcast_episode ADD COLUMN podcast_chapters TEXT;
+ALTER TABLE podcast_episode ADD COLUMN podcast_chapters_type TEXT;
+ALTER TABLE podcast_episode ADD COLUMN podcast_season INTEGER;
+ALTER TABLE podcast_episode ADD COLUMN podcast_season_name TEXT;
+
+CREATE TABLE IF NOT EXISTS podcast_transcripts (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    episode_id INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    language TEXT,
+    rel TEXT,
+    created TIMESTAMP NOT NULL DEFAULT (DATETIME('now')),
+    FOREIGN KEY (episode_id) REFERENCES podcast_episode (id) ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS podcast_transcripts_episode_id ON podcast_transcripts (episode_id);
+
+CREATE TABLE IF NOT EXISTS podcast_fundings (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    podcast_id INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    message TEXT,
+    created TIMESTAMP NOT NULL DEFAULT (DATETIME('now')),
+    FOREIGN KEY (podcast_id) REFERENCES podcast (id) ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS podcast_fundings_podcast_id ON podcast_fundings (podcast_id);
+
+-- Persons without an episode belong to the whole podcast.
+CREATE TABLE IF NOT EXISTS podcast_persons (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    podcast_id INTEGER NOT NULL,
+    episode_id INTEGER,
+    name TEXT NOT NULL,
+    role TEXT,
+    group_name TEXT,
+    img TEXT,
+    href TEXT,
+    created TIMESTAMP NOT NULL DEFAULT (DATETIME('now')),
+    FOREIGN KEY (podcast_id) REFERENCES podcast (id) ON UPDATE CASCADE ON DELETE CASCADE,
+    FOREIGN KEY (episode_id) REFERENCES podcast_episode (id) ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS podcast_persons_podcast_id_episode_id ON podcast_persons (podcast_id, episode_id);
+
+-- Values without an episode belong to the whole podcast.
+CREATE TABLE IF NOT EXISTS podcast_values (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    podcast_id INTEGER NOT NULL,
+    episode_id INTEGER,
+    kind TEXT NOT NULL,
+    method TEXT NOT NULL,
+    suggested TEXT,
+    created TIMESTAMP NOT NULL DEFAULT (DATETIME('now')),
+    FOREIGN KEY (podcast_id) REFERENCES podcast (id) ON UPDATE CASCADE ON DELETE CASCADE,
+    FOREIGN KEY (episode_id) REFERENCES podcast_episode (id) ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS podcast_values_podcast_id_episode_id ON podcast_values (podcast_id, episode_id);
+
+CREATE TABLE IF NOT EXISTS podcast_value_recipients (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    value_id INTEGER NOT NULL,
+    name TEXT,
+    kind TEXT NOT NULL,
+    address TEXT NOT NULL,
+    split INTEGER NOT NULL,
+    fee INTEGER NOT NULL DEFAULT 0,
+    custom_key TEXT,
+    custom_value TEXT,
+    FOREIGN KEY (value_id) REFERENCES podcast_values (id) ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS podcast_value_recipients_value_id ON podcast_value_recipients (value_id);
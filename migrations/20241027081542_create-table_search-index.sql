@@ -0,0 +1,25 @@
+-- Rows share their rowid with the podcast or episode they index, and are replaced whenever the
+-- feed is stored.
+CREATE VIRTUAL TABLE IF NOT EXISTS podcast_search USING fts5 (
+    title,
+    description,
+    notes,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS episode_search USING fts5 (
+    title,
+    description,
+    notes,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO podcast_search (rowid, title, description, notes)
+SELECT id, title, description, itunes_summary
+FROM podcast
+WHERE deleted IS NULL;
+
+INSERT INTO episode_search (rowid, title, description, notes)
+SELECT id, title, description, COALESCE(itunes_summary, itunes_subtitle)
+FROM podcast_episode
+WHERE deleted IS NULL;
@@ -0,0 +1,53 @@
+-- Append-only history of the changes made to subscriptions.
+--
+-- Events without a user belong to the shared subscription (feed moves and GUID changes) and are
+-- shown to everyone subscribed to it.
+CREATE TABLE IF NOT EXISTS subscription_events (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER,
+    subscription_id INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    feed TEXT,
+    guid TEXT,
+    -- NULL for events recorded before the client was tracked
+    client TEXT,
+    device_id INTEGER,
+    created TIMESTAMP NOT NULL DEFAULT (DATETIME('now')),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON UPDATE CASCADE ON DELETE CASCADE,
+    FOREIGN KEY (subscription_id) REFERENCES subscriptions (id) ON UPDATE CASCADE ON DELETE CASCADE,
+    FOREIGN KEY (device_id) REFERENCES devices (id) ON UPDATE CASCADE ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS subscription_events_subscription_id ON subscription_events (subscription_id, user_id);
+
+-- Rebuild what we can of the history from the existing timestamps.
+INSERT INTO subscription_events (user_id, subscription_id, kind, feed, guid, created)
+SELECT
+    us.user_id, us.subscription_id, 'subscribed',
+    (SELECT feed FROM subscription_feeds WHERE subscription_id = us.subscription_id ORDER BY created ASC LIMIT 1),
+    (SELECT guid FROM subscription_guids WHERE subscription_id = us.subscription_id ORDER BY created ASC LIMIT 1),
+    us.created
+FROM user_subscriptions us;
+
+INSERT INTO subscription_events (user_id, subscription_id, kind, device_id, created)
+SELECT us.user_id, us.subscription_id, 'unsubscribed', us.device_id, us.deleted
+FROM user_subscriptions us
+WHERE us.deleted IS NOT NULL;
+
+INSERT INTO subscription_events (user_id, subscription_id, kind, feed, created)
+SELECT NULL, sf.subscription_id, 'feed_moved', sf.feed, sf.created
+FROM subscription_feeds sf
+WHERE JULIANDAY(sf.created) > (
+    SELECT MIN(JULIANDAY(created)) FROM subscription_feeds WHERE subscription_id = sf.subscription_id
+);
+
+INSERT INTO subscription_events (user_id, subscription_id, kind, guid, created)
+SELECT NULL, sg.subscription_id, 'guid_changed', sg.guid, COALESCE(sg.updated, sg.created)
+FROM subscription_guids sg
+WHERE JULIANDAY(sg.created) > (
+    SELECT MIN(JULIANDAY(created)) FROM subscription_guids WHERE subscription_id = sg.subscription_id
+);
+
+INSERT INTO subscription_events (user_id, subscription_id, kind, created)
+SELECT td.user_id, td.subscription_id, 'deleted', td.created
+FROM task_deletions td;
@@ -0,0 +1,8 @@
+-- Deletions wait out a grace period before the subscription is purged, and can be undone until
+-- then. `deleted` marks deletions that were undone.
+ALTER TABLE task_deletions ADD COLUMN purge_after TIMESTAMP;
+
+-- When the user had unsubscribed before deleting, so restoring puts it back the way it was.
+ALTER TABLE task_deletions ADD COLUMN previous_unsubscribed TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS task_deletions_user_id_subscription_id ON task_deletions (user_id, subscription_id);
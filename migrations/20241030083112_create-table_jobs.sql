@@ -0,0 +1,45 @@
+-- Background work, leased by the workers of its kind. `payload` is the JSON encoded job.
+CREATE TABLE IF NOT EXISTS jobs (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    -- deletion, identification, refresh, email, export or account_deletion
+    kind TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    -- Only one queued or running job can exist per key, so the same work isn't queued twice.
+    key TEXT,
+    status TEXT NOT NULL DEFAULT 'queued', -- queued, running, done, dead or cancelled
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL,
+    run_after TIMESTAMP NOT NULL DEFAULT (DATETIME('now')),
+    -- Running jobs whose lease ran out are handed to another worker, their worker is assumed
+    -- to have died.
+    leased_until TIMESTAMP,
+    last_error TEXT,
+    created TIMESTAMP NOT NULL DEFAULT (DATETIME('now')),
+    updated TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS jobs_kind_status_run_after ON jobs (kind, status, run_after);
+CREATE UNIQUE INDEX IF NOT EXISTS jobs_key ON jobs (key) WHERE status IN ('queued', 'running');
+
+-- Deletions still waiting out their grace period.
+INSERT INTO jobs (kind, payload, key, max_attempts, run_after)
+SELECT
+    'deletion',
+    json_object('kind', 'deletion', 'deletion_id', id),
+    'deletion:' || id,
+    5,
+    COALESCE(purge_after, DATETIME('now'))
+FROM task_deletions
+WHERE status = 'pending' AND deleted IS NULL;
+
+-- Subscriptions that haven't been identified yet.
+INSERT INTO jobs (kind, payload, key, max_attempts)
+SELECT
+    'identification',
+    json_object('kind', 'identification', 'subscription_id', subscription_id),
+    'identification:' || subscription_id,
+    10
+FROM task_identifications
+WHERE deleted IS NULL;
+
+DROP TABLE IF EXISTS task_identifications;
@@ -0,0 +1,5 @@
+-- Kinds of jobs an operator paused, their workers don't lease new jobs until resumed.
+CREATE TABLE IF NOT EXISTS job_pauses (
+    kind TEXT NOT NULL PRIMARY KEY,
+    created TIMESTAMP NOT NULL DEFAULT (DATETIME('now'))
+);
@@ -0,0 +1,15 @@
+-- Takeout archives of everything the server holds about a user, built by an export job and
+-- downloaded through a link containing `token` until it expires.
+CREATE TABLE IF NOT EXISTS exports (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    token TEXT NOT NULL UNIQUE,
+    archive BLOB, -- missing until the job is done
+    created TIMESTAMP NOT NULL DEFAULT (DATETIME('now')),
+    expires TIMESTAMP NOT NULL,
+    completed TIMESTAMP,
+    failed TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS exports_user_id ON exports (user_id);
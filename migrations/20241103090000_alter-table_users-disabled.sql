@@ -0,0 +1,3 @@
+-- Accounts an operator has disabled can't log in until they are enabled again, nothing they own
+-- is removed.
+ALTER TABLE users ADD COLUMN disabled TIMESTAMP;
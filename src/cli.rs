@@ -0,0 +1,195 @@
+use std::{
+    io::{BufRead as _, IsTerminal as _, Write as _},
+    path::{Path, PathBuf},
+};
+
+use validator::Validate as _;
+
+use crate::{config::Config, database::Database, models::users::NewUser};
+
+#[derive(clap::Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// The config file to read, `pod-sync.toml` is read if it exists when not set.
+    #[arg(long, global = true, env = "POD_SYNC_CONFIG")]
+    pub config: Option<PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Runs the public and private servers, the default.
+    Serve,
+    /// Takes a backup of the database into the backup directory, the server can keep running.
+    Backup,
+    /// Replaces the database with a backup, the server has to be stopped first.
+    Restore {
+        /// The backup to restore, like `backups/pod-sync-20241102T090000000Z.db`.
+        backup: PathBuf,
+    },
+    /// Manages the database schema.
+    #[command(subcommand)]
+    Migrate(MigrateCommand),
+    /// Inspects the config file.
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Manages user accounts.
+    #[command(subcommand)]
+    User(UserCommand),
+    /// Manages login sessions and app passwords.
+    #[command(subcommand)]
+    Session(SessionCommand),
+}
+
+#[derive(clap::Subcommand)]
+pub enum MigrateCommand {
+    /// Runs the migrations that haven't been applied yet.
+    Up,
+    /// Lists the migrations and whether they have been applied.
+    Status,
+}
+
+#[derive(clap::Subcommand)]
+pub enum ConfigCommand {
+    /// Checks the config, with the environment and secret files applied.
+    Check,
+}
+
+#[derive(clap::Subcommand)]
+pub enum UserCommand {
+    /// Creates a user, the password is read from standard input.
+    Create { username: String, email: String },
+    /// Stops the user from logging in and logs them out everywhere, nothing they own is removed.
+    Disable { username: String },
+    /// Lets a disabled user log in again.
+    Enable { username: String },
+    /// Replaces the user's password and logs them out everywhere, the new password is read from
+    /// standard input.
+    ResetPassword { username: String },
+}
+
+#[derive(clap::Subcommand)]
+pub enum SessionCommand {
+    /// Removes the sessions and app passwords that have expired.
+    Prune,
+}
+
+pub async fn migrate(path: Option<&Path>, command: MigrateCommand) -> anyhow::Result<()> {
+    let config = Config::load(path).await?;
+    let db = Database::open(&config.database).await?;
+
+    match command {
+        MigrateCommand::Up => {
+            db.migrate().await?;
+            tracing::info!("database migrated");
+        }
+        MigrateCommand::Status => {
+            for migration in db.migrations_status().await? {
+                println!(
+                    "{} {:<7} {}",
+                    migration.version,
+                    if migration.applied {
+                        "applied"
+                    } else {
+                        "pending"
+                    },
+                    migration.description
+                );
+            }
+        }
+    }
+
+    db.shutdown().await
+}
+
+pub async fn config(path: Option<&Path>, command: ConfigCommand) -> anyhow::Result<()> {
+    match command {
+        ConfigCommand::Check => {
+            Config::load(path).await?;
+            tracing::info!("config is valid");
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn user(path: Option<&Path>, command: UserCommand) -> anyhow::Result<()> {
+    let config = Config::load(path).await?;
+    let db = Database::new(&config.database).await?;
+
+    match command {
+        UserCommand::Create { username, email } => {
+            let user = NewUser {
+                username,
+                email,
+                password: read_password()?,
+            };
+            if let Err(errors) = user.validate() {
+                anyhow::bail!("Invalid user: {}", errors);
+            }
+
+            let id = db
+                .user_create(&user.username, &user.email, &user.password)
+                .await?;
+            tracing::info!(id, username = %user.username, "user created");
+        }
+        UserCommand::Disable { username } => {
+            if !db.user_set_disabled(&username, true).await? {
+                anyhow::bail!("No user named {}", username);
+            }
+            tracing::info!(%username, "user disabled");
+        }
+        UserCommand::Enable { username } => {
+            if !db.user_set_disabled(&username, false).await? {
+                anyhow::bail!("No user named {}", username);
+            }
+            tracing::info!(%username, "user enabled");
+        }
+        UserCommand::ResetPassword { username } => {
+            let password = read_password()?;
+
+            if !db.user_set_password(&username, &password).await? {
+                anyhow::bail!("No user named {}", username);
+            }
+            tracing::info!(%username, "password reset");
+        }
+    }
+
+    db.shutdown().await
+}
+
+pub async fn session(path: Option<&Path>, command: SessionCommand) -> anyhow::Result<()> {
+    let config = Config::load(path).await?;
+    let db = Database::new(&config.database).await?;
+
+    match command {
+        SessionCommand::Prune => {
+            let pruned = db.sessions_prune().await?;
+            tracing::info!(pruned, "sessions pruned");
+        }
+    }
+
+    db.shutdown().await
+}
+
+/// Reads a password from the first line of standard input, so it stays out of the shell history
+/// and can be piped in by scripts.
+fn read_password() -> anyhow::Result<String> {
+    let stdin = std::io::stdin();
+
+    if stdin.is_terminal() {
+        eprint!("Password: ");
+        std::io::stderr().flush()?;
+    }
+
+    let mut password = String::new();
+    stdin.lock().read_line(&mut password)?;
+
+    let password = password.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        anyhow::bail!("Password can't be empty");
+    }
+
+    Ok(password.to_string())
+}
@@ -0,0 +1,341 @@
+//! Online backups of the SQLite database, and restoring them while the server is stopped.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    str::FromStr as _,
+    time::Duration,
+};
+
+use anyhow::Context as _;
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqliteConnection, SqliteLockingMode},
+    ConnectOptions as _, Connection as _,
+};
+use time::{macros::format_description, OffsetDateTime};
+
+use crate::{
+    config::{BackupConfig, DatabaseConfig},
+    database::Database,
+    models::backups::Backup,
+};
+
+const PREFIX: &str = "pod-sync-";
+const EXTENSION: &str = ".db";
+
+fn is_backup(name: &str) -> bool {
+    name.starts_with(PREFIX) && name.ends_with(EXTENSION)
+}
+
+async fn read_backup(path: &Path) -> anyhow::Result<Backup> {
+    let metadata = tokio::fs::metadata(path)
+        .await
+        .with_context(|| format!("Failed to read backup {}", path.display()))?;
+
+    Ok(Backup {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        size: metadata.len(),
+        created: metadata.modified()?.into(),
+    })
+}
+
+/// Lists the backups in the directory, newest first.
+pub async fn list(cfg: &BackupConfig) -> anyhow::Result<Vec<Backup>> {
+    let mut entries = match tokio::fs::read_dir(&cfg.directory).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err).context("Failed to read backup directory"),
+    };
+
+    let mut paths = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        if is_backup(&entry.file_name().to_string_lossy()) {
+            paths.push(entry.path());
+        }
+    }
+    // The names start with the time they were taken at.
+    paths.sort_unstable_by(|a, b| b.cmp(a));
+
+    let mut backups = Vec::with_capacity(paths.len());
+    for path in paths {
+        backups.push(read_backup(&path).await?);
+    }
+
+    Ok(backups)
+}
+
+/// Removes all but the newest `retention` backups.
+async fn prune(cfg: &BackupConfig) -> anyhow::Result<()> {
+    if cfg.retention == 0 {
+        return Ok(());
+    }
+
+    for backup in list(cfg).await?.into_iter().skip(cfg.retention) {
+        let path = cfg.directory.join(&backup.name);
+
+        tokio::fs::remove_file(&path)
+            .await
+            .with_context(|| format!("Failed to remove old backup {}", path.display()))?;
+    }
+
+    Ok(())
+}
+
+impl Database {
+    /// Writes a consistent copy of the database into the backup directory with `VACUUM INTO`,
+    /// the server keeps running while it is taken.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn backup(&self, cfg: &BackupConfig) -> anyhow::Result<Backup> {
+        let format =
+            format_description!("[year][month][day]T[hour][minute][second][subsecond digits:3]Z");
+
+        tokio::fs::create_dir_all(&cfg.directory)
+            .await
+            .context("Failed to create backup directory")?;
+
+        let name = format!(
+            "{}{}{}",
+            PREFIX,
+            OffsetDateTime::now_utc().format(format)?,
+            EXTENSION
+        );
+        let path = cfg.directory.join(name);
+        let target = path
+            .to_str()
+            .context("Backup directory is not valid UTF-8")?;
+
+        sqlx::query("VACUUM INTO ?1")
+            .bind(target)
+            .execute(&self.pool)
+            .await
+            .context("Failed to run query: backup database")?;
+
+        let backup = read_backup(&path).await?;
+
+        prune(cfg).await?;
+
+        Ok(backup)
+    }
+}
+
+/// Checks that the backup is a healthy pod-sync database this version knows every migration of.
+///
+/// Backups from older versions are fine, the missing migrations run when the server starts.
+async fn verify(conn: &mut SqliteConnection) -> anyhow::Result<()> {
+    let integrity: String = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_one(&mut *conn)
+        .await
+        .context("Failed to check backup integrity")?;
+    if integrity != "ok" {
+        anyhow::bail!("Backup is corrupt: {}", integrity);
+    }
+
+    let applied: Vec<i64> = sqlx::query_scalar(
+        r#"--sql
+            SELECT version
+            FROM _sqlx_migrations
+            WHERE success = TRUE
+            ORDER BY version ASC
+        "#,
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .context("Backup is not a pod-sync database")?;
+
+    let known = sqlx::migrate!("./migrations")
+        .iter()
+        .map(|migration| migration.version)
+        .collect::<HashSet<_>>();
+
+    if let Some(version) = applied.iter().find(|version| !known.contains(version)) {
+        anyhow::bail!(
+            "Backup has migration {} which this version doesn't know about, restore it with the \
+             version of pod-sync that took it",
+            version
+        );
+    }
+
+    Ok(())
+}
+
+/// Fails if something else has the database open, like a server that is still running.
+///
+/// Only a connection in exclusive locking mode can lock a database other connections have open
+/// in WAL mode, so it is refused while they are around. Closing it checkpoints the write-ahead log
+/// as well.
+async fn ensure_unused(target: &Path) -> anyhow::Result<()> {
+    if !tokio::fs::try_exists(target).await? {
+        return Ok(());
+    }
+
+    let mut conn = SqliteConnectOptions::new()
+        .filename(target)
+        .locking_mode(SqliteLockingMode::Exclusive)
+        .busy_timeout(Duration::ZERO)
+        .connect()
+        .await
+        .with_context(|| format!("Failed to open database {}", target.display()))?;
+
+    let locked = sqlx::query("BEGIN EXCLUSIVE").execute(&mut conn).await;
+    if locked.is_ok() {
+        sqlx::query("ROLLBACK").execute(&mut conn).await?;
+    }
+    conn.close().await?;
+
+    if locked.is_err() {
+        anyhow::bail!(
+            "Database {} is in use, stop the server before restoring",
+            target.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Replaces the configured database with the backup, the server has to be stopped first.
+pub async fn restore(cfg: &DatabaseConfig, backup: &Path) -> anyhow::Result<PathBuf> {
+    let mut conn = SqliteConnectOptions::new()
+        .filename(backup)
+        .read_only(true)
+        .connect()
+        .await
+        .with_context(|| format!("Failed to open backup {}", backup.display()))?;
+    verify(&mut conn).await?;
+    conn.close().await?;
+
+    let target = SqliteConnectOptions::from_str(&cfg.url)
+        .with_context(|| format!("Invalid database url {}", cfg.url))?
+        .get_filename()
+        .to_path_buf();
+
+    ensure_unused(&target).await?;
+
+    // Copied next to the database first, so the swap itself is a rename and the database is
+    // never left half written.
+    let staging = PathBuf::from(format!("{}.restore", target.display()));
+    tokio::fs::copy(backup, &staging)
+        .await
+        .context("Failed to copy backup")?;
+
+    tokio::fs::rename(&staging, &target)
+        .await
+        .context("Failed to replace database")?;
+
+    // The write-ahead log belonged to the database that was replaced, it's only removed once the
+    // swap went through so a failed one leaves the old database whole.
+    for suffix in ["-wal", "-shm"] {
+        let path = PathBuf::from(format!("{}{}", target.display(), suffix));
+
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err).context("Failed to remove write-ahead log"),
+        }
+    }
+
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::{BackupConfig, DatabaseConfig},
+        database::Database,
+    };
+
+    fn temp_dir() -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("pod-sync-{:016x}", rand::random::<u64>()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/dummy.sql"))]
+    async fn backup_restore(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let dir = temp_dir();
+        let db = Database::new_test(pool).await.unwrap();
+        let cfg = BackupConfig {
+            directory: dir.join("backups"),
+            retention: 2,
+        };
+
+        for _ in 0..3 {
+            db.backup(&cfg).await.unwrap();
+            // Backups are named after the millisecond they were taken in.
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+
+        let backups = super::list(&cfg).await.unwrap();
+        assert_eq!(backups.len(), 2);
+
+        let database = DatabaseConfig {
+            url: format!("sqlite://{}", dir.join("pod-sync.db").display()),
+            ..DatabaseConfig::default()
+        };
+
+        // Refused while a server has the database open.
+        let running = Database::new(&database).await.unwrap();
+        assert!(
+            super::restore(&database, &cfg.directory.join(&backups[0].name))
+                .await
+                .is_err()
+        );
+        running.shutdown().await.unwrap();
+
+        super::restore(&database, &cfg.directory.join(&backups[0].name))
+            .await
+            .unwrap();
+        assert!(!dir.join("pod-sync.db-wal").exists());
+
+        let restored = Database::new(&database).await.unwrap();
+        assert!(restored
+            .user_get_by_id(Database::USER_ID)
+            .await
+            .unwrap()
+            .is_some());
+        restored.shutdown().await.unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/dummy.sql"))]
+    async fn restore_newer(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let dir = temp_dir();
+        let db = Database::new_test(pool.clone()).await.unwrap();
+        let cfg = BackupConfig {
+            directory: dir.clone(),
+            retention: 0,
+        };
+
+        // Taken by a version with a migration this one doesn't have.
+        sqlx::query(
+            r#"--sql
+                INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time)
+                VALUES (99991231235959, 'from the future', TRUE, X'', 0)
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let backup = db.backup(&cfg).await.unwrap();
+
+        let database = DatabaseConfig {
+            url: format!("sqlite://{}", dir.join("pod-sync.db").display()),
+            ..DatabaseConfig::default()
+        };
+        assert!(super::restore(&database, &dir.join(&backup.name))
+            .await
+            .is_err());
+        assert!(!dir.join("pod-sync.db").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,170 @@
+use anyhow::Context as _;
+use time::OffsetDateTime;
+
+use crate::database::{user::User, Database};
+
+#[derive(Debug, Clone, Copy, PartialEq, sqlx::Type)]
+#[sqlx(transparent)]
+pub struct DeviceId(pub i64);
+
+impl From<i64> for DeviceId {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+pub struct RowDevice {
+    pub id: DeviceId,
+    pub user_id: i64,
+    pub identifier: String,
+    pub caption: String,
+    pub kind: String,
+    pub created: OffsetDateTime,
+    pub updated: Option<OffsetDateTime>,
+    pub last_seen: Option<OffsetDateTime>,
+}
+
+impl Database {
+    /// Registers a device for the user, or updates the caption and kind of an existing one.
+    ///
+    /// Fields that aren't given are left as they are, the device is marked as seen either way.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn device_upsert(
+        &self,
+        user: &User,
+        identifier: &str,
+        caption: Option<&str>,
+        kind: Option<&str>,
+    ) -> anyhow::Result<RowDevice> {
+        let now = OffsetDateTime::now_utc();
+
+        sqlx::query_as!(
+            RowDevice,
+            r#"--sql
+                INSERT INTO devices (user_id, identifier, caption, kind, last_seen)
+                VALUES (?1, ?2, COALESCE(?3, ''), COALESCE(?4, 'other'), ?5)
+                ON CONFLICT (user_id, identifier) DO UPDATE
+                SET
+                    caption = COALESCE(?3, caption),
+                    kind = COALESCE(?4, kind),
+                    updated = CASE WHEN ?3 IS NULL AND ?4 IS NULL THEN updated ELSE ?5 END,
+                    last_seen = ?5,
+                    deleted = NULL
+                RETURNING id, user_id, identifier, caption, kind, created, updated, last_seen
+            "#,
+            user.id,
+            identifier,
+            caption,
+            kind,
+            now,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: upsert device")
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn device_get_by_identifier(
+        &self,
+        user: &User,
+        identifier: &str,
+    ) -> anyhow::Result<Option<RowDevice>> {
+        sqlx::query_as!(
+            RowDevice,
+            r#"--sql
+                SELECT
+                    id, user_id, identifier, caption, kind, created, updated, last_seen
+                FROM
+                    devices
+                WHERE
+                    user_id = ?1 AND identifier = ?2 AND deleted IS NULL
+            "#,
+            user.id,
+            identifier,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get device by identifier")
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn devices_get_all(&self, user: &User) -> anyhow::Result<Vec<RowDevice>> {
+        sqlx::query_as!(
+            RowDevice,
+            r#"--sql
+                SELECT
+                    id, user_id, identifier, caption, kind, created, updated, last_seen
+                FROM
+                    devices
+                WHERE
+                    user_id = ?1 AND deleted IS NULL
+                ORDER BY created ASC
+            "#,
+            user.id,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get user devices")
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn device_get_by_id(
+        &self,
+        user: &User,
+        id: DeviceId,
+    ) -> anyhow::Result<Option<RowDevice>> {
+        sqlx::query_as!(
+            RowDevice,
+            r#"--sql
+                SELECT
+                    id, user_id, identifier, caption, kind, created, updated, last_seen
+                FROM
+                    devices
+                WHERE
+                    user_id = ?1 AND id = ?2 AND deleted IS NULL
+            "#,
+            user.id,
+            id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get device by id")
+    }
+
+    /// Changes the caption of one of the user's devices, returning `false` if it doesn't exist.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn device_rename(
+        &self,
+        user: &User,
+        id: DeviceId,
+        caption: &str,
+    ) -> anyhow::Result<bool> {
+        let now = OffsetDateTime::now_utc();
+
+        let result = sqlx::query!(
+            r#"--sql
+                UPDATE devices
+                SET caption = ?3, updated = ?4
+                WHERE user_id = ?1 AND id = ?2 AND deleted IS NULL
+            "#,
+            user.id,
+            id,
+            caption,
+            now,
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to run query: rename device")?;
+
+        Ok(result.rows_affected() != 0)
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+
+use anyhow::Context as _;
+use time::OffsetDateTime;
+
+use crate::{
+    database::{device::DeviceId, user::User, Database},
+    models::episodes::EpisodeActionKind,
+};
+
+pub struct RowEpisodeAction {
+    pub id: i64,
+    pub podcast: String,
+    pub episode: String,
+    pub guid: Option<String>,
+    pub device: Option<String>,
+    pub action: EpisodeActionKind,
+    pub timestamp: OffsetDateTime,
+    pub started: Option<i64>,
+    pub position: Option<i64>,
+    pub total: Option<i64>,
+    pub created: OffsetDateTime,
+}
+
+pub struct NewEpisodeAction {
+    pub podcast: String,
+    pub episode: String,
+    pub guid: Option<String>,
+    pub device: Option<DeviceId>,
+    pub action: EpisodeActionKind,
+    pub timestamp: OffsetDateTime,
+    pub started: Option<i64>,
+    pub position: Option<i64>,
+    pub total: Option<i64>,
+}
+
+#[derive(Default)]
+pub struct EpisodeActionFilter<'f> {
+    pub since: Option<OffsetDateTime>,
+    pub podcast: Option<&'f str>,
+    /// Only return the actions made by this device.
+    pub device: Option<DeviceId>,
+    /// Skip the actions made by this device, so a device doesn't get its own actions back.
+    pub exclude: Option<DeviceId>,
+    /// Only return the latest action for each episode.
+    pub aggregated: bool,
+}
+
+impl Database {
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn episode_actions_create(
+        &self,
+        user: &User,
+        actions: Vec<NewEpisodeAction>,
+    ) -> anyhow::Result<()> {
+        let now = OffsetDateTime::now_utc();
+
+        let mut tx = self.pool.begin().await?;
+
+        for action in actions {
+            sqlx::query!(
+                r#"--sql
+                    INSERT INTO episode_actions (
+                        user_id, subscription_id, device_id, podcast, episode, guid, action,
+                        timestamp, started, position, total, created
+                    )
+                    VALUES (
+                        ?1, (SELECT subscription_id FROM subscription_feeds WHERE feed = ?3), ?2,
+                        ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11
+                    )
+                "#,
+                user.id,
+                action.device,
+                action.podcast,
+                action.episode,
+                action.guid,
+                action.action,
+                action.timestamp,
+                action.started,
+                action.position,
+                action.total,
+                now,
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to run query: create episode action")?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn episode_actions_get(
+        &self,
+        user: &User,
+        filter: EpisodeActionFilter<'_>,
+    ) -> anyhow::Result<Vec<RowEpisodeAction>> {
+        let EpisodeActionFilter {
+            since,
+            podcast,
+            device,
+            exclude,
+            aggregated,
+        } = filter;
+
+        let actions = sqlx::query_as!(
+            RowEpisodeAction,
+            r#"--sql
+                SELECT
+                    ea.id, ea.podcast, ea.episode, ea.guid, d.identifier as device,
+                    ea.action as "action: EpisodeActionKind", ea.timestamp, ea.started,
+                    ea.position, ea.total, ea.created
+                FROM
+                    episode_actions ea
+                LEFT JOIN devices d ON ea.device_id = d.id
+                WHERE
+                    ea.user_id = ?1
+                    AND (?2 IS NULL OR JULIANDAY(ea.created) >= JULIANDAY(?2))
+                    AND (?3 IS NULL OR ea.podcast = ?3)
+                    AND (?4 IS NULL OR ea.device_id = ?4)
+                    AND (?5 IS NULL OR ea.device_id IS NOT ?5)
+                ORDER BY ea.timestamp ASC, ea.id ASC
+            "#,
+            user.id,
+            since,
+            podcast,
+            device,
+            exclude,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get episode actions")?;
+
+        if !aggregated {
+            return Ok(actions);
+        }
+
+        let mut seen = HashSet::new();
+        let mut latest = actions
+            .into_iter()
+            .rev()
+            .filter(|action| seen.insert(action.episode.clone()))
+            .collect::<Vec<_>>();
+        latest.reverse();
+
+        Ok(latest)
+    }
+}
@@ -0,0 +1,235 @@
+use anyhow::Context as _;
+use data_encoding::HEXLOWER;
+use rand::{rngs::OsRng, RngCore as _};
+use time::OffsetDateTime;
+
+use crate::database::{
+    jobs::{self, Job},
+    user::User,
+    Database,
+};
+
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, sqlx::Type)]
+#[serde(transparent)]
+#[sqlx(transparent)]
+pub struct ExportId(pub i64);
+
+impl From<i64> for ExportId {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+/// How many exports a user can have at once, each one holds a whole archive until it expires.
+const MAX_EXPORTS: i64 = 3;
+
+pub struct RowExport {
+    pub id: ExportId,
+    pub user_id: i64,
+    /// The secret part of the download link.
+    pub token: String,
+    pub created: OffsetDateTime,
+    pub expires: OffsetDateTime,
+    pub completed: Option<OffsetDateTime>,
+    pub failed: Option<OffsetDateTime>,
+}
+
+impl Database {
+    /// Queues an export of everything the server holds about the user, it can be downloaded
+    /// until `lifetime` has passed.
+    ///
+    /// Returns `None` if the user already has as many exports as they can, until one expires.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn export_create(
+        &self,
+        user: &User,
+        lifetime: time::Duration,
+    ) -> anyhow::Result<Option<RowExport>> {
+        let now = OffsetDateTime::now_utc();
+        let expires = now + lifetime;
+
+        let mut bytes = [0; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let token = HEXLOWER.encode(&bytes);
+
+        let mut tx = self.pool.begin().await?;
+
+        let exports = sqlx::query!(
+            r#"--sql
+                SELECT COUNT(*) as "total!: i64"
+                FROM exports
+                WHERE user_id = ?1 AND JULIANDAY(expires) > JULIANDAY(?2)
+            "#,
+            user.id,
+            now,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to run query: count user exports")?
+        .total;
+
+        if exports >= MAX_EXPORTS {
+            return Ok(None);
+        }
+
+        let row = sqlx::query_as!(
+            RowExport,
+            r#"--sql
+                INSERT INTO exports (user_id, token, expires)
+                VALUES (?1, ?2, ?3)
+                RETURNING
+                    id as "id!", user_id as "user_id!", token as "token!",
+                    created as "created!: OffsetDateTime", expires as "expires!: OffsetDateTime",
+                    completed as "completed?: OffsetDateTime", failed as "failed?: OffsetDateTime"
+            "#,
+            user.id,
+            token,
+            expires,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to run query: create export")?;
+
+        jobs::enqueue(&mut *tx, &Job::Export { export_id: row.id }, now)
+            .await
+            .context("Failed to queue export")?;
+
+        tx.commit().await?;
+
+        Ok(Some(row))
+    }
+
+    /// Removes the exports that expired, for every user, returning how many there were.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn exports_prune(&self) -> anyhow::Result<u64> {
+        let now = OffsetDateTime::now_utc();
+
+        let result = sqlx::query!(
+            r#"--sql
+                DELETE FROM exports
+                WHERE JULIANDAY(expires) <= JULIANDAY(?1)
+            "#,
+            now,
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to run query: prune exports")?;
+
+        Ok(result.rows_affected())
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn export_get(&self, id: ExportId) -> anyhow::Result<Option<RowExport>> {
+        sqlx::query_as!(
+            RowExport,
+            r#"--sql
+                SELECT id, user_id, token, created, expires, completed, failed
+                FROM exports
+                WHERE id = ?1
+            "#,
+            id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get export")
+    }
+
+    /// Lists the user's exports that haven't expired yet, newest first.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn exports_get_all(&self, user: &User) -> anyhow::Result<Vec<RowExport>> {
+        let now = OffsetDateTime::now_utc();
+
+        sqlx::query_as!(
+            RowExport,
+            r#"--sql
+                SELECT id, user_id, token, created, expires, completed, failed
+                FROM exports
+                WHERE user_id = ?1 AND JULIANDAY(expires) > JULIANDAY(?2)
+                ORDER BY id DESC
+            "#,
+            user.id,
+            now,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get user exports")
+    }
+
+    /// Returns the archive of the user's export, if it is done and hasn't expired.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn export_get_archive(
+        &self,
+        user: &User,
+        token: &str,
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        let now = OffsetDateTime::now_utc();
+
+        let row = sqlx::query!(
+            r#"--sql
+                SELECT archive as "archive!: Vec<u8>"
+                FROM exports
+                WHERE
+                    user_id = ?1 AND token = ?2 AND archive IS NOT NULL
+                    AND JULIANDAY(expires) > JULIANDAY(?3)
+            "#,
+            user.id,
+            token,
+            now,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to run query: get export archive")?;
+
+        Ok(row.map(|row| row.archive))
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn export_complete(&self, id: ExportId, archive: &[u8]) -> anyhow::Result<()> {
+        let now = OffsetDateTime::now_utc();
+
+        sqlx::query!(
+            r#"--sql
+                UPDATE exports
+                SET archive = ?2, completed = ?3, failed = NULL
+                WHERE id = ?1
+            "#,
+            id,
+            archive,
+            now,
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to run query: complete export")?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn export_fail(&self, id: ExportId) -> anyhow::Result<()> {
+        let now = OffsetDateTime::now_utc();
+
+        sqlx::query!(
+            r#"--sql
+                UPDATE exports
+                SET failed = ?2
+                WHERE id = ?1
+            "#,
+            id,
+            now,
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to run query: fail export")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,721 @@
+use std::collections::HashMap;
+
+use anyhow::Context as _;
+use time::OffsetDateTime;
+
+use crate::{
+    database::{
+        namespace,
+        podcast::{EpisodeId, PodcastId},
+        search, Database,
+    },
+    models::{
+        rss::{Channel, Item},
+        subscriptions::{FeedHealth, FeedStatus},
+    },
+};
+
+pub struct RowFeedFetch {
+    pub id: i64,
+    pub feed: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// Seconds between fetches.
+    pub interval: i64,
+    pub next_fetch: OffsetDateTime,
+    pub last_fetch: Option<OffsetDateTime>,
+    pub consecutive_failures: i64,
+}
+
+struct RowFeedHealth {
+    feed: String,
+    status: Option<FeedStatus>,
+    http_code: Option<i64>,
+    error: Option<String>,
+    consecutive_failures: i64,
+    last_fetch: Option<OffsetDateTime>,
+    last_success: Option<OffsetDateTime>,
+    next_fetch: OffsetDateTime,
+    suspended: Option<OffsetDateTime>,
+}
+
+impl From<RowFeedHealth> for FeedHealth {
+    fn from(row: RowFeedHealth) -> Self {
+        Self {
+            status: row.status,
+            http_code: row.http_code.and_then(|code| u16::try_from(code).ok()),
+            error: row.error,
+            consecutive_failures: row.consecutive_failures,
+            last_fetch: row.last_fetch,
+            last_success: row.last_success,
+            next_fetch: row.suspended.is_none().then_some(row.next_fetch),
+            suspended: row.suspended,
+        }
+    }
+}
+
+/// The outcome of fetching a feed.
+pub struct FeedFetchResult<'a> {
+    pub status: FeedStatus,
+    pub http_code: Option<u16>,
+    pub error: Option<&'a str>,
+    pub etag: Option<&'a str>,
+    pub last_modified: Option<&'a str>,
+}
+
+impl Database {
+    /// Starts tracking the feeds of subscriptions that haven't been fetched before, they are due
+    /// right away.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn feed_fetches_enqueue(&self, interval: i64) -> anyhow::Result<u64> {
+        let now = OffsetDateTime::now_utc();
+
+        let result = sqlx::query!(
+            r#"--sql
+                INSERT INTO feed_fetches (feed, interval, next_fetch)
+                SELECT DISTINCT
+                    sf.feed, ?1, ?2
+                FROM
+                    subscription_feeds sf
+                INNER JOIN user_subscriptions us ON us.subscription_id = sf.subscription_id
+                WHERE
+                    sf.deleted IS NULL AND us.deleted IS NULL
+                ON CONFLICT (feed) DO NOTHING
+            "#,
+            interval,
+            now,
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to run query: enqueue feed fetches")?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Returns the feeds that are due to be fetched and still have a subscriber.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn feed_fetches_get_due(&self, limit: i64) -> anyhow::Result<Vec<RowFeedFetch>> {
+        let now = OffsetDateTime::now_utc();
+
+        sqlx::query_as!(
+            RowFeedFetch,
+            r#"--sql
+                SELECT
+                    ff.id, ff.feed, ff.etag, ff.last_modified, ff.interval, ff.next_fetch,
+                    ff.last_fetch, ff.consecutive_failures
+                FROM
+                    feed_fetches ff
+                WHERE
+                    ff.suspended IS NULL
+                    AND JULIANDAY(ff.next_fetch) <= JULIANDAY(?1)
+                    AND EXISTS (
+                        SELECT 1
+                        FROM subscription_feeds sf
+                        INNER JOIN user_subscriptions us ON us.subscription_id = sf.subscription_id
+                        WHERE sf.feed = ff.feed AND sf.deleted IS NULL AND us.deleted IS NULL
+                    )
+                ORDER BY JULIANDAY(ff.next_fetch) ASC
+                LIMIT ?2
+            "#,
+            now,
+            limit,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get due feed fetches")
+    }
+
+    /// Returns the feed if it isn't suspended and still has a subscriber.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn feed_fetch_get(&self, feed: &str) -> anyhow::Result<Option<RowFeedFetch>> {
+        sqlx::query_as!(
+            RowFeedFetch,
+            r#"--sql
+                SELECT
+                    ff.id, ff.feed, ff.etag, ff.last_modified, ff.interval, ff.next_fetch,
+                    ff.last_fetch, ff.consecutive_failures
+                FROM
+                    feed_fetches ff
+                WHERE
+                    ff.feed = ?1
+                    AND ff.suspended IS NULL
+                    AND EXISTS (
+                        SELECT 1
+                        FROM subscription_feeds sf
+                        INNER JOIN user_subscriptions us ON us.subscription_id = sf.subscription_id
+                        WHERE sf.feed = ff.feed AND sf.deleted IS NULL AND us.deleted IS NULL
+                    )
+            "#,
+            feed,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get feed fetch")
+    }
+
+    /// Records a fetch of the feed and when it should be fetched next.
+    ///
+    /// A successful fetch replaces the stored validators with the ones the server sent, even when
+    /// it sent none, while a fetch that wasn't modified or failed keeps them.
+    ///
+    /// Failures are counted, once `max_failures` happen in a row the feed is suspended and won't
+    /// be fetched again until it is resumed. Returns whether the feed is now suspended.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn feed_fetch_record(
+        &self,
+        id: i64,
+        result: &FeedFetchResult<'_>,
+        interval: i64,
+        next_fetch: OffsetDateTime,
+        max_failures: i64,
+    ) -> anyhow::Result<bool> {
+        let now = OffsetDateTime::now_utc();
+        let http_code = result.http_code.map(i64::from);
+
+        let row = sqlx::query!(
+            r#"--sql
+                UPDATE feed_fetches
+                SET
+                    etag = CASE
+                        WHEN ?7 = 'success' THEN ?2
+                        ELSE COALESCE(?2, etag)
+                    END,
+                    last_modified = CASE
+                        WHEN ?7 = 'success' THEN ?3
+                        ELSE COALESCE(?3, last_modified)
+                    END,
+                    interval = ?4,
+                    next_fetch = ?5,
+                    last_fetch = ?6,
+                    updated = ?6,
+                    status = ?7,
+                    http_code = ?8,
+                    error = ?9,
+                    consecutive_failures = CASE
+                        WHEN ?7 = 'failure' THEN consecutive_failures + 1
+                        ELSE 0
+                    END,
+                    last_success = CASE
+                        WHEN ?7 = 'failure' THEN last_success
+                        ELSE ?6
+                    END,
+                    suspended = CASE
+                        WHEN ?7 = 'failure' AND consecutive_failures + 1 >= ?10 THEN ?6
+                        ELSE NULL
+                    END
+                WHERE id = ?1
+                RETURNING suspended IS NOT NULL as "suspended!: bool"
+            "#,
+            id,
+            result.etag,
+            result.last_modified,
+            interval,
+            next_fetch,
+            now,
+            result.status,
+            http_code,
+            result.error,
+            max_failures,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to run query: record feed fetch")?;
+
+        Ok(row.map(|row| row.suspended).unwrap_or(false))
+    }
+
+    /// Clears the failures of a feed and makes it due right away, resuming it if it was suspended.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn feed_fetch_resume(&self, feed: &str) -> anyhow::Result<bool> {
+        let now = OffsetDateTime::now_utc();
+
+        let result = sqlx::query!(
+            r#"--sql
+                UPDATE feed_fetches
+                SET
+                    consecutive_failures = 0,
+                    suspended = NULL,
+                    next_fetch = ?2,
+                    updated = ?2
+                WHERE feed = ?1
+            "#,
+            feed,
+            now,
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to run query: resume feed fetch")?;
+
+        Ok(result.rows_affected() != 0)
+    }
+
+    /// Returns how fetching the feed has been going, `None` if it isn't being tracked yet.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn feed_health_get(&self, feed: &str) -> anyhow::Result<Option<FeedHealth>> {
+        let row = sqlx::query_as!(
+            RowFeedHealth,
+            r#"--sql
+                SELECT
+                    feed, status as "status: FeedStatus", http_code, error, consecutive_failures,
+                    last_fetch, last_success, next_fetch, suspended
+                FROM
+                    feed_fetches
+                WHERE
+                    feed = ?1
+            "#,
+            feed,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to run query: get feed health")?;
+
+        Ok(row.map(FeedHealth::from))
+    }
+
+    /// Returns how fetching each of the feeds has been going, keyed by feed, feeds that aren't
+    /// being tracked yet are left out.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn feeds_health_get(
+        &self,
+        feeds: &[&str],
+    ) -> anyhow::Result<HashMap<String, FeedHealth>> {
+        let feeds = serde_json::to_string(feeds)?;
+
+        let rows = sqlx::query_as!(
+            RowFeedHealth,
+            r#"--sql
+                SELECT
+                    feed, status as "status: FeedStatus", http_code, error, consecutive_failures,
+                    last_fetch, last_success, next_fetch, suspended
+                FROM
+                    feed_fetches
+                WHERE
+                    feed IN (SELECT value FROM json_each(?1))
+            "#,
+            feeds,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to run query: get feeds health")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.feed.clone(), FeedHealth::from(row)))
+            .collect())
+    }
+
+    /// Stores the podcast and episodes of a fetched (or pushed) feed, returning how many episodes
+    /// are new.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn feed_store(&self, feed: &str, channel: &Channel) -> anyhow::Result<usize> {
+        let podcast = self.podcast_upsert_from_feed(feed, channel).await?;
+
+        self.podcast_episodes_upsert(podcast, &channel.items).await
+    }
+
+    /// Creates or updates the podcast behind the feed from its channel.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn podcast_upsert_from_feed(
+        &self,
+        feed: &str,
+        channel: &Channel,
+    ) -> anyhow::Result<PodcastId> {
+        let now = OffsetDateTime::now_utc();
+
+        let image = channel.image();
+        let category = channel.category();
+        let subcategory = channel.subcategory();
+        let owner_name = channel
+            .itunes_owner
+            .as_ref()
+            .and_then(|owner| owner.name.as_deref());
+        let owner_email = channel
+            .itunes_owner
+            .as_ref()
+            .and_then(|owner| owner.email.as_deref());
+        let locked = channel
+            .podcast_locked
+            .as_ref()
+            .map(|locked| locked.is_locked());
+        let locked_owner = channel
+            .podcast_locked
+            .as_ref()
+            .and_then(|locked| locked.owner.as_deref());
+
+        let mut tx = self.pool.begin().await?;
+
+        let existing = sqlx::query!(
+            r#"--sql
+                SELECT
+                    podcast_id
+                FROM
+                    podcast_feed
+                WHERE
+                    feed_url = ?1 AND deleted IS NULL
+                LIMIT 1
+            "#,
+            feed,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to run query: get podcast by feed")?;
+
+        let id = match existing {
+            Some(row) => {
+                sqlx::query!(
+                    r#"--sql
+                        UPDATE podcast
+                        SET
+                            title = ?2, description = ?3, image = ?4, language = ?5, link = ?6,
+                            copyright = ?7, itunes_author = ?8, itunes_category = ?9,
+                            itunes_subcategory = ?10, itunes_owner_name = ?11,
+                            itunes_owner_email = ?12, itunes_type = ?13, itunes_summary = ?14,
+                            updated = ?15, podcast_locked = ?16, podcast_locked_owner = ?17
+                        WHERE id = ?1
+                    "#,
+                    row.podcast_id,
+                    channel.title,
+                    channel.description,
+                    image,
+                    channel.language,
+                    channel.link,
+                    channel.copyright,
+                    channel.itunes_author,
+                    category,
+                    subcategory,
+                    owner_name,
+                    owner_email,
+                    channel.itunes_type,
+                    channel.itunes_summary,
+                    now,
+                    locked,
+                    locked_owner,
+                )
+                .execute(&mut *tx)
+                .await
+                .context("Failed to run query: update podcast")?;
+
+                row.podcast_id
+            }
+            None => {
+                let row = sqlx::query!(
+                    r#"--sql
+                        INSERT INTO podcast (
+                            title, description, image, language, link, copyright, itunes_author,
+                            itunes_category, itunes_subcategory, itunes_owner_name,
+                            itunes_owner_email, itunes_type, itunes_summary, podcast_locked,
+                            podcast_locked_owner
+                        )
+                        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+                        RETURNING id
+                    "#,
+                    channel.title,
+                    channel.description,
+                    image,
+                    channel.language,
+                    channel.link,
+                    channel.copyright,
+                    channel.itunes_author,
+                    category,
+                    subcategory,
+                    owner_name,
+                    owner_email,
+                    channel.itunes_type,
+                    channel.itunes_summary,
+                    locked,
+                    locked_owner,
+                )
+                .fetch_one(&mut *tx)
+                .await
+                .context("Failed to run query: create podcast")?;
+
+                sqlx::query!(
+                    r#"--sql
+                        INSERT INTO podcast_feed (podcast_id, feed_url)
+                        VALUES (?1, ?2)
+                    "#,
+                    row.id,
+                    feed,
+                )
+                .execute(&mut *tx)
+                .await
+                .context("Failed to run query: create podcast feed")?;
+
+                row.id
+            }
+        };
+
+        let id = PodcastId(id);
+
+        namespace::store_channel(&mut *tx, id, channel).await?;
+        search::index_podcast(&mut *tx, id, channel).await?;
+
+        tx.commit().await?;
+
+        Ok(id)
+    }
+
+    /// Creates or updates the podcast's episodes, returning how many of them are new.
+    ///
+    /// Items that can't be identified (no GUID or enclosure) are skipped.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn podcast_episodes_upsert(
+        &self,
+        podcast: PodcastId,
+        items: &[Item],
+    ) -> anyhow::Result<usize> {
+        let now = OffsetDateTime::now_utc();
+        let mut created = 0;
+
+        let mut tx = self.pool.begin().await?;
+
+        for item in items {
+            let Some(guid) = item.id() else {
+                continue;
+            };
+
+            let enclosure = item.enclosure.as_ref().map(|enclosure| &enclosure.url);
+            let season = item
+                .itunes_season
+                .as_deref()
+                .and_then(|season| season.trim().parse::<i64>().ok());
+            let episode = item
+                .itunes_episode
+                .as_deref()
+                .and_then(|episode| episode.trim().parse::<i64>().ok());
+            let image = item.itunes_image.as_ref().map(|image| &image.href);
+            // Kept for clients that only know about a single transcript.
+            let transcript = item
+                .podcast_transcripts
+                .first()
+                .map(|transcript| &transcript.url);
+            let (chapters, chapters_type) = match &item.podcast_chapters {
+                Some(chapters) => (Some(&chapters.url), chapters.kind.as_ref()),
+                None => (None, None),
+            };
+            let podcast_season = item
+                .podcast_season
+                .as_ref()
+                .and_then(|season| season.number());
+            let podcast_season_name = item
+                .podcast_season
+                .as_ref()
+                .and_then(|season| season.name.as_ref());
+
+            let row = sqlx::query!(
+                r#"--sql
+                    INSERT INTO podcast_episode (
+                        podcast_id, title, enclosure, guid, published, description,
+                        itunes_season, itunes_episode, itunes_duration, itunes_image, itunes_type,
+                        itunes_subtitle, itunes_summary, podcast_transcript, podcast_chapters,
+                        podcast_chapters_type, podcast_season, podcast_season_name
+                    )
+                    VALUES (
+                        ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?15, ?16, ?17, ?18,
+                        ?19
+                    )
+                    ON CONFLICT (podcast_id, guid) DO UPDATE
+                    SET
+                        title = ?2, enclosure = ?3, published = ?5, description = ?6,
+                        itunes_season = ?7, itunes_episode = ?8, itunes_duration = ?9,
+                        itunes_image = ?10, itunes_type = ?11, itunes_subtitle = ?12,
+                        itunes_summary = ?13, podcast_transcript = ?15, podcast_chapters = ?16,
+                        podcast_chapters_type = ?17, podcast_season = ?18,
+                        podcast_season_name = ?19, updated = ?14, deleted = NULL
+                    RETURNING id as "id!: EpisodeId", updated IS NULL as "created!: bool"
+                "#,
+                podcast,
+                item.title,
+                enclosure,
+                guid,
+                item.pub_date,
+                item.description,
+                season,
+                episode,
+                item.itunes_duration,
+                image,
+                item.itunes_episode_type,
+                item.itunes_subtitle,
+                item.itunes_summary,
+                now,
+                transcript,
+                chapters,
+                chapters_type,
+                podcast_season,
+                podcast_season_name,
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .context("Failed to run query: upsert podcast episode")?;
+
+            namespace::store_item(&mut *tx, podcast, row.id, item).await?;
+            search::index_episode(&mut *tx, row.id, item).await?;
+
+            if row.created {
+                created += 1;
+            }
+        }
+
+        tx.commit().await?;
+
+        Ok(created)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+
+    use crate::{
+        database::{feed::FeedFetchResult, Database},
+        models::subscriptions::FeedStatus,
+    };
+
+    #[sqlx::test(fixtures("../../fixtures/dummy.sql"))]
+    async fn fetches(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool.clone()).await.unwrap();
+
+        assert_eq!(db.feed_fetches_enqueue(3600).await.unwrap(), 4);
+        assert_eq!(db.feed_fetches_enqueue(3600).await.unwrap(), 0);
+        assert_eq!(db.feed_fetches_get_due(10).await.unwrap().len(), 4);
+
+        // Feeds that were replaced aren't fetched any more.
+        sqlx::query("UPDATE subscription_feeds SET deleted = DATETIME('now') WHERE feed = ?1")
+            .bind(Database::SUBSCRIPTION_2_FEED_OLD)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(db.feed_fetches_get_due(10).await.unwrap().len(), 3);
+        assert!(db
+            .feed_fetch_get(Database::SUBSCRIPTION_2_FEED_OLD)
+            .await
+            .unwrap()
+            .is_none());
+
+        let fetch = db
+            .feed_fetch_get(Database::SUBSCRIPTION_1_FEED)
+            .await
+            .unwrap()
+            .unwrap();
+        let later = OffsetDateTime::now_utc() + time::Duration::hours(1);
+
+        let success = FeedFetchResult {
+            status: FeedStatus::Success,
+            http_code: Some(200),
+            error: None,
+            etag: Some("\"v1\""),
+            last_modified: None,
+        };
+        assert!(!db
+            .feed_fetch_record(fetch.id, &success, 1800, later, 2)
+            .await
+            .unwrap());
+
+        let fetch = db
+            .feed_fetch_get(Database::SUBSCRIPTION_1_FEED)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fetch.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(fetch.interval, 1800);
+        assert!(fetch.last_fetch.is_some());
+        assert_eq!(db.feed_fetches_get_due(10).await.unwrap().len(), 2);
+
+        // Failures keep the validators and suspend the feed once there are too many in a row.
+        let failure = FeedFetchResult {
+            status: FeedStatus::Failure,
+            http_code: Some(500),
+            error: Some("Internal Server Error"),
+            etag: None,
+            last_modified: None,
+        };
+        assert!(!db
+            .feed_fetch_record(fetch.id, &failure, 1800, later, 2)
+            .await
+            .unwrap());
+        assert!(db
+            .feed_fetch_record(fetch.id, &failure, 1800, later, 2)
+            .await
+            .unwrap());
+
+        assert!(db
+            .feed_fetch_get(Database::SUBSCRIPTION_1_FEED)
+            .await
+            .unwrap()
+            .is_none());
+        let health = db
+            .feed_health_get(Database::SUBSCRIPTION_1_FEED)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(health.consecutive_failures, 2);
+        assert!(health.suspended.is_some());
+
+        assert!(db
+            .feed_fetch_resume(Database::SUBSCRIPTION_1_FEED)
+            .await
+            .unwrap());
+        let fetch = db
+            .feed_fetch_get(Database::SUBSCRIPTION_1_FEED)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fetch.consecutive_failures, 0);
+        assert_eq!(fetch.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(db.feed_fetches_get_due(10).await.unwrap().len(), 3);
+
+        // A feed that wasn't modified keeps its validators, one that changed without sending any
+        // drops the stale ones.
+        let not_modified = FeedFetchResult {
+            status: FeedStatus::NotModified,
+            http_code: Some(304),
+            error: None,
+            etag: None,
+            last_modified: None,
+        };
+        db.feed_fetch_record(fetch.id, &not_modified, 1800, later, 2)
+            .await
+            .unwrap();
+        let fetch = db
+            .feed_fetch_get(Database::SUBSCRIPTION_1_FEED)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fetch.etag.as_deref(), Some("\"v1\""));
+
+        let success = FeedFetchResult {
+            status: FeedStatus::Success,
+            http_code: Some(200),
+            error: None,
+            etag: None,
+            last_modified: Some("Wed, 21 Oct 2015 07:28:00 GMT"),
+        };
+        db.feed_fetch_record(fetch.id, &success, 1800, later, 2)
+            .await
+            .unwrap();
+        let fetch = db
+            .feed_fetch_get(Database::SUBSCRIPTION_1_FEED)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fetch.etag, None);
+        assert_eq!(
+            fetch.last_modified.as_deref(),
+            Some("Wed, 21 Oct 2015 07:28:00 GMT")
+        );
+    }
+}
@@ -0,0 +1,540 @@
+use anyhow::Context as _;
+use sqlx::SqliteConnection;
+use time::OffsetDateTime;
+
+use crate::{
+    database::{
+        export::ExportId,
+        podcast::{limit_offset, RowPage},
+        subscription::SubscriptionId,
+        tasks::DeletionId,
+        user::UserId,
+        Database,
+    },
+    models::jobs::{JobKind, JobStatus},
+};
+
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, sqlx::Type)]
+#[serde(transparent)]
+#[sqlx(transparent)]
+pub struct JobId(pub i64);
+
+impl From<i64> for JobId {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+/// A piece of background work, stored as JSON until a worker of its kind picks it up.
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Job {
+    Deletion {
+        deletion_id: DeletionId,
+    },
+    Identification {
+        subscription_id: SubscriptionId,
+    },
+    Refresh {
+        feed: String,
+    },
+    Email {
+        to: String,
+        subject: String,
+        body: String,
+    },
+    Export {
+        export_id: ExportId,
+    },
+    AccountDeletion {
+        user_id: UserId,
+    },
+}
+
+impl Job {
+    pub fn kind(&self) -> JobKind {
+        match self {
+            Job::Deletion { .. } => JobKind::Deletion,
+            Job::Identification { .. } => JobKind::Identification,
+            Job::Refresh { .. } => JobKind::Refresh,
+            Job::Email { .. } => JobKind::Email,
+            Job::Export { .. } => JobKind::Export,
+            Job::AccountDeletion { .. } => JobKind::AccountDeletion,
+        }
+    }
+
+    /// Identifies the work the job does, a job isn't queued while another one with the same key
+    /// is queued or running.
+    fn key(&self) -> Option<String> {
+        match self {
+            Job::Deletion { deletion_id } => Some(format!("deletion:{}", deletion_id.0)),
+            Job::Identification { subscription_id } => {
+                Some(format!("identification:{}", subscription_id.0))
+            }
+            Job::Refresh { feed } => Some(format!("refresh:{}", feed)),
+            Job::Email { .. } => None,
+            Job::Export { export_id } => Some(format!("export:{}", export_id.0)),
+            Job::AccountDeletion { user_id } => Some(format!("account_deletion:{}", user_id.0)),
+        }
+    }
+
+    fn max_attempts(&self) -> i64 {
+        match self {
+            Job::Deletion { .. } => 5,
+            Job::Identification { .. } => 10,
+            // Failing feeds are backed off and suspended by the refresh itself.
+            Job::Refresh { .. } => 3,
+            Job::Email { .. } => 5,
+            Job::Export { .. } => 3,
+            Job::AccountDeletion { .. } => 5,
+        }
+    }
+}
+
+pub struct RowJobCount {
+    pub kind: JobKind,
+    pub status: JobStatus,
+    pub total: i64,
+}
+
+pub struct RowJob {
+    pub id: JobId,
+    pub kind: JobKind,
+    pub payload: String,
+    pub status: JobStatus,
+    /// How many times the job was leased, including the current attempt.
+    pub attempts: i64,
+    pub max_attempts: i64,
+    pub run_after: OffsetDateTime,
+    pub leased_until: Option<OffsetDateTime>,
+    pub last_error: Option<String>,
+    pub created: OffsetDateTime,
+    pub updated: Option<OffsetDateTime>,
+}
+
+impl RowJob {
+    pub fn job(&self) -> anyhow::Result<Job> {
+        serde_json::from_str(&self.payload).context("Failed to deserialize job payload")
+    }
+}
+
+/// Queues the job to run once `run_after` has passed, returns `false` if the same work is
+/// already queued.
+pub(in crate::database) async fn enqueue(
+    conn: &mut SqliteConnection,
+    job: &Job,
+    run_after: OffsetDateTime,
+) -> anyhow::Result<bool> {
+    let kind = job.kind();
+    let payload = serde_json::to_string(job).context("Failed to serialize job payload")?;
+    let key = job.key();
+    let max_attempts = job.max_attempts();
+
+    let result = sqlx::query!(
+        r#"--sql
+            INSERT INTO jobs (kind, payload, key, max_attempts, run_after)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT DO NOTHING
+        "#,
+        kind,
+        payload,
+        key,
+        max_attempts,
+        run_after,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: create job")?;
+
+    Ok(result.rows_affected() != 0)
+}
+
+/// Drops the job if it hasn't started yet.
+pub(in crate::database) async fn cancel(
+    conn: &mut SqliteConnection,
+    job: &Job,
+) -> anyhow::Result<()> {
+    let key = job.key();
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM jobs
+            WHERE key = ?1 AND status = ?2
+        "#,
+        key,
+        JobStatus::Queued,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: cancel job")?;
+
+    Ok(())
+}
+
+impl Database {
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn job_enqueue(&self, job: &Job, run_after: OffsetDateTime) -> anyhow::Result<bool> {
+        let mut conn = self.pool.acquire().await?;
+
+        enqueue(&mut conn, job, run_after).await
+    }
+
+    /// Hands the next job of the kind to a worker for `lease`, after which it is assumed the
+    /// worker died and the job is handed to another one.
+    ///
+    /// Every lease counts as an attempt, the worker that holds the lease is the one that knows
+    /// the current attempt.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn job_lease(
+        &self,
+        kind: JobKind,
+        lease: time::Duration,
+    ) -> anyhow::Result<Option<RowJob>> {
+        let now = OffsetDateTime::now_utc();
+        let leased_until = now + lease;
+
+        sqlx::query_as!(
+            RowJob,
+            r#"--sql
+                UPDATE jobs
+                SET status = ?2, attempts = attempts + 1, leased_until = ?5, updated = ?4
+                WHERE id = (
+                    SELECT id
+                    FROM jobs
+                    WHERE
+                        kind = ?1
+                        AND NOT EXISTS (SELECT 1 FROM job_pauses WHERE kind = ?1)
+                        AND (
+                            (status = ?3 AND JULIANDAY(run_after) <= JULIANDAY(?4))
+                            OR (status = ?2 AND JULIANDAY(leased_until) <= JULIANDAY(?4))
+                        )
+                    ORDER BY JULIANDAY(run_after) ASC, id ASC
+                    LIMIT 1
+                )
+                RETURNING
+                    id as "id!", kind as "kind!: JobKind", payload as "payload!",
+                    status as "status!: JobStatus", attempts as "attempts!",
+                    max_attempts as "max_attempts!", run_after as "run_after!: OffsetDateTime",
+                    leased_until as "leased_until?: OffsetDateTime", last_error as "last_error?",
+                    created as "created!: OffsetDateTime", updated as "updated?: OffsetDateTime"
+            "#,
+            kind,
+            JobStatus::Running,
+            JobStatus::Queued,
+            now,
+            leased_until,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: lease job")
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn job_complete(&self, job: &RowJob) -> anyhow::Result<()> {
+        self.job_finish(job, JobStatus::Done, None, job.run_after)
+            .await
+            .context("Failed to complete job")
+    }
+
+    /// Queues the job again after a failed attempt.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn job_retry(
+        &self,
+        job: &RowJob,
+        error: &str,
+        run_after: OffsetDateTime,
+    ) -> anyhow::Result<()> {
+        self.job_finish(job, JobStatus::Queued, Some(error), run_after)
+            .await
+            .context("Failed to retry job")
+    }
+
+    /// Gives up on the job, it stays around for operators to look at until it is pruned.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn job_dead(&self, job: &RowJob, error: &str) -> anyhow::Result<()> {
+        self.job_finish(job, JobStatus::Dead, Some(error), job.run_after)
+            .await
+            .context("Failed to dead-letter job")
+    }
+
+    /// Ends the worker's lease, unless it ran out and the job was handed to another worker.
+    async fn job_finish(
+        &self,
+        job: &RowJob,
+        status: JobStatus,
+        error: Option<&str>,
+        run_after: OffsetDateTime,
+    ) -> anyhow::Result<()> {
+        let now = OffsetDateTime::now_utc();
+
+        let result = sqlx::query!(
+            r#"--sql
+                UPDATE jobs
+                SET
+                    status = ?3, last_error = COALESCE(?4, last_error), run_after = ?5,
+                    leased_until = NULL, updated = ?6
+                WHERE id = ?1 AND attempts = ?2 AND status = ?7
+            "#,
+            job.id,
+            job.attempts,
+            status,
+            error,
+            run_after,
+            now,
+            JobStatus::Running,
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to run query: finish job")?;
+
+        if result.rows_affected() == 0 {
+            tracing::warn!(job_id = job.id.0, "Job lease ran out before it finished");
+        }
+
+        Ok(())
+    }
+
+    /// Removes the jobs that finished, failed for good or were cancelled before `before`,
+    /// returning how many there were.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn jobs_prune(&self, before: OffsetDateTime) -> anyhow::Result<u64> {
+        let result = sqlx::query!(
+            r#"--sql
+                DELETE FROM jobs
+                WHERE
+                    status IN (?1, ?2, ?3)
+                    AND JULIANDAY(COALESCE(updated, created)) < JULIANDAY(?4)
+            "#,
+            JobStatus::Done,
+            JobStatus::Dead,
+            JobStatus::Cancelled,
+            before,
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to run query: prune jobs")?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Lists the jobs, newest first, optionally only the ones of a kind or in a status.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn jobs_list(
+        &self,
+        kind: Option<JobKind>,
+        status: Option<JobStatus>,
+        page: Option<i64>,
+        per_page: Option<i64>,
+    ) -> anyhow::Result<RowPage<RowJob>> {
+        let (page, per_page, offset) = limit_offset(page, per_page);
+
+        let total = sqlx::query!(
+            r#"--sql
+                SELECT
+                    COUNT(*) as "total!: i64"
+                FROM
+                    jobs
+                WHERE
+                    (?1 IS NULL OR kind = ?1) AND (?2 IS NULL OR status = ?2)
+            "#,
+            kind,
+            status,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to run query: count jobs")?
+        .total;
+
+        let rows = sqlx::query_as!(
+            RowJob,
+            r#"--sql
+                SELECT
+                    id, kind as "kind: JobKind", payload, status as "status: JobStatus",
+                    attempts, max_attempts, run_after, leased_until, last_error, created,
+                    updated
+                FROM
+                    jobs
+                WHERE
+                    (?1 IS NULL OR kind = ?1) AND (?2 IS NULL OR status = ?2)
+                ORDER BY id DESC
+                LIMIT ?3 OFFSET ?4
+            "#,
+            kind,
+            status,
+            per_page,
+            offset,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to run query: list jobs")?;
+
+        Ok(RowPage {
+            total,
+            page,
+            per_page,
+            rows,
+        })
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn job_get(&self, id: JobId) -> anyhow::Result<Option<RowJob>> {
+        sqlx::query_as!(
+            RowJob,
+            r#"--sql
+                SELECT
+                    id, kind as "kind: JobKind", payload, status as "status: JobStatus",
+                    attempts, max_attempts, run_after, leased_until, last_error, created,
+                    updated
+                FROM
+                    jobs
+                WHERE
+                    id = ?1
+            "#,
+            id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get job")
+    }
+
+    /// Queues a dead job again with all its attempts, to run straight away.
+    ///
+    /// Returns `None` if the job isn't dead or the same work has been queued since.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn job_requeue(&self, id: JobId) -> anyhow::Result<Option<RowJob>> {
+        let now = OffsetDateTime::now_utc();
+
+        sqlx::query_as!(
+            RowJob,
+            r#"--sql
+                UPDATE OR IGNORE jobs
+                SET status = ?2, attempts = 0, run_after = ?3, leased_until = NULL, updated = ?3
+                WHERE id = ?1 AND status = ?4
+                RETURNING
+                    id as "id!", kind as "kind!: JobKind", payload as "payload!",
+                    status as "status!: JobStatus", attempts as "attempts!",
+                    max_attempts as "max_attempts!", run_after as "run_after!: OffsetDateTime",
+                    leased_until as "leased_until?: OffsetDateTime", last_error as "last_error?",
+                    created as "created!: OffsetDateTime", updated as "updated?: OffsetDateTime"
+            "#,
+            id,
+            JobStatus::Queued,
+            now,
+            JobStatus::Dead,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: requeue job")
+    }
+
+    /// Drops a job that is waiting to run, returns `None` if it isn't queued.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn job_cancel(&self, id: JobId) -> anyhow::Result<Option<RowJob>> {
+        let now = OffsetDateTime::now_utc();
+
+        sqlx::query_as!(
+            RowJob,
+            r#"--sql
+                UPDATE jobs
+                SET status = ?2, updated = ?3
+                WHERE id = ?1 AND status = ?4
+                RETURNING
+                    id as "id!", kind as "kind!: JobKind", payload as "payload!",
+                    status as "status!: JobStatus", attempts as "attempts!",
+                    max_attempts as "max_attempts!", run_after as "run_after!: OffsetDateTime",
+                    leased_until as "leased_until?: OffsetDateTime", last_error as "last_error?",
+                    created as "created!: OffsetDateTime", updated as "updated?: OffsetDateTime"
+            "#,
+            id,
+            JobStatus::Cancelled,
+            now,
+            JobStatus::Queued,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: cancel job")
+    }
+
+    /// Counts the jobs of each kind in each status.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn jobs_count(&self) -> anyhow::Result<Vec<RowJobCount>> {
+        sqlx::query_as!(
+            RowJobCount,
+            r#"--sql
+                SELECT
+                    kind as "kind: JobKind", status as "status: JobStatus",
+                    COUNT(*) as "total!: i64"
+                FROM
+                    jobs
+                GROUP BY kind, status
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: count jobs by kind")
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn job_kinds_paused(&self) -> anyhow::Result<Vec<JobKind>> {
+        let rows = sqlx::query!(
+            r#"--sql
+                SELECT kind as "kind: JobKind"
+                FROM job_pauses
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to run query: get paused job kinds")?;
+
+        Ok(rows.into_iter().map(|row| row.kind).collect())
+    }
+
+    /// Stops the workers of the kind from starting new jobs, or lets them start again.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn job_kind_set_paused(&self, kind: JobKind, paused: bool) -> anyhow::Result<()> {
+        if paused {
+            sqlx::query!(
+                r#"--sql
+                    INSERT INTO job_pauses (kind)
+                    VALUES (?1)
+                    ON CONFLICT (kind) DO NOTHING
+                "#,
+                kind,
+            )
+            .execute(&self.pool)
+            .await
+            .context("Failed to run query: pause job kind")?;
+        } else {
+            sqlx::query!(
+                r#"--sql
+                    DELETE FROM job_pauses
+                    WHERE kind = ?1
+                "#,
+                kind,
+            )
+            .execute(&self.pool)
+            .await
+            .context("Failed to run query: resume job kind")?;
+        }
+
+        Ok(())
+    }
+}
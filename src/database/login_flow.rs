@@ -0,0 +1,195 @@
+use anyhow::Context as _;
+use data_encoding::BASE64URL_NOPAD;
+use rand::{rngs::OsRng, RngCore as _};
+use time::OffsetDateTime;
+
+use crate::database::{session, user::User, Database};
+
+/// How long a client has to finish the Nextcloud login flow.
+const LOGIN_FLOW_LIFETIME: time::Duration = time::Duration::minutes(20);
+
+/// How long the app password handed out by the login flow stays valid.
+const APP_PASSWORD_LIFETIME: time::Duration = time::Duration::days(365);
+
+pub struct RowLoginFlow {
+    pub id: i64,
+    pub poll_token: String,
+    pub login_token: String,
+    pub expires: OffsetDateTime,
+}
+
+pub struct LoginCredentials {
+    pub username: String,
+    pub app_password: String,
+}
+
+fn token() -> String {
+    let mut bytes = [0; 48];
+    OsRng.fill_bytes(&mut bytes);
+
+    BASE64URL_NOPAD.encode(&bytes)
+}
+
+impl Database {
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn login_flow_create(&self) -> anyhow::Result<RowLoginFlow> {
+        let expires = OffsetDateTime::now_utc() + LOGIN_FLOW_LIFETIME;
+        let poll_token = token();
+        let login_token = token();
+
+        sqlx::query_as!(
+            RowLoginFlow,
+            r#"--sql
+                INSERT INTO login_flows (poll_token, login_token, expires)
+                VALUES (?1, ?2, ?3)
+                RETURNING id, poll_token, login_token, expires
+            "#,
+            poll_token,
+            login_token,
+            expires,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: create login flow")
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn login_flow_get_by_login_token(
+        &self,
+        login_token: &str,
+    ) -> anyhow::Result<Option<RowLoginFlow>> {
+        let now = OffsetDateTime::now_utc();
+
+        sqlx::query_as!(
+            RowLoginFlow,
+            r#"--sql
+                SELECT
+                    id, poll_token, login_token, expires
+                FROM
+                    login_flows
+                WHERE
+                    login_token = ?1 AND user_id IS NULL AND JULIANDAY(expires) > JULIANDAY(?2)
+            "#,
+            login_token,
+            now,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get login flow")
+    }
+
+    /// Grants the login flow access to the user's account by creating an app password for it.
+    ///
+    /// The flow is claimed before the app password is created, in the same transaction, so a flow
+    /// that was already granted never leaves an unused app password behind.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn login_flow_grant(&self, login_token: &str, user: &User) -> anyhow::Result<bool> {
+        let now = OffsetDateTime::now_utc();
+        let expires = now + APP_PASSWORD_LIFETIME;
+        let app_password = session::token();
+
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query!(
+            r#"--sql
+                UPDATE login_flows
+                SET user_id = ?2, app_password = ?3
+                WHERE
+                    login_token = ?1
+                    AND user_id IS NULL
+                    AND JULIANDAY(expires) > JULIANDAY(?4)
+            "#,
+            login_token,
+            user.id,
+            app_password,
+            now,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: grant login flow")?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            r#"--sql
+                INSERT INTO user_sessions (user_id, token, expires)
+                VALUES (?1, ?2, ?3)
+            "#,
+            user.id,
+            app_password,
+            expires,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: create app password")?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Hands out the credentials of a granted login flow, this can only happen once.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn login_flow_poll(
+        &self,
+        poll_token: &str,
+    ) -> anyhow::Result<Option<LoginCredentials>> {
+        let now = OffsetDateTime::now_utc();
+
+        sqlx::query_as!(
+            LoginCredentials,
+            r#"--sql
+                DELETE FROM login_flows
+                WHERE
+                    poll_token = ?1
+                    AND app_password IS NOT NULL
+                    AND JULIANDAY(expires) > JULIANDAY(?2)
+                RETURNING
+                    (SELECT username FROM users WHERE id = user_id) as "username!: String",
+                    app_password as "app_password!: String"
+            "#,
+            poll_token,
+            now,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: poll login flow")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::Database;
+
+    #[sqlx::test(fixtures("../../fixtures/dummy.sql"))]
+    async fn grant_once(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool).await.unwrap();
+        let user = db.user_get_by_id(Database::USER_ID).await.unwrap().unwrap();
+        let flow = db.login_flow_create().await.unwrap();
+
+        assert!(db.login_flow_grant(&flow.login_token, &user).await.unwrap());
+        assert!(!db.login_flow_grant(&flow.login_token, &user).await.unwrap());
+
+        // The fixture's session and the one app password.
+        assert_eq!(db.sessions_get_all(&user).await.unwrap().len(), 2);
+
+        let credentials = db.login_flow_poll(&flow.poll_token).await.unwrap().unwrap();
+        assert_eq!(credentials.username, "example");
+        assert!(db
+            .session_get_by_token(&credentials.app_password)
+            .await
+            .unwrap()
+            .is_some());
+    }
+}
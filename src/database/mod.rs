@@ -1,9 +1,34 @@
 pub mod subscription;
 pub mod tasks;
 
+pub mod backup;
+pub mod device;
+pub mod episode;
+pub mod export;
+pub mod feed;
+pub mod jobs;
+pub mod login_flow;
+pub mod namespace;
 pub mod orm;
+pub mod podcast;
+pub mod search;
 pub mod session;
+pub mod tag;
 pub mod user;
+pub mod websub;
+
+use std::{collections::HashSet, str::FromStr as _, time::Duration};
+
+use anyhow::Context as _;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
+
+use crate::config::{DatabaseConfig, JournalMode, Synchronous};
+
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+}
 
 #[derive(Clone)]
 pub struct Database {
@@ -11,18 +36,108 @@ pub struct Database {
 }
 
 impl Database {
-    pub async fn new() -> anyhow::Result<Self> {
-        let pool = sqlx::SqlitePool::connect("sqlite://pod-sync.db").await?;
+    /// Opens the database and brings its schema up to date.
+    pub async fn new(cfg: &DatabaseConfig) -> anyhow::Result<Self> {
+        let db = Self::open(cfg).await?;
 
-        sqlx::migrate!("./migrations").run(&pool).await?;
+        db.migrate().await?;
 
-        sqlx::query!("PRAGMA foreign_keys = ON;")
-            .execute(&pool)
-            .await?;
+        Ok(db)
+    }
+
+    /// Opens the database without touching its schema.
+    pub async fn open(cfg: &DatabaseConfig) -> anyhow::Result<Self> {
+        let pool = SqlitePoolOptions::new()
+            .min_connections(cfg.min_connections)
+            .max_connections(cfg.max_connections)
+            .acquire_timeout(Duration::from_secs(cfg.acquire_timeout))
+            .connect_with(Self::connect_options(cfg)?)
+            .await
+            .with_context(|| format!("Failed to open database {}", cfg.url))?;
 
         Ok(Self { pool })
     }
 
+    /// Runs the migrations that haven't been applied yet.
+    pub async fn migrate(&self) -> anyhow::Result<()> {
+        sqlx::migrate!("./migrations")
+            .run(&self.pool)
+            .await
+            .context("Failed to run migrations")
+    }
+
+    /// Lists every migration this version knows about, oldest first, and whether it has been
+    /// applied.
+    pub async fn migrations_status(&self) -> anyhow::Result<Vec<MigrationStatus>> {
+        let table: Option<String> = sqlx::query_scalar(
+            r#"--sql
+                SELECT name
+                FROM sqlite_master
+                WHERE type = 'table' AND name = '_sqlx_migrations'
+            "#,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to run query: find migrations table")?;
+
+        // A new database has no migrations table until the first run.
+        let applied: HashSet<i64> = match table {
+            Some(_) => sqlx::query_scalar(
+                r#"--sql
+                    SELECT version
+                    FROM _sqlx_migrations
+                    WHERE success = TRUE
+                "#,
+            )
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to run query: get applied migrations")?
+            .into_iter()
+            .collect(),
+            None => HashSet::new(),
+        };
+
+        Ok(sqlx::migrate!("./migrations")
+            .iter()
+            .map(|migration| MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                applied: applied.contains(&migration.version),
+            })
+            .collect())
+    }
+
+    /// The options every pooled connection is opened with, pragmas included so each connection
+    /// gets them rather than just the first.
+    fn connect_options(cfg: &DatabaseConfig) -> anyhow::Result<SqliteConnectOptions> {
+        let journal_mode = match cfg.journal_mode {
+            JournalMode::Delete => SqliteJournalMode::Delete,
+            JournalMode::Truncate => SqliteJournalMode::Truncate,
+            JournalMode::Persist => SqliteJournalMode::Persist,
+            JournalMode::Memory => SqliteJournalMode::Memory,
+            JournalMode::Wal => SqliteJournalMode::Wal,
+            JournalMode::Off => SqliteJournalMode::Off,
+        };
+        let synchronous = match cfg.synchronous {
+            Synchronous::Off => SqliteSynchronous::Off,
+            Synchronous::Normal => SqliteSynchronous::Normal,
+            Synchronous::Full => SqliteSynchronous::Full,
+            Synchronous::Extra => SqliteSynchronous::Extra,
+        };
+
+        let options = SqliteConnectOptions::from_str(&cfg.url)
+            .with_context(|| format!("Invalid database url {}", cfg.url))?
+            .create_if_missing(true)
+            .foreign_keys(true)
+            .journal_mode(journal_mode)
+            .synchronous(synchronous)
+            .busy_timeout(Duration::from_secs(cfg.busy_timeout));
+
+        Ok(cfg.pragmas.iter().fold(options, |options, (key, value)| {
+            options.pragma(key.clone(), value.clone())
+        }))
+    }
+
     pub async fn shutdown(&self) -> anyhow::Result<()> {
         self.pool.close().await;
 
@@ -77,3 +192,79 @@ impl Database {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{config::DatabaseConfig, database::Database};
+
+    #[tokio::test]
+    async fn connect_options() {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let dir = std::env::temp_dir().join(format!("pod-sync-{:016x}", rand::random::<u64>()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let cfg = DatabaseConfig {
+            url: format!("sqlite://{}", dir.join("pod-sync.db").display()),
+            min_connections: 2,
+            max_connections: 2,
+            pragmas: BTreeMap::from([("cache_size".to_string(), "-4000".to_string())]),
+            ..DatabaseConfig::default()
+        };
+        let db = Database::new(&cfg).await.unwrap();
+
+        // Every connection is set up, not just the first one handed out.
+        let mut first = db.pool.acquire().await.unwrap();
+        let mut second = db.pool.acquire().await.unwrap();
+        for conn in [&mut first, &mut second] {
+            let foreign_keys: i64 = sqlx::query_scalar("PRAGMA foreign_keys")
+                .fetch_one(&mut **conn)
+                .await
+                .unwrap();
+            let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+                .fetch_one(&mut **conn)
+                .await
+                .unwrap();
+            let cache_size: i64 = sqlx::query_scalar("PRAGMA cache_size")
+                .fetch_one(&mut **conn)
+                .await
+                .unwrap();
+
+            assert_eq!(foreign_keys, 1);
+            assert_eq!(journal_mode, "wal");
+            assert_eq!(cache_size, -4000);
+        }
+        drop((first, second));
+
+        db.shutdown().await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn migrations_status() {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let dir = std::env::temp_dir().join(format!("pod-sync-{:016x}", rand::random::<u64>()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let cfg = DatabaseConfig {
+            url: format!("sqlite://{}", dir.join("pod-sync.db").display()),
+            ..DatabaseConfig::default()
+        };
+        let db = Database::open(&cfg).await.unwrap();
+
+        let pending = db.migrations_status().await.unwrap();
+        assert!(!pending.is_empty());
+        assert!(pending.iter().all(|migration| !migration.applied));
+
+        db.migrate().await.unwrap();
+
+        let applied = db.migrations_status().await.unwrap();
+        assert_eq!(applied.len(), pending.len());
+        assert!(applied.iter().all(|migration| migration.applied));
+
+        db.shutdown().await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,387 @@
+//! Storage for the Podcasting 2.0 (`podcast:`) namespace.
+//!
+//! Everything here is replaced whenever the feed is stored, the feed is the source of truth.
+
+use anyhow::Context as _;
+use sqlx::SqliteConnection;
+
+use crate::{
+    database::{
+        podcast::{EpisodeId, PodcastId},
+        Database,
+    },
+    models::rss::{Channel, Item, PodcastPerson, PodcastValue},
+};
+
+pub struct RowTranscript {
+    pub url: String,
+    pub kind: String,
+    pub language: Option<String>,
+    pub rel: Option<String>,
+}
+
+pub struct RowFunding {
+    pub url: String,
+    pub message: Option<String>,
+}
+
+pub struct RowPerson {
+    pub name: String,
+    pub role: Option<String>,
+    pub group_name: Option<String>,
+    pub img: Option<String>,
+    pub href: Option<String>,
+}
+
+pub struct RowValue {
+    pub id: i64,
+    pub kind: String,
+    pub method: String,
+    pub suggested: Option<String>,
+}
+
+pub struct RowValueRecipient {
+    pub name: Option<String>,
+    pub kind: String,
+    pub address: String,
+    pub split: i64,
+    pub fee: bool,
+    pub custom_key: Option<String>,
+    pub custom_value: Option<String>,
+}
+
+/// Replaces the podcast wide parts of the namespace.
+pub(super) async fn store_channel(
+    conn: &mut SqliteConnection,
+    podcast: PodcastId,
+    channel: &Channel,
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM podcast_fundings
+            WHERE podcast_id = ?1
+        "#,
+        podcast,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete podcast fundings")?;
+
+    for funding in &channel.podcast_fundings {
+        let message = funding.message.as_deref().map(str::trim);
+
+        sqlx::query!(
+            r#"--sql
+                INSERT INTO podcast_fundings (podcast_id, url, message)
+                VALUES (?1, ?2, ?3)
+            "#,
+            podcast,
+            funding.url,
+            message,
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to run query: create podcast funding")?;
+    }
+
+    store_persons(conn, podcast, None, &channel.podcast_persons).await?;
+    store_value(conn, podcast, None, channel.podcast_value.as_ref()).await?;
+
+    Ok(())
+}
+
+/// Replaces the episode's parts of the namespace.
+pub(super) async fn store_item(
+    conn: &mut SqliteConnection,
+    podcast: PodcastId,
+    episode: EpisodeId,
+    item: &Item,
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM podcast_transcripts
+            WHERE episode_id = ?1
+        "#,
+        episode,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete episode transcripts")?;
+
+    for transcript in &item.podcast_transcripts {
+        sqlx::query!(
+            r#"--sql
+                INSERT INTO podcast_transcripts (episode_id, url, kind, language, rel)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+            episode,
+            transcript.url,
+            transcript.kind,
+            transcript.language,
+            transcript.rel,
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to run query: create episode transcript")?;
+    }
+
+    store_persons(conn, podcast, Some(episode), &item.podcast_persons).await?;
+    store_value(conn, podcast, Some(episode), item.podcast_value.as_ref()).await?;
+
+    Ok(())
+}
+
+async fn store_persons(
+    conn: &mut SqliteConnection,
+    podcast: PodcastId,
+    episode: Option<EpisodeId>,
+    persons: &[PodcastPerson],
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM podcast_persons
+            WHERE podcast_id = ?1 AND episode_id IS ?2
+        "#,
+        podcast,
+        episode,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete podcast persons")?;
+
+    for person in persons {
+        let name = person.name.trim();
+        if name.is_empty() {
+            continue;
+        }
+
+        sqlx::query!(
+            r#"--sql
+                INSERT INTO podcast_persons (podcast_id, episode_id, name, role, group_name, img, href)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+            podcast,
+            episode,
+            name,
+            person.role,
+            person.group,
+            person.img,
+            person.href,
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to run query: create podcast person")?;
+    }
+
+    Ok(())
+}
+
+async fn store_value(
+    conn: &mut SqliteConnection,
+    podcast: PodcastId,
+    episode: Option<EpisodeId>,
+    value: Option<&PodcastValue>,
+) -> anyhow::Result<()> {
+    // Recipients are removed by hand, foreign keys may not be enforced.
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM podcast_value_recipients
+            WHERE value_id IN (
+                SELECT id
+                FROM podcast_values
+                WHERE podcast_id = ?1 AND episode_id IS ?2
+            )
+        "#,
+        podcast,
+        episode,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete podcast value recipients")?;
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM podcast_values
+            WHERE podcast_id = ?1 AND episode_id IS ?2
+        "#,
+        podcast,
+        episode,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete podcast values")?;
+
+    let Some(value) = value else {
+        return Ok(());
+    };
+
+    let row = sqlx::query!(
+        r#"--sql
+            INSERT INTO podcast_values (podcast_id, episode_id, kind, method, suggested)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            RETURNING id
+        "#,
+        podcast,
+        episode,
+        value.kind,
+        value.method,
+        value.suggested,
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .context("Failed to run query: create podcast value")?;
+
+    for recipient in &value.recipients {
+        let split = recipient.split();
+        let fee = recipient.is_fee();
+
+        sqlx::query!(
+            r#"--sql
+                INSERT INTO podcast_value_recipients (
+                    value_id, name, kind, address, split, fee, custom_key, custom_value
+                )
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#,
+            row.id,
+            recipient.name,
+            recipient.kind,
+            recipient.address,
+            split,
+            fee,
+            recipient.custom_key,
+            recipient.custom_value,
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to run query: create podcast value recipient")?;
+    }
+
+    Ok(())
+}
+
+impl Database {
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn podcast_fundings_get(&self, id: PodcastId) -> anyhow::Result<Vec<RowFunding>> {
+        sqlx::query_as!(
+            RowFunding,
+            r#"--sql
+                SELECT
+                    url, message
+                FROM
+                    podcast_fundings
+                WHERE
+                    podcast_id = ?1
+                ORDER BY id ASC
+            "#,
+            id,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get podcast fundings")
+    }
+
+    /// Returns the persons of the podcast, or of one of its episodes.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn podcast_persons_get(
+        &self,
+        id: PodcastId,
+        episode: Option<EpisodeId>,
+    ) -> anyhow::Result<Vec<RowPerson>> {
+        sqlx::query_as!(
+            RowPerson,
+            r#"--sql
+                SELECT
+                    name, role, group_name, img, href
+                FROM
+                    podcast_persons
+                WHERE
+                    podcast_id = ?1 AND episode_id IS ?2
+                ORDER BY id ASC
+            "#,
+            id,
+            episode,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get podcast persons")
+    }
+
+    /// Returns the value block of the podcast, or of one of its episodes, with its recipients.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn podcast_value_get(
+        &self,
+        id: PodcastId,
+        episode: Option<EpisodeId>,
+    ) -> anyhow::Result<Option<(RowValue, Vec<RowValueRecipient>)>> {
+        let value = sqlx::query_as!(
+            RowValue,
+            r#"--sql
+                SELECT
+                    id, kind, method, suggested
+                FROM
+                    podcast_values
+                WHERE
+                    podcast_id = ?1 AND episode_id IS ?2
+                LIMIT 1
+            "#,
+            id,
+            episode,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to run query: get podcast value")?;
+
+        let Some(value) = value else {
+            return Ok(None);
+        };
+
+        let recipients = sqlx::query_as!(
+            RowValueRecipient,
+            r#"--sql
+                SELECT
+                    name, kind, address, split, fee as "fee: bool", custom_key, custom_value
+                FROM
+                    podcast_value_recipients
+                WHERE
+                    value_id = ?1
+                ORDER BY id ASC
+            "#,
+            value.id,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to run query: get podcast value recipients")?;
+
+        Ok(Some((value, recipients)))
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn episode_transcripts_get(
+        &self,
+        episode: EpisodeId,
+    ) -> anyhow::Result<Vec<RowTranscript>> {
+        sqlx::query_as!(
+            RowTranscript,
+            r#"--sql
+                SELECT
+                    url, kind, language, rel
+                FROM
+                    podcast_transcripts
+                WHERE
+                    episode_id = ?1
+                ORDER BY id ASC
+            "#,
+            episode,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get episode transcripts")
+    }
+}
@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+
+use anyhow::Context as _;
+use time::OffsetDateTime;
+use url::Url;
+
+use crate::database::{user::User, Database};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, sqlx::Type)]
+#[sqlx(transparent)]
+pub struct PodcastId(pub i64);
+
+impl From<i64> for PodcastId {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, sqlx::Type)]
+#[sqlx(transparent)]
+pub struct EpisodeId(pub i64);
+
+impl From<i64> for EpisodeId {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+pub struct RowPodcast {
+    pub id: PodcastId,
+    pub title: String,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub language: Option<String>,
+    pub link: Option<String>,
+    pub copyright: Option<String>,
+    pub itunes_author: Option<String>,
+    pub itunes_category: Option<String>,
+    pub itunes_subcategory: Option<String>,
+    pub itunes_owner_name: Option<String>,
+    pub itunes_owner_email: Option<String>,
+    pub itunes_type: Option<String>,
+    pub itunes_summary: Option<String>,
+    pub podcast_locked: Option<bool>,
+    pub podcast_locked_owner: Option<String>,
+    pub created: OffsetDateTime,
+    pub updated: Option<OffsetDateTime>,
+}
+
+pub struct RowPodcastEpisode {
+    pub id: EpisodeId,
+    pub podcast_id: PodcastId,
+    pub title: Option<String>,
+    pub enclosure: Option<String>,
+    pub guid: Option<String>,
+    pub published: Option<String>,
+    pub description: Option<String>,
+    pub itunes_season: Option<i64>,
+    pub itunes_episode: Option<i64>,
+    pub itunes_duration: Option<String>,
+    pub itunes_image: Option<String>,
+    pub itunes_type: Option<String>,
+    pub itunes_subtitle: Option<String>,
+    pub itunes_summary: Option<String>,
+    pub podcast_chapters: Option<String>,
+    pub podcast_chapters_type: Option<String>,
+    pub podcast_season: Option<i64>,
+    pub podcast_season_name: Option<String>,
+    pub created: OffsetDateTime,
+    pub updated: Option<OffsetDateTime>,
+}
+
+/// One page of rows along with the total amount of rows.
+pub struct RowPage<T> {
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+    pub rows: Vec<T>,
+}
+
+/// Splits the page and page size into a limit and offset, with the same defaults as the
+/// subscription listing.
+pub(super) fn limit_offset(page: Option<i64>, per_page: Option<i64>) -> (i64, i64, i64) {
+    let page = page.filter(|page| *page > 0).unwrap_or(1);
+    let per_page = per_page
+        .filter(|per_page| *per_page > 0)
+        .unwrap_or(50)
+        .min(500);
+
+    (page, per_page, (per_page * page) - per_page)
+}
+
+impl Database {
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn podcast_get_by_feed(&self, feed_url: &Url) -> anyhow::Result<Option<RowPodcast>> {
+        let feed_url = feed_url.as_str();
+
+        sqlx::query_as!(
+            RowPodcast,
+            r#"--sql
+                SELECT
+                    p.id, p.title, p.description, p.image, p.language, p.link, p.copyright,
+                    p.itunes_author, p.itunes_category, p.itunes_subcategory, p.itunes_owner_name,
+                    p.itunes_owner_email, p.itunes_type, p.itunes_summary,
+                    p.podcast_locked as "podcast_locked: bool", p.podcast_locked_owner, p.created,
+                    p.updated
+                FROM
+                    podcast_feed pf
+                INNER JOIN podcast p ON pf.podcast_id = p.id
+                WHERE
+                    pf.feed_url = ?1 AND pf.deleted IS NULL AND p.deleted IS NULL
+                LIMIT 1
+            "#,
+            feed_url,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get podcast by feed")
+    }
+
+    /// Returns the podcast if the user is currently subscribed to one of its feeds.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn podcast_get_by_id(
+        &self,
+        user: &User,
+        id: PodcastId,
+    ) -> anyhow::Result<Option<RowPodcast>> {
+        sqlx::query_as!(
+            RowPodcast,
+            r#"--sql
+                SELECT
+                    p.id, p.title, p.description, p.image, p.language, p.link, p.copyright,
+                    p.itunes_author, p.itunes_category, p.itunes_subcategory, p.itunes_owner_name,
+                    p.itunes_owner_email, p.itunes_type, p.itunes_summary,
+                    p.podcast_locked as "podcast_locked: bool", p.podcast_locked_owner, p.created,
+                    p.updated
+                FROM
+                    podcast p
+                WHERE
+                    p.id = ?2
+                    AND p.deleted IS NULL
+                    AND EXISTS (
+                        SELECT 1
+                        FROM podcast_feed pf
+                        INNER JOIN subscription_feeds sf ON sf.feed = pf.feed_url
+                        INNER JOIN user_subscriptions us ON us.subscription_id = sf.subscription_id
+                        WHERE
+                            pf.podcast_id = p.id
+                            AND pf.deleted IS NULL
+                            AND us.user_id = ?1
+                            AND us.deleted IS NULL
+                    )
+            "#,
+            user.id,
+            id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get podcast by id")
+    }
+
+    /// Returns the feed URLs of a podcast, newest first.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn podcast_get_feeds(&self, id: PodcastId) -> anyhow::Result<Vec<String>> {
+        let rows = sqlx::query!(
+            r#"--sql
+                SELECT
+                    feed_url
+                FROM
+                    podcast_feed
+                WHERE
+                    podcast_id = ?1 AND deleted IS NULL
+                ORDER BY created DESC
+            "#,
+            id,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to run query: get podcast feeds")?;
+
+        Ok(rows.into_iter().map(|row| row.feed_url).collect())
+    }
+
+    /// Returns the feed URLs of each of the podcasts, newest first, podcasts without any feeds are
+    /// left out.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn podcasts_get_feeds(
+        &self,
+        ids: &[PodcastId],
+    ) -> anyhow::Result<HashMap<PodcastId, Vec<String>>> {
+        let ids = serde_json::to_string(&ids.iter().map(|id| id.0).collect::<Vec<_>>())?;
+
+        let rows = sqlx::query!(
+            r#"--sql
+                SELECT
+                    podcast_id as "podcast_id: PodcastId", feed_url
+                FROM
+                    podcast_feed
+                WHERE
+                    podcast_id IN (SELECT value FROM json_each(?1)) AND deleted IS NULL
+                ORDER BY created DESC
+            "#,
+            ids,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to run query: get podcasts feeds")?;
+
+        let mut feeds = HashMap::<_, Vec<_>>::new();
+        for row in rows {
+            feeds.entry(row.podcast_id).or_default().push(row.feed_url);
+        }
+
+        Ok(feeds)
+    }
+
+    /// Lists the podcasts the user is currently subscribed to, along with the total amount.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn podcasts_get_all(
+        &self,
+        user: &User,
+        page: Option<i64>,
+        per_page: Option<i64>,
+    ) -> anyhow::Result<RowPage<RowPodcast>> {
+        let (page, per_page, offset) = limit_offset(page, per_page);
+
+        let total = sqlx::query!(
+            r#"--sql
+                SELECT
+                    COUNT(DISTINCT pf.podcast_id) as "total!: i64"
+                FROM
+                    user_subscriptions us
+                INNER JOIN subscription_feeds sf ON sf.subscription_id = us.subscription_id
+                INNER JOIN podcast_feed pf ON pf.feed_url = sf.feed AND pf.deleted IS NULL
+                INNER JOIN podcast p ON pf.podcast_id = p.id AND p.deleted IS NULL
+                WHERE
+                    us.user_id = ?1 AND us.deleted IS NULL
+            "#,
+            user.id,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to run query: count user podcasts")?
+        .total;
+
+        let podcasts = sqlx::query_as!(
+            RowPodcast,
+            r#"--sql
+                SELECT DISTINCT
+                    p.id, p.title, p.description, p.image, p.language, p.link, p.copyright,
+                    p.itunes_author, p.itunes_category, p.itunes_subcategory, p.itunes_owner_name,
+                    p.itunes_owner_email, p.itunes_type, p.itunes_summary,
+                    p.podcast_locked as "podcast_locked: bool", p.podcast_locked_owner, p.created,
+                    p.updated
+                FROM
+                    user_subscriptions us
+                INNER JOIN subscription_feeds sf ON sf.subscription_id = us.subscription_id
+                INNER JOIN podcast_feed pf ON pf.feed_url = sf.feed AND pf.deleted IS NULL
+                INNER JOIN podcast p ON pf.podcast_id = p.id AND p.deleted IS NULL
+                WHERE
+                    us.user_id = ?1 AND us.deleted IS NULL
+                ORDER BY p.title ASC, p.id ASC
+                LIMIT ?2
+                OFFSET ?3
+            "#,
+            user.id,
+            per_page,
+            offset,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get user podcasts")?;
+
+        Ok(RowPage {
+            total,
+            page,
+            per_page,
+            rows: podcasts,
+        })
+    }
+
+    /// Lists the episodes of a podcast, newest first, along with the total amount.
+    ///
+    /// Callers are expected to have checked the user can see the podcast.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn podcast_episodes_get_all(
+        &self,
+        id: PodcastId,
+        page: Option<i64>,
+        per_page: Option<i64>,
+    ) -> anyhow::Result<RowPage<RowPodcastEpisode>> {
+        let (page, per_page, offset) = limit_offset(page, per_page);
+
+        let total = sqlx::query!(
+            r#"--sql
+                SELECT
+                    COUNT(*) as "total!: i64"
+                FROM
+                    podcast_episode
+                WHERE
+                    podcast_id = ?1 AND deleted IS NULL
+            "#,
+            id,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to run query: count podcast episodes")?
+        .total;
+
+        let episodes = sqlx::query_as!(
+            RowPodcastEpisode,
+            r#"--sql
+                SELECT
+                    id, podcast_id, title, enclosure, guid, published, description,
+                    itunes_season, itunes_episode, itunes_duration, itunes_image, itunes_type,
+                    itunes_subtitle, itunes_summary, podcast_chapters, podcast_chapters_type,
+                    podcast_season, podcast_season_name, created, updated
+                FROM
+                    podcast_episode
+                WHERE
+                    podcast_id = ?1 AND deleted IS NULL
+                ORDER BY created DESC, id DESC
+                LIMIT ?2
+                OFFSET ?3
+            "#,
+            id,
+            per_page,
+            offset,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get podcast episodes")?;
+
+        Ok(RowPage {
+            total,
+            page,
+            per_page,
+            rows: episodes,
+        })
+    }
+
+    /// Callers are expected to have checked the user can see the podcast.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn podcast_episode_get_by_id(
+        &self,
+        podcast: PodcastId,
+        id: EpisodeId,
+    ) -> anyhow::Result<Option<RowPodcastEpisode>> {
+        sqlx::query_as!(
+            RowPodcastEpisode,
+            r#"--sql
+                SELECT
+                    id, podcast_id, title, enclosure, guid, published, description,
+                    itunes_season, itunes_episode, itunes_duration, itunes_image, itunes_type,
+                    itunes_subtitle, itunes_summary, podcast_chapters, podcast_chapters_type,
+                    podcast_season, podcast_season_name, created, updated
+                FROM
+                    podcast_episode
+                WHERE
+                    podcast_id = ?1 AND id = ?2 AND deleted IS NULL
+            "#,
+            podcast,
+            id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get podcast episode by id")
+    }
+}
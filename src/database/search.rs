@@ -0,0 +1,260 @@
+//! Full-text search over the podcasts and episodes users are subscribed to.
+//!
+//! The index lives in FTS5 tables sharing their rowid with the podcast or episode, it is
+//! rewritten in the same transaction whenever a feed is stored.
+
+use anyhow::Context as _;
+use sqlx::SqliteConnection;
+
+use crate::{
+    database::{
+        podcast::{limit_offset, EpisodeId, PodcastId, RowPage},
+        user::User,
+        Database,
+    },
+    models::rss::{Channel, Item},
+};
+
+pub struct RowSearchHit {
+    pub podcast_id: PodcastId,
+    pub podcast_title: String,
+    /// Missing when the podcast itself matched.
+    pub episode_id: Option<EpisodeId>,
+    pub title: Option<String>,
+    pub snippet: String,
+    /// The BM25 rank of the hit, lower is better.
+    pub rank: f64,
+}
+
+pub(super) async fn index_podcast(
+    conn: &mut SqliteConnection,
+    podcast: PodcastId,
+    channel: &Channel,
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM podcast_search
+            WHERE rowid = ?1
+        "#,
+        podcast,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete podcast search index")?;
+
+    sqlx::query!(
+        r#"--sql
+            INSERT INTO podcast_search (rowid, title, description, notes)
+            VALUES (?1, ?2, ?3, ?4)
+        "#,
+        podcast,
+        channel.title,
+        channel.description,
+        channel.itunes_summary,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: create podcast search index")?;
+
+    Ok(())
+}
+
+pub(super) async fn index_episode(
+    conn: &mut SqliteConnection,
+    episode: EpisodeId,
+    item: &Item,
+) -> anyhow::Result<()> {
+    let notes = item
+        .itunes_summary
+        .as_ref()
+        .or(item.itunes_subtitle.as_ref());
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM episode_search
+            WHERE rowid = ?1
+        "#,
+        episode,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete episode search index")?;
+
+    sqlx::query!(
+        r#"--sql
+            INSERT INTO episode_search (rowid, title, description, notes)
+            VALUES (?1, ?2, ?3, ?4)
+        "#,
+        episode,
+        item.title,
+        item.description,
+        notes,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: create episode search index")?;
+
+    Ok(())
+}
+
+/// Turns what the user typed into an FTS5 query, every word has to match and the last one may
+/// only be the start of a word.
+///
+/// Words are quoted so FTS5 operators and column filters are searched for as text instead of
+/// being interpreted, returns `None` when there is nothing to search for.
+pub fn match_query(query: &str) -> Option<String> {
+    let words = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>();
+
+    if words.is_empty() {
+        return None;
+    }
+
+    Some(format!("{}*", words.join(" ")))
+}
+
+impl Database {
+    /// Searches the titles, descriptions and show notes of the podcasts the user is subscribed
+    /// to, and of their episodes, best matches first.
+    ///
+    /// `query` is expected to come from [`match_query`].
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn search(
+        &self,
+        user: &User,
+        query: &str,
+        page: Option<i64>,
+        per_page: Option<i64>,
+    ) -> anyhow::Result<RowPage<RowSearchHit>> {
+        let (page, per_page, offset) = limit_offset(page, per_page);
+
+        let total = sqlx::query!(
+            r#"--sql
+                WITH hits AS (
+                    SELECT podcast_search.rowid as podcast_id
+                    FROM podcast_search
+                    WHERE podcast_search MATCH ?2
+                    UNION ALL
+                    SELECT pe.podcast_id
+                    FROM episode_search
+                    INNER JOIN podcast_episode pe
+                        ON pe.id = episode_search.rowid AND pe.deleted IS NULL
+                    WHERE episode_search MATCH ?2
+                )
+                SELECT
+                    COUNT(*) as "total!: i64"
+                FROM
+                    hits h
+                INNER JOIN podcast p ON p.id = h.podcast_id AND p.deleted IS NULL
+                WHERE
+                    EXISTS (
+                        SELECT 1
+                        FROM podcast_feed pf
+                        INNER JOIN subscription_feeds sf ON sf.feed = pf.feed_url
+                        INNER JOIN user_subscriptions us ON us.subscription_id = sf.subscription_id
+                        WHERE
+                            pf.podcast_id = p.id
+                            AND pf.deleted IS NULL
+                            AND us.user_id = ?1
+                            AND us.deleted IS NULL
+                    )
+            "#,
+            user.id,
+            query,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to run query: count search results")?
+        .total;
+
+        // Titles weigh the most, then descriptions, then show notes.
+        let hits = sqlx::query_as!(
+            RowSearchHit,
+            r#"--sql
+                WITH hits AS (
+                    SELECT
+                        podcast_search.rowid as podcast_id,
+                        NULL as episode_id,
+                        podcast_search.title as title,
+                        snippet(podcast_search, -1, '', '', '…', 24) as snippet,
+                        bm25(podcast_search, 10.0, 2.0, 1.0) as rank
+                    FROM podcast_search
+                    WHERE podcast_search MATCH ?2
+                    UNION ALL
+                    SELECT
+                        pe.podcast_id,
+                        episode_search.rowid,
+                        episode_search.title,
+                        snippet(episode_search, -1, '', '', '…', 24),
+                        bm25(episode_search, 10.0, 2.0, 1.0)
+                    FROM episode_search
+                    INNER JOIN podcast_episode pe
+                        ON pe.id = episode_search.rowid AND pe.deleted IS NULL
+                    WHERE episode_search MATCH ?2
+                )
+                SELECT
+                    h.podcast_id as "podcast_id!: PodcastId",
+                    p.title as "podcast_title!: String",
+                    h.episode_id as "episode_id: EpisodeId",
+                    h.title as "title: String",
+                    h.snippet as "snippet!: String",
+                    h.rank as "rank!: f64"
+                FROM
+                    hits h
+                INNER JOIN podcast p ON p.id = h.podcast_id AND p.deleted IS NULL
+                WHERE
+                    EXISTS (
+                        SELECT 1
+                        FROM podcast_feed pf
+                        INNER JOIN subscription_feeds sf ON sf.feed = pf.feed_url
+                        INNER JOIN user_subscriptions us ON us.subscription_id = sf.subscription_id
+                        WHERE
+                            pf.podcast_id = p.id
+                            AND pf.deleted IS NULL
+                            AND us.user_id = ?1
+                            AND us.deleted IS NULL
+                    )
+                ORDER BY h.rank ASC, h.podcast_id ASC, h.episode_id ASC
+                LIMIT ?3
+                OFFSET ?4
+            "#,
+            user.id,
+            query,
+            per_page,
+            offset,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: search podcasts")?;
+
+        Ok(RowPage {
+            total,
+            page,
+            per_page,
+            rows: hits,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::match_query;
+
+    #[test]
+    fn match_queries() {
+        assert_eq!(match_query("   "), None);
+        assert_eq!(match_query("rust"), Some("\"rust\"*".to_string()));
+        assert_eq!(
+            match_query("rust  async"),
+            Some("\"rust\" \"async\"*".to_string())
+        );
+        assert_eq!(
+            match_query("title:\"x\" OR"),
+            Some("\"title:\"\"x\"\"\" \"OR\"*".to_string())
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use anyhow::Context as _;
 use data_encoding::BASE64;
 use rand::{rngs::OsRng, RngCore as _};
 use time::OffsetDateTime;
@@ -16,6 +17,11 @@ pub struct OptionalSession {
     pub expires: Option<OffsetDateTime>,
 }
 
+pub struct RowSession {
+    pub id: i64,
+    pub expires: OffsetDateTime,
+}
+
 impl OptionalSession {
     pub fn into_session(self) -> Option<Session> {
         let id = self.id?;
@@ -36,16 +42,35 @@ impl OptionalSession {
     }
 }
 
+/// Generates a new session token.
+pub(in crate::database) fn token() -> String {
+    let mut bytes = [0; 64];
+    OsRng.fill_bytes(&mut bytes);
+
+    BASE64.encode(&bytes)
+}
+
 impl Database {
     #[tracing::instrument(skip_all, err)]
     #[autometrics::autometrics]
     pub async fn session_crate(&self, user: &User) -> anyhow::Result<(String, OffsetDateTime)> {
+        self.session_crate_with_lifetime(user, time::Duration::days(7 * 3))
+            .await
+    }
+
+    /// Creates a session that lasts for the given amount of time, used for long lived app
+    /// passwords.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn session_crate_with_lifetime(
+        &self,
+        user: &User,
+        lifetime: time::Duration,
+    ) -> anyhow::Result<(String, OffsetDateTime)> {
         let now = OffsetDateTime::now_utc();
-        let expires = now + time::Duration::days(7 * 3);
+        let expires = now + lifetime;
 
-        let mut bytes = [0; 64];
-        OsRng.fill_bytes(&mut bytes);
-        let token = BASE64.encode(&bytes);
+        let token = token();
 
         sqlx::query!(
             r#"--sql
@@ -76,7 +101,7 @@ impl Database {
                     user_sessions us
                 LEFT JOIN users u ON us.user_id = u.id
                 WHERE
-                    us.token = ?
+                    us.token = ? AND u.disabled IS NULL
                 LIMIT 1
             "#,
             token
@@ -86,4 +111,86 @@ impl Database {
         .map_err(anyhow::Error::from)
         .map(|ok| ok.and_then(OptionalSession::into_session))
     }
+
+    /// Removes the session, like on logout.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn session_delete_by_token(&self, token: &str) -> anyhow::Result<()> {
+        sqlx::query!(
+            r#"--sql
+                DELETE FROM user_sessions
+                WHERE token = ?
+            "#,
+            token,
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to run query: delete session")?;
+
+        Ok(())
+    }
+
+    /// Lists the user's sessions and app passwords, without their tokens.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn sessions_get_all(&self, user: &User) -> anyhow::Result<Vec<RowSession>> {
+        sqlx::query_as!(
+            RowSession,
+            r#"--sql
+                SELECT id, expires
+                FROM user_sessions
+                WHERE user_id = ?
+                ORDER BY id ASC
+            "#,
+            user.id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+    }
+
+    /// Removes the sessions and app passwords that have expired, returning how many there were.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn sessions_prune(&self) -> anyhow::Result<u64> {
+        let now = OffsetDateTime::now_utc();
+
+        let result = sqlx::query!(
+            r#"--sql
+                DELETE FROM user_sessions
+                WHERE expires < ?
+            "#,
+            now,
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to run query: prune sessions")?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::Database;
+
+    #[sqlx::test(fixtures("../../fixtures/dummy.sql"))]
+    async fn prune(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool).await.unwrap();
+        let user = db.user_get_by_id(Database::USER_ID).await.unwrap().unwrap();
+
+        db.session_crate_with_lifetime(&user, time::Duration::days(-1))
+            .await
+            .unwrap();
+        assert_eq!(db.sessions_get_all(&user).await.unwrap().len(), 2);
+
+        assert_eq!(db.sessions_prune().await.unwrap(), 1);
+        assert!(db
+            .session_get_by_token(Database::TOKEN)
+            .await
+            .unwrap()
+            .is_some());
+    }
 }
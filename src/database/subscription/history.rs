@@ -0,0 +1,170 @@
+use anyhow::Context as _;
+use sqlx::SqliteConnection;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{
+    database::{device::DeviceId, subscription::SubscriptionId, user::User, Database},
+    models::subscriptions::{Client, EventKind},
+};
+
+/// Who made a change to a subscription.
+#[derive(Debug, Clone, Copy)]
+pub struct Origin {
+    pub client: Client,
+    pub device: Option<DeviceId>,
+}
+
+impl Origin {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            device: None,
+        }
+    }
+
+    pub fn device(client: Client, device: Option<DeviceId>) -> Self {
+        Self { client, device }
+    }
+}
+
+pub struct RowSubscriptionEvent {
+    pub kind: EventKind,
+    pub feed: Option<String>,
+    pub guid: Option<Uuid>,
+    pub client: Option<Client>,
+    pub device_identifier: Option<String>,
+    pub created: OffsetDateTime,
+}
+
+/// Appends an event to the subscription's history, `user` is left out for changes to the shared
+/// subscription.
+pub(in crate::database) async fn record(
+    conn: &mut SqliteConnection,
+    user: Option<&User>,
+    id: SubscriptionId,
+    kind: EventKind,
+    origin: Origin,
+    feed: Option<&str>,
+    guid: Option<Uuid>,
+) -> anyhow::Result<()> {
+    let user_id = user.map(|user| user.id);
+
+    sqlx::query!(
+        r#"--sql
+            INSERT INTO subscription_events (
+                user_id, subscription_id, kind, feed, guid, client, device_id
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        "#,
+        user_id,
+        id,
+        kind,
+        feed,
+        guid,
+        origin.client,
+        origin.device,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: create subscription event")?;
+
+    Ok(())
+}
+
+impl Database {
+    /// Lists the changes made to the user's subscription, oldest first, along with the changes
+    /// made to the shared subscription.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn subscription_history_get(
+        &self,
+        user: &User,
+        id: SubscriptionId,
+    ) -> anyhow::Result<Vec<RowSubscriptionEvent>> {
+        sqlx::query_as!(
+            RowSubscriptionEvent,
+            r#"--sql
+                SELECT
+                    se.kind as "kind: EventKind", se.feed, se.guid as "guid: Uuid",
+                    se.client as "client: Client", d.identifier as "device_identifier?",
+                    se.created
+                FROM
+                    subscription_events se
+                LEFT JOIN devices d ON d.id = se.device_id
+                WHERE
+                    se.subscription_id = ?2 AND (se.user_id = ?1 OR se.user_id IS NULL)
+                ORDER BY JULIANDAY(se.created) ASC, se.id ASC
+            "#,
+            user.id,
+            id,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get subscription history")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use crate::{
+        database::{
+            subscription::{history::Origin, SubscriptionId},
+            Database,
+        },
+        models::subscriptions::{Client, EventKind},
+    };
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn only_changes(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool).await.unwrap();
+        let user = db.user_get_by_id(Database::USER_ID).await.unwrap().unwrap();
+        let origin = Origin::new(Client::Api);
+        let id = SubscriptionId(Database::SUBSCRIPTION_1_ID);
+        let feed_url = Url::parse(Database::SUBSCRIPTION_1_FEED).unwrap();
+
+        // Already subscribed, nothing changes.
+        db.subscription_create(&user, origin, &feed_url, None)
+            .await
+            .unwrap();
+        assert!(db
+            .subscription_set_subscribed(&user, origin, id, true)
+            .await
+            .unwrap());
+        assert!(db
+            .subscription_history_get(&user, id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert!(db
+            .subscription_set_subscribed(&user, origin, id, false)
+            .await
+            .unwrap());
+        assert!(db
+            .subscription_set_subscribed(&user, origin, id, false)
+            .await
+            .unwrap());
+        db.subscription_create(&user, origin, &feed_url, None)
+            .await
+            .unwrap();
+        db.subscription_create(&user, origin, &feed_url, None)
+            .await
+            .unwrap();
+
+        let kinds = db
+            .subscription_history_get(&user, id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|event| event.kind)
+            .collect::<Vec<_>>();
+
+        assert_eq!(kinds, [EventKind::Unsubscribed, EventKind::Subscribed]);
+    }
+}
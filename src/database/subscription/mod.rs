@@ -1,10 +1,30 @@
+pub mod history;
 pub mod mutation;
 pub mod query;
 
 use time::OffsetDateTime;
+use url::Url;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Copy, sqlx::Type)]
+/// Namespace used by the `podcast:guid` tag to derive a GUID from a feed URL.
+const PODCAST_NAMESPACE: Uuid = uuid::uuid!("ead4c236-bf58-58c6-a2c6-a6b28d128cb6");
+
+/// Generates the `podcast:guid` for a feed that didn't come with one.
+///
+/// The feed URL has its scheme and any trailing slashes removed before being hashed, as described by
+/// the Podcasting 2.0 namespace.
+pub fn feed_guid(feed_url: &Url) -> Uuid {
+    let url = feed_url.as_str();
+    let url = url
+        .strip_prefix(feed_url.scheme())
+        .and_then(|url| url.strip_prefix("://"))
+        .unwrap_or(url);
+
+    Uuid::new_v5(&PODCAST_NAMESPACE, url.trim_end_matches('/').as_bytes())
+}
+
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, sqlx::Type)]
+#[serde(transparent)]
 #[sqlx(transparent)]
 pub struct SubscriptionId(pub i64);
 
@@ -55,6 +75,13 @@ pub struct RowSubscriptionGuid {
     pub deleted: Option<OffsetDateTime>,
 }
 
+/// The current feed of a subscription and whether the user is subscribed to it.
+pub struct RowSubscriptionChange {
+    pub subscription_id: SubscriptionId,
+    pub feed: String,
+    pub is_subscribed: bool,
+}
+
 pub struct WrapperId {
     pub id: SubscriptionId,
 }
@@ -1 +1,773 @@
+use anyhow::Context as _;
+use sqlx::SqliteConnection;
+use time::OffsetDateTime;
+use url::Url;
+use uuid::Uuid;
 
+use crate::{
+    database::{
+        jobs::{self, Job},
+        subscription::{
+            feed_guid,
+            history::{self, Origin},
+            SubscriptionId, WrapperId,
+        },
+        tasks::deletion,
+        user::User,
+        Database,
+    },
+    models::{
+        subscriptions::{EventKind, NewSubscription},
+        takeout::TakeoutSubscription,
+    },
+};
+
+/// A subscription restored from a takeout.
+pub struct ImportedSubscription {
+    pub id: SubscriptionId,
+    /// The GUID the subscription is known by on this server.
+    pub guid: Uuid,
+    /// Whether the user already had the subscription, its history is left as it was if so.
+    pub merged: bool,
+}
+
+/// The outcome of pointing a subscription at a new feed or GUID.
+pub enum UpdatedSubscription {
+    Updated,
+    /// The user doesn't have the subscription.
+    NotFound,
+    /// The feed or GUID already belongs to another subscription.
+    Conflict,
+}
+
+impl Database {
+    /// Subscribes the user to a feed, creating the shared subscription if nobody has added the feed
+    /// (or the given GUID) before.
+    ///
+    /// Subscribing to a feed the user had previously unsubscribed from marks it as subscribed again.
+    /// The change is recorded against the device that made it, if any, and added to the
+    /// subscription's history unless the user was already subscribed.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn subscription_create(
+        &self,
+        user: &User,
+        origin: Origin,
+        feed_url: &Url,
+        guid: Option<Uuid>,
+    ) -> anyhow::Result<NewSubscription> {
+        let now = OffsetDateTime::now_utc();
+        let feed = feed_url.as_str();
+
+        let mut tx = self.pool.begin().await?;
+
+        let mut existing = sqlx::query_as!(
+            WrapperId,
+            r#"--sql
+                SELECT
+                    subscription_id as id
+                FROM
+                    subscription_feeds
+                WHERE
+                    feed = ?1
+            "#,
+            feed,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to run query: get subscription by feed")?;
+
+        if let (None, Some(guid)) = (&existing, guid) {
+            existing = sqlx::query_as!(
+                WrapperId,
+                r#"--sql
+                    SELECT
+                        subscription_id as id
+                    FROM
+                        subscription_guids
+                    WHERE
+                        guid = ?1
+                "#,
+                guid,
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .context("Failed to run query: get subscription by guid")?;
+        }
+
+        let id = match existing {
+            Some(WrapperId { id }) => id,
+            None => {
+                let guid = guid.unwrap_or_else(|| feed_guid(feed_url));
+
+                let row = sqlx::query_as!(
+                    WrapperId,
+                    r#"--sql
+                        INSERT INTO subscriptions DEFAULT VALUES
+                        RETURNING id
+                    "#,
+                )
+                .fetch_one(&mut *tx)
+                .await
+                .context("Failed to run query: create subscription")?;
+
+                sqlx::query!(
+                    r#"--sql
+                        INSERT INTO subscription_feeds (subscription_id, feed)
+                        VALUES (?1, ?2)
+                    "#,
+                    row.id,
+                    feed,
+                )
+                .execute(&mut *tx)
+                .await
+                .context("Failed to run query: create subscription feed")?;
+
+                sqlx::query!(
+                    r#"--sql
+                        INSERT INTO subscription_guids (subscription_id, guid)
+                        VALUES (?1, ?2)
+                    "#,
+                    row.id,
+                    guid,
+                )
+                .execute(&mut *tx)
+                .await
+                .context("Failed to run query: create subscription guid")?;
+
+                jobs::enqueue(
+                    &mut *tx,
+                    &Job::Identification {
+                        subscription_id: row.id,
+                    },
+                    now,
+                )
+                .await
+                .context("Failed to queue identification")?;
+
+                row.id
+            }
+        };
+
+        let subscribed = sqlx::query!(
+            r#"--sql
+                SELECT
+                    deleted IS NULL as "subscribed!: bool"
+                FROM
+                    user_subscriptions
+                WHERE
+                    user_id = ?1 AND subscription_id = ?2
+            "#,
+            user.id,
+            id,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to run query: get user subscription")?
+        .is_some_and(|row| row.subscribed);
+
+        sqlx::query!(
+            r#"--sql
+                INSERT INTO user_subscriptions (user_id, subscription_id, device_id)
+                VALUES (?1, ?2, ?4)
+                ON CONFLICT (user_id, subscription_id) DO UPDATE
+                SET updated = ?3, deleted = NULL, device_id = ?4
+            "#,
+            user.id,
+            id,
+            now,
+            origin.device,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: create user subscription")?;
+
+        deletion::cancel_pending(&mut *tx, user.id, id, now).await?;
+
+        let guid = sqlx::query!(
+            r#"--sql
+                SELECT
+                    guid as "guid: Uuid"
+                FROM
+                    subscription_guids
+                WHERE
+                    subscription_id = ?1
+                ORDER BY created ASC
+                LIMIT 1
+            "#,
+            id,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to run query: get subscription guid")?
+        .guid;
+
+        if !subscribed {
+            history::record(
+                &mut *tx,
+                Some(user),
+                id,
+                EventKind::Subscribed,
+                origin,
+                Some(feed),
+                Some(guid),
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(NewSubscription {
+            feed_url: feed_url.clone(),
+            guid,
+            is_subscribed: true,
+            subscription_changed: now,
+        })
+    }
+
+    /// Marks the user's subscription as (un)subscribed, returning `false` if the user isn't
+    /// subscribed to it.
+    ///
+    /// Only an actual change is added to the subscription's history.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn subscription_set_subscribed(
+        &self,
+        user: &User,
+        origin: Origin,
+        id: SubscriptionId,
+        is_subscribed: bool,
+    ) -> anyhow::Result<bool> {
+        let now = OffsetDateTime::now_utc();
+        let deleted = (!is_subscribed).then_some(now);
+        let kind = if is_subscribed {
+            EventKind::Subscribed
+        } else {
+            EventKind::Unsubscribed
+        };
+
+        let mut tx = self.pool.begin().await?;
+
+        let subscribed = sqlx::query!(
+            r#"--sql
+                SELECT
+                    deleted IS NULL as "subscribed!: bool"
+                FROM
+                    user_subscriptions
+                WHERE
+                    user_id = ?1 AND subscription_id = ?2
+            "#,
+            user.id,
+            id,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to run query: get user subscription")?;
+
+        let Some(subscribed) = subscribed.map(|row| row.subscribed) else {
+            return Ok(false);
+        };
+
+        sqlx::query!(
+            r#"--sql
+                UPDATE user_subscriptions
+                SET updated = ?3, deleted = ?4, device_id = ?5
+                WHERE user_id = ?1 AND subscription_id = ?2
+            "#,
+            user.id,
+            id,
+            now,
+            deleted,
+            origin.device,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: update user subscription")?;
+
+        if is_subscribed {
+            deletion::cancel_pending(&mut *tx, user.id, id, now).await?;
+        }
+
+        if subscribed != is_subscribed {
+            history::record(&mut *tx, Some(user), id, kind, origin, None, None).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Moves the shared subscription to a new feed and GUID, and marks the user's subscription as
+    /// (un)subscribed.
+    ///
+    /// The previous feeds stop being fetched. Each actual change is added to the subscription's
+    /// history, feed moves and GUID changes are shown to everyone subscribed to it.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn subscription_update(
+        &self,
+        user: &User,
+        origin: Origin,
+        id: SubscriptionId,
+        feed_url: &Url,
+        guid: Uuid,
+        is_subscribed: bool,
+    ) -> anyhow::Result<UpdatedSubscription> {
+        let now = OffsetDateTime::now_utc();
+        let feed = feed_url.as_str();
+
+        let mut tx = self.pool.begin().await?;
+
+        let subscribed = sqlx::query!(
+            r#"--sql
+                SELECT
+                    deleted IS NULL as "subscribed!: bool"
+                FROM
+                    user_subscriptions
+                WHERE
+                    user_id = ?1 AND subscription_id = ?2
+            "#,
+            user.id,
+            id,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to run query: get user subscription")?;
+
+        let Some(subscribed) = subscribed.map(|row| row.subscribed) else {
+            return Ok(UpdatedSubscription::NotFound);
+        };
+
+        let feed_owner = sqlx::query_as!(
+            WrapperId,
+            r#"--sql
+                SELECT
+                    subscription_id as id
+                FROM
+                    subscription_feeds
+                WHERE
+                    feed = ?1
+            "#,
+            feed,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to run query: get subscription by feed")?;
+
+        let guid_owner = sqlx::query_as!(
+            WrapperId,
+            r#"--sql
+                SELECT
+                    subscription_id as id
+                FROM
+                    subscription_guids
+                WHERE
+                    guid = ?1
+            "#,
+            guid,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to run query: get subscription by guid")?;
+
+        let owned_elsewhere =
+            |owner: &Option<WrapperId>| owner.as_ref().is_some_and(|owner| owner.id.0 != id.0);
+        if owned_elsewhere(&feed_owner) || owned_elsewhere(&guid_owner) {
+            return Ok(UpdatedSubscription::Conflict);
+        }
+
+        let current = sqlx::query!(
+            r#"--sql
+                SELECT
+                    feed
+                FROM
+                    subscription_feeds
+                WHERE
+                    subscription_id = ?1
+                ORDER BY created DESC
+                LIMIT 1
+            "#,
+            id,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to run query: get subscription feed")?;
+
+        if current.map(|row| row.feed).as_deref() != Some(feed) {
+            // Moving back to a previous feed makes it the current one again.
+            sqlx::query!(
+                r#"--sql
+                    INSERT INTO subscription_feeds (subscription_id, feed, created)
+                    VALUES (?1, ?2, ?3)
+                    ON CONFLICT (feed) DO UPDATE
+                    SET created = ?3, updated = ?3, deleted = NULL
+                "#,
+                id,
+                feed,
+                now,
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to run query: create subscription feed")?;
+
+            sqlx::query!(
+                r#"--sql
+                    UPDATE subscription_feeds
+                    SET updated = ?3, deleted = ?3
+                    WHERE subscription_id = ?1 AND feed != ?2 AND deleted IS NULL
+                "#,
+                id,
+                feed,
+                now,
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to run query: delete previous subscription feeds")?;
+
+            history::record(
+                &mut *tx,
+                None,
+                id,
+                EventKind::FeedMoved,
+                origin,
+                Some(feed),
+                None,
+            )
+            .await?;
+        }
+
+        if guid_owner.is_none() {
+            sqlx::query!(
+                r#"--sql
+                    INSERT INTO subscription_guids (subscription_id, guid, updated)
+                    VALUES (?1, ?2, ?3)
+                "#,
+                id,
+                guid,
+                now,
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to run query: create subscription guid")?;
+
+            history::record(
+                &mut *tx,
+                None,
+                id,
+                EventKind::GuidChanged,
+                origin,
+                None,
+                Some(guid),
+            )
+            .await?;
+        }
+
+        if subscribed != is_subscribed {
+            let deleted = (!is_subscribed).then_some(now);
+            let kind = if is_subscribed {
+                EventKind::Subscribed
+            } else {
+                EventKind::Unsubscribed
+            };
+
+            sqlx::query!(
+                r#"--sql
+                    UPDATE user_subscriptions
+                    SET updated = ?3, deleted = ?4, device_id = ?5
+                    WHERE user_id = ?1 AND subscription_id = ?2
+                "#,
+                user.id,
+                id,
+                now,
+                deleted,
+                origin.device,
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to run query: update user subscription")?;
+
+            if is_subscribed {
+                deletion::cancel_pending(&mut *tx, user.id, id, now).await?;
+            }
+
+            history::record(&mut *tx, Some(user), id, kind, origin, None, None).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(UpdatedSubscription::Updated)
+    }
+
+    /// Restores a subscription from a takeout, matching it to a shared subscription by its GUIDs
+    /// and then by its feeds.
+    ///
+    /// Feeds and GUIDs are only added when no shared subscription matched and one is created. The
+    /// takeout's history is only added if the user didn't have the subscription yet.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn subscription_import(
+        &self,
+        user: &User,
+        origin: Origin,
+        subscription: &TakeoutSubscription,
+    ) -> anyhow::Result<ImportedSubscription> {
+        let now = OffsetDateTime::now_utc();
+
+        let mut tx = self.pool.begin().await?;
+
+        let mut existing = None;
+
+        let guids = std::iter::once(subscription.guid)
+            .chain(subscription.guids.iter().map(|guid| guid.guid));
+        for guid in guids {
+            existing = sqlx::query_as!(
+                WrapperId,
+                r#"--sql
+                    SELECT
+                        subscription_id as id
+                    FROM
+                        subscription_guids
+                    WHERE
+                        guid = ?1
+                "#,
+                guid,
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .context("Failed to run query: get subscription by guid")?;
+
+            if existing.is_some() {
+                break;
+            }
+        }
+
+        if existing.is_none() {
+            let feeds = std::iter::once(subscription.feed_url.as_str())
+                .chain(subscription.feeds.iter().map(|feed| feed.feed.as_str()));
+            for feed in feeds {
+                existing = sqlx::query_as!(
+                    WrapperId,
+                    r#"--sql
+                        SELECT
+                            subscription_id as id
+                        FROM
+                            subscription_feeds
+                        WHERE
+                            feed = ?1
+                    "#,
+                    feed,
+                )
+                .fetch_optional(&mut *tx)
+                .await
+                .context("Failed to run query: get subscription by feed")?;
+
+                if existing.is_some() {
+                    break;
+                }
+            }
+        }
+
+        let id = match existing {
+            Some(WrapperId { id }) => id,
+            None => {
+                let row = sqlx::query_as!(
+                    WrapperId,
+                    r#"--sql
+                        INSERT INTO subscriptions DEFAULT VALUES
+                        RETURNING id
+                    "#,
+                )
+                .fetch_one(&mut *tx)
+                .await
+                .context("Failed to run query: create subscription")?;
+
+                import_feeds_and_guids(&mut *tx, row.id, subscription, now).await?;
+
+                jobs::enqueue(
+                    &mut *tx,
+                    &Job::Identification {
+                        subscription_id: row.id,
+                    },
+                    now,
+                )
+                .await
+                .context("Failed to queue identification")?;
+
+                row.id
+            }
+        };
+
+        let deleted =
+            (!subscription.is_subscribed).then(|| subscription.subscription_changed.unwrap_or(now));
+
+        let result = sqlx::query!(
+            r#"--sql
+                INSERT INTO user_subscriptions (user_id, subscription_id, updated, deleted)
+                VALUES (?1, ?2, ?3, ?4)
+                ON CONFLICT (user_id, subscription_id) DO NOTHING
+            "#,
+            user.id,
+            id,
+            subscription.subscription_changed,
+            deleted,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: create user subscription")?;
+
+        let merged = result.rows_affected() == 0;
+
+        if !merged {
+            // Devices aren't carried over, the events keep their client only.
+            for event in &subscription.history {
+                sqlx::query!(
+                    r#"--sql
+                        INSERT INTO subscription_events (
+                            user_id, subscription_id, kind, feed, guid, client, created
+                        )
+                        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                    "#,
+                    user.id,
+                    id,
+                    event.kind,
+                    event.feed_url,
+                    event.guid,
+                    event.client,
+                    event.timestamp,
+                )
+                .execute(&mut *tx)
+                .await
+                .context("Failed to run query: create subscription event")?;
+            }
+        }
+
+        let guid = sqlx::query!(
+            r#"--sql
+                SELECT
+                    guid as "guid: Uuid"
+                FROM
+                    subscription_guids
+                WHERE
+                    subscription_id = ?1
+                ORDER BY created ASC
+                LIMIT 1
+            "#,
+            id,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to run query: get subscription guid")?
+        .guid;
+
+        let feed = subscription.feed_url.as_str();
+        if !merged && subscription.history.is_empty() {
+            let kind = if subscription.is_subscribed {
+                EventKind::Subscribed
+            } else {
+                EventKind::Unsubscribed
+            };
+
+            history::record(
+                &mut *tx,
+                Some(user),
+                id,
+                kind,
+                origin,
+                Some(feed),
+                Some(guid),
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(ImportedSubscription { id, guid, merged })
+    }
+}
+
+/// Adds the feeds and GUIDs of a subscription the import created, shared subscriptions that
+/// already exist are only matched against so one user's archive can't move them for everyone.
+///
+/// The archive's timestamps aren't trusted, only the current feed (latest) and GUID (earliest)
+/// keep their place.
+async fn import_feeds_and_guids(
+    conn: &mut SqliteConnection,
+    id: SubscriptionId,
+    subscription: &TakeoutSubscription,
+    now: OffsetDateTime,
+) -> anyhow::Result<()> {
+    let before = now - time::Duration::seconds(1);
+
+    let feeds = subscription
+        .feeds
+        .iter()
+        .map(|feed| feed.feed.as_str())
+        .filter(|feed| *feed != subscription.feed_url);
+    for feed in feeds {
+        sqlx::query!(
+            r#"--sql
+                INSERT INTO subscription_feeds (subscription_id, feed, created, deleted)
+                VALUES (?1, ?2, ?3, ?4)
+                ON CONFLICT DO NOTHING
+            "#,
+            id,
+            feed,
+            before,
+            now,
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to run query: create subscription feed")?;
+    }
+
+    sqlx::query!(
+        r#"--sql
+            INSERT INTO subscription_feeds (subscription_id, feed, created)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT DO NOTHING
+        "#,
+        id,
+        subscription.feed_url,
+        now,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: create subscription feed")?;
+
+    sqlx::query!(
+        r#"--sql
+            INSERT INTO subscription_guids (subscription_id, guid, created)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT DO NOTHING
+        "#,
+        id,
+        subscription.guid,
+        before,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: create subscription guid")?;
+
+    let guids = subscription
+        .guids
+        .iter()
+        .map(|guid| guid.guid)
+        .filter(|guid| *guid != subscription.guid);
+    for guid in guids {
+        sqlx::query!(
+            r#"--sql
+                INSERT INTO subscription_guids (subscription_id, guid, created)
+                VALUES (?1, ?2, ?3)
+                ON CONFLICT DO NOTHING
+            "#,
+            id,
+            guid,
+            now,
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to run query: create subscription guid")?;
+    }
+
+    Ok(())
+}
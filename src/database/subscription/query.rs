@@ -5,9 +5,10 @@ use uuid::Uuid;
 
 use crate::{
     database::{
+        device::DeviceId,
         subscription::{
-            RowSubscriptionFeed, RowSubscriptionGuid, RowUserSubscription, SubscriptionId,
-            WrapperId,
+            RowSubscriptionChange, RowSubscriptionFeed, RowSubscriptionGuid, RowUserSubscription,
+            SubscriptionId, WrapperId,
         },
         user::User,
         Database,
@@ -40,10 +41,81 @@ impl Database {
         .context("Failed to run query: get subscriptions by guid")
     }
 
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn subscription_get_id_by_feed(
+        &self,
+        feed: &str,
+    ) -> anyhow::Result<Option<RowSubscriptionFeed>> {
+        sqlx::query_as!(
+            RowSubscriptionFeed,
+            r#"--sql
+                SELECT
+                    subscription_id, feed, created, updated, deleted
+                FROM
+                    subscription_feeds
+                WHERE
+                    feed = ?1
+            "#,
+            feed,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get subscriptions by feed")
+    }
+
+    /// Lists the user's subscriptions that were added, changed or removed since the given time,
+    /// along with the feed they currently point at.
+    ///
+    /// Changes made by the `exclude` device are skipped so a device doesn't get its own changes
+    /// back, a full sync (without `since`) always lists everything.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn subscriptions_get_changes(
+        &self,
+        user: &User,
+        since: Option<OffsetDateTime>,
+        exclude: Option<DeviceId>,
+    ) -> anyhow::Result<Vec<RowSubscriptionChange>> {
+        sqlx::query_as!(
+            RowSubscriptionChange,
+            r#"--sql
+                SELECT
+                    us.subscription_id,
+                    (
+                        SELECT sf.feed
+                        FROM subscription_feeds sf
+                        WHERE sf.subscription_id = us.subscription_id
+                        ORDER BY sf.created DESC
+                        LIMIT 1
+                    ) as "feed!: String",
+                    us.deleted IS NULL as "is_subscribed!: bool"
+                FROM
+                    user_subscriptions us
+                WHERE
+                    us.user_id = ?1
+                    AND (
+                        ?2 IS NULL
+                        OR JULIANDAY(COALESCE(us.deleted, us.updated, us.created)) >= JULIANDAY(?2)
+                    )
+                    AND (?2 IS NULL OR ?3 IS NULL OR us.device_id IS NOT ?3)
+                ORDER BY us.created ASC, us.subscription_id ASC
+            "#,
+            user.id,
+            since,
+            exclude,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get user subscription changes")
+    }
+
     // TODO: add since support so we don't have to process as much
     #[tracing::instrument(skip_all, err)]
     #[autometrics::autometrics]
-    async fn subscription_get_feeds(
+    pub async fn subscription_get_feeds(
         &self,
         id: SubscriptionId,
     ) -> anyhow::Result<Vec<RowSubscriptionFeed>> {
@@ -69,7 +141,7 @@ impl Database {
     // TODO: add since support so we don't have to process as much
     #[tracing::instrument(skip_all, err)]
     #[autometrics::autometrics]
-    async fn subscription_get_guids(
+    pub async fn subscription_get_guids(
         &self,
         id: SubscriptionId,
     ) -> anyhow::Result<Vec<RowSubscriptionGuid>> {
@@ -130,6 +202,7 @@ impl Database {
     pub async fn subscriptions_get_all(
         &self,
         user: &User,
+        tag: Option<&str>,
         page: Option<i64>,
         per_page: Option<i64>,
     ) -> anyhow::Result<Option<Subscriptions>> {
@@ -156,6 +229,15 @@ impl Database {
                 LEFT JOIN subscriptions s ON us.subscription_id = s.id
                 WHERE
                     us.user_id = ?1
+                    AND (?4 IS NULL OR EXISTS (
+                        SELECT 1
+                        FROM user_subscription_tags ust
+                        JOIN tag t ON ust.tag_id = t.id
+                        WHERE
+                            ust.user_id = us.user_id
+                            AND ust.subscription_id = us.subscription_id
+                            AND t.name = ?4
+                    ))
                 ORDER BY us.created DESC
                 LIMIT ?2
                 OFFSET ?3
@@ -163,6 +245,7 @@ impl Database {
             user.id,
             per_page,
             offset,
+            tag,
         )
         .fetch_all(&self.pool)
         .await
@@ -172,12 +255,49 @@ impl Database {
         self.subscriptions_fill_all(user, page, per_page, ids).await
     }
 
+    /// Collects every one of the user's subscriptions by walking through all of the pages.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn subscriptions_get_all_unpaged(
+        &self,
+        user: &User,
+    ) -> anyhow::Result<Vec<Subscription>> {
+        const PER_PAGE: i64 = 100;
+
+        let mut subscriptions = Vec::new();
+        let mut page = 1;
+
+        while let Some(Subscriptions {
+            subscriptions: chunk,
+            ..
+        }) = self
+            .subscriptions_get_all(user, None, Some(page), Some(PER_PAGE))
+            .await?
+        {
+            let done = (chunk.len() as i64) < PER_PAGE;
+
+            subscriptions.extend(chunk);
+
+            if done {
+                break;
+            }
+
+            page += 1;
+        }
+
+        Ok(subscriptions)
+    }
+
+    /// Lists the subscriptions that changed since `since`, leaving out the ones `exclude` changed
+    /// last so a device doesn't get its own changes back.
     #[tracing::instrument(skip_all, err)]
     #[autometrics::autometrics]
     pub async fn subscriptions_get_all_since(
         &self,
         user: &User,
+        tag: Option<&str>,
         since: OffsetDateTime,
+        exclude: Option<DeviceId>,
         page: Option<i64>,
         per_page: Option<i64>,
     ) -> anyhow::Result<Option<Subscriptions>> {
@@ -203,7 +323,18 @@ impl Database {
                     user_subscriptions us
                 LEFT JOIN subscriptions s ON us.subscription_id = s.id
                 WHERE
-                    us.user_id = ?1 AND (us.created < ?4 OR us.updated < ?4 OR us.deleted < ?4)
+                    us.user_id = ?1
+                    AND JULIANDAY(COALESCE(us.deleted, us.updated, us.created)) >= JULIANDAY(?4)
+                    AND (?6 IS NULL OR us.device_id IS NOT ?6)
+                    AND (?5 IS NULL OR EXISTS (
+                        SELECT 1
+                        FROM user_subscription_tags ust
+                        JOIN tag t ON ust.tag_id = t.id
+                        WHERE
+                            ust.user_id = us.user_id
+                            AND ust.subscription_id = us.subscription_id
+                            AND t.name = ?5
+                    ))
                 ORDER BY us.created DESC
                 LIMIT ?2
                 OFFSET ?3
@@ -212,6 +343,8 @@ impl Database {
             per_page,
             offset,
             since,
+            tag,
+            exclude,
         )
         .fetch_all(&self.pool)
         .await
@@ -291,6 +424,11 @@ impl Database {
 
         let guid_changed = new_guid_row.and_then(|g| g.updated);
 
+        let tags = self
+            .subscription_get_tags(user, id)
+            .await
+            .context("Failed get subscription tags")?;
+
         Ok(Some(Subscription {
             feed_url,
             guid,
@@ -299,6 +437,8 @@ impl Database {
             new_guid,
             guid_changed,
             deleted: subscription.deleted,
+            tags,
+            health: None,
         }))
     }
 
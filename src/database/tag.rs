@@ -0,0 +1,251 @@
+use anyhow::Context as _;
+use time::OffsetDateTime;
+
+use crate::database::{subscription::SubscriptionId, user::User, Database};
+
+/// The kind given to tags (folders) created by users.
+const USER_TAG: &str = "user";
+
+#[derive(Debug, Clone, Copy, PartialEq, sqlx::Type)]
+#[sqlx(transparent)]
+pub struct TagId(pub i64);
+
+impl From<i64> for TagId {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+pub struct RowTag {
+    pub id: TagId,
+    pub name: String,
+    pub created: OffsetDateTime,
+    pub updated: Option<OffsetDateTime>,
+}
+
+impl Database {
+    /// Creates a tag for the user, returning `None` if the user already has a tag with that name.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn tag_create(&self, user: &User, name: &str) -> anyhow::Result<Option<RowTag>> {
+        sqlx::query_as!(
+            RowTag,
+            r#"--sql
+                INSERT INTO tag (user_id, kind, name)
+                VALUES (?1, ?2, ?3)
+                ON CONFLICT (user_id, kind, name) DO NOTHING
+                RETURNING id, name, created, updated
+            "#,
+            user.id,
+            USER_TAG,
+            name,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: create tag")
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn tag_get_by_id(&self, user: &User, id: TagId) -> anyhow::Result<Option<RowTag>> {
+        sqlx::query_as!(
+            RowTag,
+            r#"--sql
+                SELECT
+                    id, name, created, updated
+                FROM
+                    tag
+                WHERE
+                    user_id = ?1 AND kind = ?2 AND id = ?3
+            "#,
+            user.id,
+            USER_TAG,
+            id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get tag by id")
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn tags_get_all(&self, user: &User) -> anyhow::Result<Vec<RowTag>> {
+        sqlx::query_as!(
+            RowTag,
+            r#"--sql
+                SELECT
+                    id, name, created, updated
+                FROM
+                    tag
+                WHERE
+                    user_id = ?1 AND kind = ?2
+                ORDER BY name ASC
+            "#,
+            user.id,
+            USER_TAG,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get user tags")
+    }
+
+    /// Renames one of the user's tags, returning `None` if the tag doesn't exist or the new name
+    /// is already taken.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn tag_rename(
+        &self,
+        user: &User,
+        id: TagId,
+        name: &str,
+    ) -> anyhow::Result<Option<RowTag>> {
+        let now = OffsetDateTime::now_utc();
+
+        sqlx::query_as!(
+            RowTag,
+            r#"--sql
+                UPDATE OR IGNORE tag
+                SET name = ?4, updated = ?5
+                WHERE user_id = ?1 AND kind = ?2 AND id = ?3
+                RETURNING id, name, created, updated
+            "#,
+            user.id,
+            USER_TAG,
+            id,
+            name,
+            now,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: rename tag")
+    }
+
+    /// Deletes one of the user's tags, removing it from every subscription it was assigned to.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn tag_delete(&self, user: &User, id: TagId) -> anyhow::Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"--sql
+                DELETE FROM user_subscription_tags
+                WHERE user_id = ?1 AND tag_id = ?2
+            "#,
+            user.id,
+            id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: delete tag assignments")?;
+
+        let result = sqlx::query!(
+            r#"--sql
+                DELETE FROM tag
+                WHERE user_id = ?1 AND kind = ?2 AND id = ?3
+            "#,
+            user.id,
+            USER_TAG,
+            id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: delete tag")?;
+
+        tx.commit().await?;
+
+        Ok(result.rows_affected() != 0)
+    }
+
+    /// Returns the names of the tags assigned to the user's subscription.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn subscription_get_tags(
+        &self,
+        user: &User,
+        id: SubscriptionId,
+    ) -> anyhow::Result<Vec<String>> {
+        let rows = sqlx::query!(
+            r#"--sql
+                SELECT
+                    t.name
+                FROM
+                    user_subscription_tags ust
+                JOIN tag t ON ust.tag_id = t.id
+                WHERE
+                    ust.user_id = ?1 AND ust.subscription_id = ?2
+                ORDER BY t.name ASC
+            "#,
+            user.id,
+            id,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to run query: get subscription tags")?;
+
+        Ok(rows.into_iter().map(|row| row.name).collect())
+    }
+
+    /// Replaces the tags assigned to the user's subscription, creating any tags the user doesn't
+    /// have yet.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn subscription_set_tags(
+        &self,
+        user: &User,
+        id: SubscriptionId,
+        names: &[String],
+    ) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"--sql
+                DELETE FROM user_subscription_tags
+                WHERE user_id = ?1 AND subscription_id = ?2
+            "#,
+            user.id,
+            id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: clear subscription tags")?;
+
+        for name in names {
+            sqlx::query!(
+                r#"--sql
+                    INSERT INTO tag (user_id, kind, name)
+                    VALUES (?1, ?2, ?3)
+                    ON CONFLICT (user_id, kind, name) DO NOTHING
+                "#,
+                user.id,
+                USER_TAG,
+                name,
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to run query: create tag")?;
+
+            sqlx::query!(
+                r#"--sql
+                    INSERT INTO user_subscription_tags (user_id, subscription_id, tag_id)
+                    SELECT ?1, ?2, id FROM tag WHERE user_id = ?1 AND kind = ?3 AND name = ?4
+                    ON CONFLICT (user_id, subscription_id, tag_id) DO NOTHING
+                "#,
+                user.id,
+                id,
+                USER_TAG,
+                name,
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to run query: assign subscription tag")?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
@@ -1,31 +1,264 @@
+use anyhow::Context as _;
+use sqlx::SqliteConnection;
+use time::OffsetDateTime;
 use uuid::Uuid;
 
 use crate::{
-    database::{tasks::DeletionId, user::User, Database},
-    models::subscriptions::{Deletion, DeletionStatus},
+    database::{
+        jobs::{self, Job},
+        subscription::{
+            history::{self, Origin},
+            SubscriptionId,
+        },
+        tasks::{DeletionId, RowDeletion},
+        user::User,
+        Database,
+    },
+    models::subscriptions::{Deletion, DeletionStatus, EventKind},
 };
 
+/// Undoes the pending deletion of a subscription the user subscribed to again, so it isn't purged
+/// once its grace period is over.
+pub(in crate::database) async fn cancel_pending(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    subscription_id: SubscriptionId,
+    now: OffsetDateTime,
+) -> anyhow::Result<()> {
+    let deletions = sqlx::query!(
+        r#"--sql
+            UPDATE task_deletions
+            SET updated = ?4, deleted = ?4
+            WHERE user_id = ?1 AND subscription_id = ?2 AND status = ?3 AND deleted IS NULL
+            RETURNING id as "id!: DeletionId"
+        "#,
+        user_id,
+        subscription_id,
+        DeletionStatus::Pending,
+        now,
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .context("Failed to run query: cancel pending deletions")?;
+
+    for deletion in deletions {
+        jobs::cancel(
+            &mut *conn,
+            &Job::Deletion {
+                deletion_id: deletion.id,
+            },
+        )
+        .await
+        .context("Failed to cancel deletion")?;
+    }
+
+    Ok(())
+}
+
 impl Database {
-    pub async fn deletion_create(&self, user: &User, uuid: Uuid) -> anyhow::Result<Option<i64>> {
-        // TODO: create a version that is bound by the user
-        let Some(row) = self.subscription_get_id_by_guid(uuid).await? else {
+    /// Queues the deletion of the user's subscription, it is purged once `grace` has passed and
+    /// can be restored until then.
+    ///
+    /// The subscription is marked as unsubscribed straight away so clients stop syncing it.
+    /// Deleting a subscription that is already waiting to be purged returns the existing
+    /// deletion.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn deletion_create(
+        &self,
+        user: &User,
+        origin: Origin,
+        uuid: Uuid,
+        grace: time::Duration,
+    ) -> anyhow::Result<Option<i64>> {
+        let now = OffsetDateTime::now_utc();
+        let purge_after = now + grace;
+
+        let Some(subscription) = self.subscription_get_id_by_guid(uuid).await? else {
             return Ok(None);
         };
+        let id = subscription.subscription_id;
+
+        let mut tx = self.pool.begin().await?;
+
+        let user_subscription = sqlx::query!(
+            r#"--sql
+                SELECT
+                    deleted
+                FROM
+                    user_subscriptions
+                WHERE
+                    user_id = ?1 AND subscription_id = ?2
+            "#,
+            user.id,
+            id,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to run query: get user subscription")?;
+
+        let Some(user_subscription) = user_subscription else {
+            return Ok(None);
+        };
+
+        let existing = sqlx::query!(
+            r#"--sql
+                SELECT
+                    id
+                FROM
+                    task_deletions
+                WHERE
+                    user_id = ?1 AND subscription_id = ?2 AND status = ?3 AND deleted IS NULL
+                LIMIT 1
+            "#,
+            user.id,
+            id,
+            DeletionStatus::Pending,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to run query: get pending deletion")?;
+
+        if let Some(existing) = existing {
+            return Ok(Some(existing.id));
+        }
 
         let row = sqlx::query!(
             r#"--sql
-                INSERT INTO task_deletions(user_id, subscription_id, status)
-                VALUES (?, ?, ?)
+                INSERT INTO task_deletions (
+                    user_id, subscription_id, status, purge_after, previous_unsubscribed
+                )
+                VALUES (?1, ?2, ?3, ?4, ?5)
                 RETURNING id
             "#,
             user.id,
-            row.subscription_id,
+            id,
             DeletionStatus::Pending,
+            purge_after,
+            user_subscription.deleted,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to run query: create deletion")?;
+
+        sqlx::query!(
+            r#"--sql
+                UPDATE user_subscriptions
+                SET updated = ?3, deleted = COALESCE(deleted, ?3), device_id = ?4
+                WHERE user_id = ?1 AND subscription_id = ?2
+            "#,
+            user.id,
+            id,
+            now,
+            origin.device,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: unsubscribe deleted user subscription")?;
+
+        history::record(
+            &mut *tx,
+            Some(user),
+            id,
+            EventKind::Deleted,
+            origin,
+            None,
+            None,
+        )
+        .await?;
+
+        jobs::enqueue(
+            &mut *tx,
+            &Job::Deletion {
+                deletion_id: DeletionId(row.id),
+            },
+            purge_after,
+        )
+        .await
+        .context("Failed to queue deletion")?;
+
+        tx.commit().await?;
+
+        Ok(Some(row.id))
+    }
+
+    /// Undoes the pending deletion of the user's subscription, putting it back the way it was
+    /// before it was deleted.
+    ///
+    /// Returns `false` if there is no deletion left to undo.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn deletion_restore(
+        &self,
+        user: &User,
+        origin: Origin,
+        id: SubscriptionId,
+    ) -> anyhow::Result<bool> {
+        let now = OffsetDateTime::now_utc();
+
+        let mut tx = self.pool.begin().await?;
+
+        let deletion = sqlx::query!(
+            r#"--sql
+                UPDATE task_deletions
+                SET updated = ?4, deleted = ?4
+                WHERE user_id = ?1 AND subscription_id = ?2 AND status = ?3 AND deleted IS NULL
+                RETURNING
+                    id as "id!: DeletionId",
+                    previous_unsubscribed as "previous_unsubscribed: OffsetDateTime"
+            "#,
+            user.id,
+            id,
+            DeletionStatus::Pending,
+            now,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to run query: restore deletion")?;
+
+        let Some(deletion) = deletion else {
+            return Ok(false);
+        };
+
+        sqlx::query!(
+            r#"--sql
+                UPDATE user_subscriptions
+                SET updated = ?3, deleted = ?4, device_id = ?5
+                WHERE user_id = ?1 AND subscription_id = ?2
+            "#,
+            user.id,
+            id,
+            now,
+            deletion.previous_unsubscribed,
+            origin.device,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: restore user subscription")?;
+
+        history::record(
+            &mut *tx,
+            Some(user),
+            id,
+            EventKind::Restored,
+            origin,
+            None,
+            None,
         )
-        .fetch_optional(&self.pool)
         .await?;
 
-        Ok(row.map(|row| row.id))
+        jobs::cancel(
+            &mut *tx,
+            &Job::Deletion {
+                deletion_id: deletion.id,
+            },
+        )
+        .await
+        .context("Failed to cancel deletion")?;
+
+        tx.commit().await?;
+
+        Ok(true)
     }
 
     pub async fn deletion_get(
@@ -37,7 +270,7 @@ impl Database {
             r#"--sql
                 SELECT status as "status: DeletionStatus"
                 FROM task_deletions
-                WHERE id = ?1 AND user_id = ?2
+                WHERE id = ?1 AND user_id = ?2 AND deleted IS NULL
             "#,
             id,
             user.id,
@@ -51,4 +284,214 @@ impl Database {
             DeletionStatus::Failure => Deletion::failure(id.0),
         }))
     }
+
+    /// Lists the user's deletions of the subscription, newest first.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn deletions_get_by_subscription(
+        &self,
+        user: &User,
+        id: SubscriptionId,
+    ) -> anyhow::Result<Vec<RowDeletion>> {
+        sqlx::query_as!(
+            RowDeletion,
+            r#"--sql
+                SELECT
+                    id, user_id, subscription_id, status as "status: DeletionStatus",
+                    purge_after, created, updated, deleted
+                FROM
+                    task_deletions
+                WHERE
+                    user_id = ?1 AND subscription_id = ?2
+                ORDER BY created DESC
+            "#,
+            user.id,
+            id,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to run query: get deletions by subscription")
+    }
+
+    /// Lists all of the user's deletions, including the ones that were restored.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn deletions_get_all(&self, user: &User) -> anyhow::Result<Vec<RowDeletion>> {
+        sqlx::query_as!(
+            RowDeletion,
+            r#"--sql
+                SELECT
+                    id, user_id, subscription_id, status as "status: DeletionStatus",
+                    purge_after, created, updated, deleted
+                FROM
+                    task_deletions
+                WHERE
+                    user_id = ?1
+                ORDER BY created ASC
+            "#,
+            user.id,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn deletion_get_row(&self, id: DeletionId) -> anyhow::Result<Option<RowDeletion>> {
+        sqlx::query_as!(
+            RowDeletion,
+            r#"--sql
+                SELECT
+                    id, user_id, subscription_id, status as "status: DeletionStatus",
+                    purge_after, created, updated, deleted
+                FROM
+                    task_deletions
+                WHERE
+                    id = ?1
+            "#,
+            id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get deletion")
+    }
+
+    /// Whether the user is subscribed to the deleted subscription again, in which case it must
+    /// not be purged.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn deletion_is_resubscribed(&self, deletion: &RowDeletion) -> anyhow::Result<bool> {
+        let row = sqlx::query!(
+            r#"--sql
+                SELECT
+                    deleted IS NULL as "subscribed!: bool"
+                FROM
+                    user_subscriptions
+                WHERE
+                    user_id = ?1 AND subscription_id = ?2
+            "#,
+            deletion.user_id,
+            deletion.subscription_id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to run query: get deleted user subscription")?;
+
+        Ok(row.is_some_and(|row| row.subscribed))
+    }
+
+    /// Removes everything the user kept about the subscription: the subscription itself, its
+    /// tags and its history.
+    ///
+    /// The shared subscription, its feeds and GUIDs are left for the other users.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn deletion_purge(&self, deletion: &RowDeletion) -> anyhow::Result<()> {
+        let now = OffsetDateTime::now_utc();
+
+        let mut tx = self.pool.begin().await?;
+
+        // Removed by hand, foreign keys may not be enforced.
+        sqlx::query!(
+            r#"--sql
+                DELETE FROM user_subscription_tags
+                WHERE user_id = ?1 AND subscription_id = ?2
+            "#,
+            deletion.user_id,
+            deletion.subscription_id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: delete user subscription tags")?;
+
+        sqlx::query!(
+            r#"--sql
+                DELETE FROM subscription_events
+                WHERE user_id = ?1 AND subscription_id = ?2
+            "#,
+            deletion.user_id,
+            deletion.subscription_id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: delete subscription events")?;
+
+        sqlx::query!(
+            r#"--sql
+                DELETE FROM user_subscriptions
+                WHERE user_id = ?1 AND subscription_id = ?2
+            "#,
+            deletion.user_id,
+            deletion.subscription_id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: delete user subscription")?;
+
+        sqlx::query!(
+            r#"--sql
+                UPDATE task_deletions
+                SET status = ?2, updated = ?3
+                WHERE id = ?1
+            "#,
+            deletion.id,
+            DeletionStatus::Success,
+            now,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: complete deletion")?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn deletion_fail(&self, id: DeletionId) -> anyhow::Result<()> {
+        let now = OffsetDateTime::now_utc();
+
+        sqlx::query!(
+            r#"--sql
+                UPDATE task_deletions
+                SET status = ?2, updated = ?3
+                WHERE id = ?1
+            "#,
+            id,
+            DeletionStatus::Failure,
+            now,
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to run query: fail deletion")?;
+
+        Ok(())
+    }
+
+    /// Puts a failed deletion back to pending, for when its job is retried.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn deletion_reset(&self, id: DeletionId) -> anyhow::Result<()> {
+        let now = OffsetDateTime::now_utc();
+
+        sqlx::query!(
+            r#"--sql
+                UPDATE task_deletions
+                SET status = ?2, updated = ?3
+                WHERE id = ?1 AND status = ?4
+            "#,
+            id,
+            DeletionStatus::Pending,
+            now,
+            DeletionStatus::Failure,
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to run query: reset deletion")?;
+
+        Ok(())
+    }
 }
@@ -1,3 +1,62 @@
-use crate::database::Database;
+use anyhow::Context as _;
+use time::OffsetDateTime;
+use uuid::Uuid;
 
-impl Database {}
+use crate::{
+    database::{
+        subscription::{
+            history::{self, Origin},
+            SubscriptionId,
+        },
+        Database,
+    },
+    models::subscriptions::{Client, EventKind},
+};
+
+impl Database {
+    /// Finishes an identification, recording the GUID the feed gave itself if there is one.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn identification_complete(
+        &self,
+        subscription_id: SubscriptionId,
+        guid: Option<Uuid>,
+    ) -> anyhow::Result<()> {
+        let now = OffsetDateTime::now_utc();
+
+        let mut tx = self.pool.begin().await?;
+
+        if let Some(guid) = guid {
+            let result = sqlx::query!(
+                r#"--sql
+                    INSERT INTO subscription_guids (subscription_id, guid, updated)
+                    VALUES (?1, ?2, ?3)
+                    ON CONFLICT DO NOTHING
+                "#,
+                subscription_id,
+                guid,
+                now,
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to run query: create identified subscription guid")?;
+
+            if result.rows_affected() != 0 {
+                history::record(
+                    &mut *tx,
+                    None,
+                    subscription_id,
+                    EventKind::GuidChanged,
+                    Origin::new(Client::Server),
+                    None,
+                    Some(guid),
+                )
+                .await?;
+            }
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
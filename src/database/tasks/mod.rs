@@ -1,6 +1,10 @@
 pub mod deletion;
 pub mod identification;
 
+use time::OffsetDateTime;
+
+use crate::{database::subscription::SubscriptionId, models::subscriptions::DeletionStatus};
+
 #[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, sqlx::Type)]
 #[serde(transparent)]
 #[sqlx(transparent)]
@@ -12,12 +16,15 @@ impl From<i64> for DeletionId {
     }
 }
 
-#[derive(Debug, Clone, Copy, sqlx::Type)]
-#[sqlx(transparent)]
-pub struct IdentificationId(pub i64);
-
-impl From<i64> for IdentificationId {
-    fn from(value: i64) -> Self {
-        Self(value)
-    }
+pub struct RowDeletion {
+    pub id: DeletionId,
+    pub user_id: i64,
+    pub subscription_id: SubscriptionId,
+    pub status: DeletionStatus,
+    /// When the subscription is purged, missing for deletions from before the grace period.
+    pub purge_after: Option<OffsetDateTime>,
+    pub created: OffsetDateTime,
+    pub updated: Option<OffsetDateTime>,
+    /// When the deletion was undone.
+    pub deleted: Option<OffsetDateTime>,
 }
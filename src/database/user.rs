@@ -1,9 +1,27 @@
+use anyhow::Context as _;
 use argon2::{
     password_hash::SaltString, Argon2, PasswordHash, PasswordHasher as _, PasswordVerifier as _,
 };
 use rand::rngs::OsRng;
+use sqlx::SqliteConnection;
+use time::OffsetDateTime;
 
-use crate::database::Database;
+use crate::database::{
+    jobs::{self, Job},
+    subscription::SubscriptionId,
+    Database,
+};
+
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, sqlx::Type)]
+#[serde(transparent)]
+#[sqlx(transparent)]
+pub struct UserId(pub i64);
+
+impl From<i64> for UserId {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
 
 #[derive(sqlx::FromRow)]
 pub struct User {
@@ -13,6 +31,12 @@ pub struct User {
     pub password_hash: String,
 }
 
+pub struct RowProfile {
+    pub username: String,
+    pub email: String,
+    pub created: OffsetDateTime,
+}
+
 impl User {
     #[tracing::instrument(skip_all)]
     #[autometrics::autometrics]
@@ -29,6 +53,16 @@ impl User {
     }
 }
 
+pub(in crate::database) fn hash_password(password: &str) -> anyhow::Result<String> {
+    // TODO: use a pepper
+    let salt = SaltString::generate(&mut OsRng);
+    let argon2 = Argon2::default();
+
+    Ok(argon2
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
 impl Database {
     #[tracing::instrument(skip_all, err)]
     #[autometrics::autometrics]
@@ -42,12 +76,7 @@ impl Database {
             id: i64,
         }
 
-        // TODO: use a pepper
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let password_hash = argon2
-            .hash_password(password.as_bytes(), &salt)?
-            .to_string();
+        let password_hash = hash_password(password)?;
 
         let wrapper = sqlx::query_as!(
             Wrapper,
@@ -62,6 +91,8 @@ impl Database {
         Ok(wrapper.id)
     }
 
+    /// Looks up a user by their id, accounts disabled by an operator are left out while ones
+    /// waiting to be deleted are kept for the deletion job.
     #[tracing::instrument(skip_all, err)]
     #[autometrics::autometrics]
     pub async fn user_get_by_id(&self, id: i64) -> anyhow::Result<Option<User>> {
@@ -70,7 +101,7 @@ impl Database {
             r#"--sql
                 SELECT id, username, email, password_hash
                 FROM users
-                WHERE id = ?
+                WHERE id = ? AND disabled IS NULL
                 LIMIT 1
             "#,
             id
@@ -80,6 +111,8 @@ impl Database {
         .map_err(anyhow::Error::from)
     }
 
+    /// Looks up a user that can still log in, accounts waiting to be deleted or disabled by an
+    /// operator are left out.
     #[tracing::instrument(skip_all, err)]
     #[autometrics::autometrics]
     pub async fn user_get_by_username(&self, username: &str) -> anyhow::Result<Option<User>> {
@@ -88,7 +121,7 @@ impl Database {
             r#"--sql
                 SELECT id, username, email, password_hash
                 FROM users
-                WHERE username = ?
+                WHERE username = ? AND deleted IS NULL AND disabled IS NULL
                 LIMIT 1
             "#,
             username
@@ -97,4 +130,688 @@ impl Database {
         .await
         .map_err(anyhow::Error::from)
     }
+
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn user_get_profile(&self, user: &User) -> anyhow::Result<RowProfile> {
+        sqlx::query_as!(
+            RowProfile,
+            r#"--sql
+                SELECT username, email, created
+                FROM users
+                WHERE id = ?
+                LIMIT 1
+            "#,
+            user.id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+    }
+
+    /// Disables or re-enables the account, disabling also logs the user out everywhere.
+    ///
+    /// Returns `false` if there is no such user, accounts waiting to be deleted included.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn user_set_disabled(&self, username: &str, disabled: bool) -> anyhow::Result<bool> {
+        let now = OffsetDateTime::now_utc();
+        let disabled = disabled.then_some(now);
+
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query!(
+            r#"--sql
+                UPDATE users
+                SET disabled = ?2, updated = ?3
+                WHERE username = ?1 AND deleted IS NULL
+            "#,
+            username,
+            disabled,
+            now,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: set user disabled")?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        if disabled.is_some() {
+            sqlx::query!(
+                r#"--sql
+                    DELETE FROM user_sessions
+                    WHERE user_id IN (SELECT id FROM users WHERE username = ?1)
+                "#,
+                username,
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to run query: delete user sessions")?;
+        }
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Replaces the user's password and logs them out everywhere, app passwords included.
+    ///
+    /// Returns `false` if there is no such user, accounts waiting to be deleted included.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn user_set_password(&self, username: &str, password: &str) -> anyhow::Result<bool> {
+        let now = OffsetDateTime::now_utc();
+        let password_hash = hash_password(password)?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query!(
+            r#"--sql
+                UPDATE users
+                SET password_hash = ?2, updated = ?3
+                WHERE username = ?1 AND deleted IS NULL
+            "#,
+            username,
+            password_hash,
+            now,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: set user password")?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            r#"--sql
+                DELETE FROM user_sessions
+                WHERE user_id IN (SELECT id FROM users WHERE username = ?1)
+            "#,
+            username,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: delete user sessions")?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Marks the user as deleted and logs them out everywhere, the account itself is removed by
+    /// the queued job.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn user_delete_request(&self, user: &User) -> anyhow::Result<()> {
+        let now = OffsetDateTime::now_utc();
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"--sql
+                UPDATE users
+                SET deleted = ?2, updated = ?2
+                WHERE id = ?1
+            "#,
+            user.id,
+            now,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: mark user deleted")?;
+
+        sqlx::query!(
+            r#"--sql
+                DELETE FROM user_sessions
+                WHERE user_id = ?1
+            "#,
+            user.id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: delete user sessions")?;
+
+        jobs::enqueue(
+            &mut *tx,
+            &Job::AccountDeletion {
+                user_id: UserId(user.id),
+            },
+            now,
+        )
+        .await
+        .context("Failed to queue account deletion")?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Removes the user along with everything that belonged to them, then the shared
+    /// subscriptions (and their podcasts) the user had that nobody else references.
+    ///
+    /// `confirmation` is queued along with it, so the user only hears back once it's done.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn user_purge(&self, user: &User, confirmation: Option<&Job>) -> anyhow::Result<()> {
+        let now = OffsetDateTime::now_utc();
+
+        let mut tx = self.pool.begin().await?;
+
+        let subscriptions = sqlx::query!(
+            r#"--sql
+                SELECT subscription_id as "subscription_id: SubscriptionId"
+                FROM user_subscriptions
+                WHERE user_id = ?1
+                UNION
+                SELECT subscription_id as "subscription_id: SubscriptionId"
+                FROM task_deletions
+                WHERE user_id = ?1
+            "#,
+            user.id,
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .context("Failed to run query: get user subscriptions")?
+        .into_iter()
+        .map(|row| row.subscription_id)
+        .collect::<Vec<_>>();
+
+        // Removed by hand, foreign keys may not be enforced.
+        sqlx::query!(
+            r#"--sql
+                DELETE FROM user_subscription_tags
+                WHERE user_id = ?1
+            "#,
+            user.id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: delete user subscription tags")?;
+
+        sqlx::query!(
+            r#"--sql
+                DELETE FROM tag
+                WHERE user_id = ?1
+            "#,
+            user.id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: delete user tags")?;
+
+        sqlx::query!(
+            r#"--sql
+                DELETE FROM subscription_events
+                WHERE user_id = ?1
+            "#,
+            user.id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: delete user subscription events")?;
+
+        sqlx::query!(
+            r#"--sql
+                DELETE FROM task_deletions
+                WHERE user_id = ?1
+            "#,
+            user.id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: delete user deletions")?;
+
+        sqlx::query!(
+            r#"--sql
+                DELETE FROM user_subscriptions
+                WHERE user_id = ?1
+            "#,
+            user.id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: delete user subscriptions")?;
+
+        sqlx::query!(
+            r#"--sql
+                DELETE FROM episode_actions
+                WHERE user_id = ?1
+            "#,
+            user.id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: delete user episode actions")?;
+
+        sqlx::query!(
+            r#"--sql
+                DELETE FROM devices
+                WHERE user_id = ?1
+            "#,
+            user.id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: delete user devices")?;
+
+        sqlx::query!(
+            r#"--sql
+                DELETE FROM login_flows
+                WHERE user_id = ?1
+            "#,
+            user.id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: delete user login flows")?;
+
+        sqlx::query!(
+            r#"--sql
+                DELETE FROM exports
+                WHERE user_id = ?1
+            "#,
+            user.id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: delete user exports")?;
+
+        sqlx::query!(
+            r#"--sql
+                DELETE FROM user_sessions
+                WHERE user_id = ?1
+            "#,
+            user.id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: delete user sessions")?;
+
+        sqlx::query!(
+            r#"--sql
+                DELETE FROM users
+                WHERE id = ?1
+            "#,
+            user.id,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to run query: delete user")?;
+
+        collect_garbage(&mut *tx, &subscriptions).await?;
+
+        if let Some(confirmation) = confirmation {
+            jobs::enqueue(&mut *tx, confirmation, now)
+                .await
+                .context("Failed to queue account deletion confirmation")?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
+
+/// Removes the given shared subscriptions if no user (or deletion) references them any more, then
+/// the feed fetches, WebSub subscriptions and podcasts of their feeds.
+async fn collect_garbage(
+    conn: &mut SqliteConnection,
+    subscriptions: &[SubscriptionId],
+) -> anyhow::Result<()> {
+    let subscriptions = serde_json::to_string(
+        &subscriptions
+            .iter()
+            .map(|subscription| subscription.0)
+            .collect::<Vec<_>>(),
+    )?;
+
+    let unused = sqlx::query!(
+        r#"--sql
+            SELECT value as "id!: i64"
+            FROM json_each(?1)
+            WHERE
+                value NOT IN (SELECT subscription_id FROM user_subscriptions)
+                AND value NOT IN (SELECT subscription_id FROM task_deletions)
+        "#,
+        subscriptions,
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .context("Failed to run query: get unused subscriptions")?
+    .into_iter()
+    .map(|row| row.id)
+    .collect::<Vec<_>>();
+
+    if unused.is_empty() {
+        return Ok(());
+    }
+
+    let unused = serde_json::to_string(&unused)?;
+
+    let feeds = sqlx::query!(
+        r#"--sql
+            SELECT feed
+            FROM subscription_feeds
+            WHERE subscription_id IN (SELECT value FROM json_each(?1))
+        "#,
+        unused,
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .context("Failed to run query: get unused subscription feeds")?
+    .into_iter()
+    .map(|row| row.feed)
+    .collect::<Vec<_>>();
+    let feeds = serde_json::to_string(&feeds)?;
+
+    sqlx::query!(
+        r#"--sql
+            UPDATE episode_actions
+            SET subscription_id = NULL
+            WHERE subscription_id IN (SELECT value FROM json_each(?1))
+        "#,
+        unused,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: detach episode actions from unused subscriptions")?;
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM subscription_events
+            WHERE subscription_id IN (SELECT value FROM json_each(?1))
+        "#,
+        unused,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete unused subscription events")?;
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM subscription_feeds
+            WHERE subscription_id IN (SELECT value FROM json_each(?1))
+        "#,
+        unused,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete unused subscription feeds")?;
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM subscription_guids
+            WHERE subscription_id IN (SELECT value FROM json_each(?1))
+        "#,
+        unused,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete unused subscription guids")?;
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM subscriptions
+            WHERE id IN (SELECT value FROM json_each(?1))
+        "#,
+        unused,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete unused subscriptions")?;
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM feed_fetches
+            WHERE
+                feed IN (SELECT value FROM json_each(?1))
+                AND feed NOT IN (SELECT feed FROM subscription_feeds)
+        "#,
+        feeds,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete unused feed fetches")?;
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM websub_subscriptions
+            WHERE
+                feed IN (SELECT value FROM json_each(?1))
+                AND feed NOT IN (SELECT feed FROM subscription_feeds)
+        "#,
+        feeds,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete unused websub subscriptions")?;
+
+    // Podcasts are only reachable through the feeds of subscriptions, one can have feeds that
+    // other subscriptions still use.
+    let podcasts = sqlx::query!(
+        r#"--sql
+            SELECT DISTINCT podcast_id
+            FROM podcast_feed
+            WHERE
+                feed_url IN (SELECT value FROM json_each(?1))
+                AND podcast_id NOT IN (
+                    SELECT pf.podcast_id FROM podcast_feed pf
+                    INNER JOIN subscription_feeds sf ON sf.feed = pf.feed_url
+                )
+        "#,
+        feeds,
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .context("Failed to run query: get unused podcasts")?
+    .into_iter()
+    .map(|row| row.podcast_id)
+    .collect::<Vec<_>>();
+
+    if podcasts.is_empty() {
+        return Ok(());
+    }
+
+    let podcasts = serde_json::to_string(&podcasts)?;
+
+    // The episodes (and what hangs off of them) go first.
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM podcast_value_recipients
+            WHERE value_id IN (
+                SELECT id FROM podcast_values
+                WHERE podcast_id IN (SELECT value FROM json_each(?1))
+            )
+        "#,
+        podcasts,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete unused podcast value recipients")?;
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM podcast_values
+            WHERE podcast_id IN (SELECT value FROM json_each(?1))
+        "#,
+        podcasts,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete unused podcast values")?;
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM podcast_persons
+            WHERE podcast_id IN (SELECT value FROM json_each(?1))
+        "#,
+        podcasts,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete unused podcast persons")?;
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM podcast_fundings
+            WHERE podcast_id IN (SELECT value FROM json_each(?1))
+        "#,
+        podcasts,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete unused podcast fundings")?;
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM podcast_transcripts
+            WHERE episode_id IN (
+                SELECT id FROM podcast_episode
+                WHERE podcast_id IN (SELECT value FROM json_each(?1))
+            )
+        "#,
+        podcasts,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete unused podcast transcripts")?;
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM episode_search
+            WHERE rowid IN (
+                SELECT id FROM podcast_episode
+                WHERE podcast_id IN (SELECT value FROM json_each(?1))
+            )
+        "#,
+        podcasts,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete unused episode search index")?;
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM podcast_episode
+            WHERE podcast_id IN (SELECT value FROM json_each(?1))
+        "#,
+        podcasts,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete unused podcast episodes")?;
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM podcast_search
+            WHERE rowid IN (SELECT value FROM json_each(?1))
+        "#,
+        podcasts,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete unused podcast search index")?;
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM podcast_guid
+            WHERE podcast_id IN (SELECT value FROM json_each(?1))
+        "#,
+        podcasts,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete unused podcast guids")?;
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM podcast_tag
+            WHERE podcast_id IN (SELECT value FROM json_each(?1))
+        "#,
+        podcasts,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete unused podcast tags")?;
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM podcast_feed
+            WHERE podcast_id IN (SELECT value FROM json_each(?1))
+        "#,
+        podcasts,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete unused podcast feeds")?;
+
+    sqlx::query!(
+        r#"--sql
+            DELETE FROM podcast
+            WHERE id IN (SELECT value FROM json_each(?1))
+        "#,
+        podcasts,
+    )
+    .execute(&mut *conn)
+    .await
+    .context("Failed to run query: delete unused podcasts")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::Database;
+
+    #[sqlx::test(fixtures("../../fixtures/dummy.sql"))]
+    async fn set_disabled(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool).await.unwrap();
+        let user = db.user_get_by_id(Database::USER_ID).await.unwrap().unwrap();
+
+        assert!(db.user_set_disabled("example", true).await.unwrap());
+        assert!(db.user_get_by_username("example").await.unwrap().is_none());
+        assert!(db
+            .user_get_by_id(Database::USER_ID)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(db
+            .session_get_by_token(Database::TOKEN)
+            .await
+            .unwrap()
+            .is_none());
+
+        // Even one created afterwards doesn't let them back in.
+        let (token, _) = db.session_crate(&user).await.unwrap();
+        assert!(db.session_get_by_token(&token).await.unwrap().is_none());
+
+        assert!(db.user_set_disabled("example", false).await.unwrap());
+        assert!(db.user_get_by_username("example").await.unwrap().is_some());
+
+        assert!(!db.user_set_disabled("missing", true).await.unwrap());
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/dummy.sql"))]
+    async fn set_password(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool).await.unwrap();
+
+        assert!(db.user_set_password("example", "hunter2").await.unwrap());
+
+        let user = db.user_get_by_username("example").await.unwrap().unwrap();
+        assert!(user.verify("hunter2"));
+        assert!(db
+            .session_get_by_token(Database::TOKEN)
+            .await
+            .unwrap()
+            .is_none());
+
+        assert!(!db.user_set_password("missing", "hunter2").await.unwrap());
+    }
 }
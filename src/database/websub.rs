@@ -0,0 +1,235 @@
+use anyhow::Context as _;
+use time::OffsetDateTime;
+
+use crate::database::Database;
+
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, sqlx::Type)]
+#[serde(transparent)]
+#[sqlx(transparent)]
+pub struct WebSubId(pub i64);
+
+impl From<i64> for WebSubId {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "websub_status")]
+#[sqlx(rename_all = "lowercase")]
+pub enum WebSubStatus {
+    /// Waiting for the hub to verify our intent.
+    Pending,
+    Verified,
+    Denied,
+}
+
+pub struct RowWebSub {
+    pub id: WebSubId,
+    pub feed: String,
+    pub hub: String,
+    pub topic: String,
+    pub secret: String,
+    pub status: WebSubStatus,
+    pub lease_expires: Option<OffsetDateTime>,
+}
+
+impl RowWebSub {
+    /// Whether the hub is allowed to push to us, a renewal waiting to be verified still has the
+    /// lease it was granted before.
+    pub fn is_active(&self, now: OffsetDateTime) -> bool {
+        match self.status {
+            WebSubStatus::Verified => true,
+            WebSubStatus::Pending => self.lease_expires.is_some_and(|expires| expires > now),
+            WebSubStatus::Denied => false,
+        }
+    }
+}
+
+impl Database {
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn websub_get_by_id(&self, id: WebSubId) -> anyhow::Result<Option<RowWebSub>> {
+        sqlx::query_as!(
+            RowWebSub,
+            r#"--sql
+                SELECT
+                    id, feed, hub, topic, secret, status as "status: WebSubStatus", lease_expires
+                FROM
+                    websub_subscriptions
+                WHERE
+                    id = ?1
+            "#,
+            id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get websub subscription by id")
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn websub_get_by_feed(&self, feed: &str) -> anyhow::Result<Option<RowWebSub>> {
+        sqlx::query_as!(
+            RowWebSub,
+            r#"--sql
+                SELECT
+                    id, feed, hub, topic, secret, status as "status: WebSubStatus", lease_expires
+                FROM
+                    websub_subscriptions
+                WHERE
+                    feed = ?1
+            "#,
+            feed,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get websub subscription by feed")
+    }
+
+    /// Records a subscription request sent to a hub.
+    ///
+    /// Every request has to be verified by the hub again, renewing a subscription at the same hub
+    /// and topic keeps its current lease until then.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn websub_request(
+        &self,
+        feed: &str,
+        hub: &str,
+        topic: &str,
+        secret: &str,
+    ) -> anyhow::Result<RowWebSub> {
+        let now = OffsetDateTime::now_utc();
+
+        sqlx::query_as!(
+            RowWebSub,
+            r#"--sql
+                INSERT INTO websub_subscriptions (feed, hub, topic, secret, status)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT (feed) DO UPDATE
+                SET
+                    status = excluded.status,
+                    lease_expires = CASE
+                        WHEN hub = excluded.hub AND topic = excluded.topic THEN lease_expires
+                        ELSE NULL
+                    END,
+                    hub = excluded.hub,
+                    topic = excluded.topic,
+                    secret = excluded.secret,
+                    updated = ?6
+                RETURNING
+                    id, feed, hub, topic, secret, status as "status: WebSubStatus", lease_expires
+            "#,
+            feed,
+            hub,
+            topic,
+            secret,
+            WebSubStatus::Pending,
+            now,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: request websub subscription")
+    }
+
+    /// Marks the subscription as verified by the hub, for as long as the hub granted.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn websub_verify(
+        &self,
+        id: WebSubId,
+        lease_expires: Option<OffsetDateTime>,
+    ) -> anyhow::Result<()> {
+        let now = OffsetDateTime::now_utc();
+
+        sqlx::query!(
+            r#"--sql
+                UPDATE websub_subscriptions
+                SET status = ?2, lease_expires = ?3, updated = ?4
+                WHERE id = ?1
+            "#,
+            id,
+            WebSubStatus::Verified,
+            lease_expires,
+            now,
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to run query: verify websub subscription")?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn websub_deny(&self, id: WebSubId) -> anyhow::Result<()> {
+        let now = OffsetDateTime::now_utc();
+
+        sqlx::query!(
+            r#"--sql
+                UPDATE websub_subscriptions
+                SET status = ?2, lease_expires = NULL, updated = ?3
+                WHERE id = ?1
+            "#,
+            id,
+            WebSubStatus::Denied,
+            now,
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to run query: deny websub subscription")?;
+
+        Ok(())
+    }
+
+    /// Returns the subscriptions that need to be renewed, those with a lease ending before
+    /// `expires_before` and those the hub never verified since `pending_before`.
+    ///
+    /// Feeds nobody is subscribed to anymore are left to expire.
+    #[tracing::instrument(skip_all, err)]
+    #[autometrics::autometrics]
+    pub async fn websubs_get_renewable(
+        &self,
+        expires_before: OffsetDateTime,
+        pending_before: OffsetDateTime,
+        limit: i64,
+    ) -> anyhow::Result<Vec<RowWebSub>> {
+        sqlx::query_as!(
+            RowWebSub,
+            r#"--sql
+                SELECT
+                    ws.id, ws.feed, ws.hub, ws.topic, ws.secret,
+                    ws.status as "status: WebSubStatus", ws.lease_expires
+                FROM
+                    websub_subscriptions ws
+                WHERE
+                    (
+                        (ws.status = 'verified' AND JULIANDAY(ws.lease_expires) <= JULIANDAY(?1))
+                        OR (
+                            ws.status = 'pending'
+                            AND JULIANDAY(COALESCE(ws.updated, ws.created)) <= JULIANDAY(?2)
+                        )
+                    )
+                    AND EXISTS (
+                        SELECT 1
+                        FROM subscription_feeds sf
+                        INNER JOIN user_subscriptions us ON us.subscription_id = sf.subscription_id
+                        WHERE sf.feed = ws.feed AND us.deleted IS NULL
+                    )
+                ORDER BY JULIANDAY(ws.lease_expires) ASC
+                LIMIT ?3
+            "#,
+            expires_before,
+            pending_before,
+            limit,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .context("Failed to run query: get renewable websub subscriptions")
+    }
+}
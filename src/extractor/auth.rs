@@ -4,15 +4,24 @@ use axum::{
     http::{request::Parts, StatusCode},
     response::IntoResponse,
 };
-use axum_extra::TypedHeader;
-use headers::{authorization::Bearer, Authorization};
-use time::OffsetDateTime;
+use axum_extra::{
+    extract::{
+        cookie::{Cookie, Key, SameSite},
+        PrivateCookieJar,
+    },
+    TypedHeader,
+};
+use headers::{
+    authorization::{Basic, Bearer},
+    Authorization,
+};
+use time::{Duration, OffsetDateTime};
 
 use crate::database::user::User;
 
 #[derive(Debug, thiserror::Error)]
 pub enum SessionRejection {
-    #[error("Missing authorization header")]
+    #[error("Missing authorization header or session cookie")]
     MissingHeader,
     #[error("Unauthorized")]
     Unauthorized,
@@ -44,6 +53,75 @@ impl Session {
     }
 }
 
+/// Builds the cookie that keeps a browser (or cookie aware client) logged in.
+pub fn session_cookie(name: String, token: String, expires: OffsetDateTime) -> Cookie<'static> {
+    let mut cookie = Cookie::new(name, token);
+    cookie.set_http_only(true);
+    cookie.set_path("/");
+    cookie.set_same_site(SameSite::Strict);
+    cookie.set_expires(expires);
+
+    cookie
+}
+
+/// Authenticates a request using HTTP basic auth, which is what gpodder.net and Nextcloud clients
+/// send.
+///
+/// The password can either be the user's password or an app password (a session token) handed out
+/// by the Nextcloud login flow. Passwords are checked on every request so the session only needs
+/// to outlive the request.
+async fn basic_session(
+    state: &crate::SyncState,
+    basic: &Basic,
+) -> Result<Session, SessionRejection> {
+    let session = state
+        .db
+        .session_get_by_token(basic.password())
+        .await
+        .map_err(|_| SessionRejection::Unauthorized)?;
+    if let Some(session) = session {
+        if session.user.username == basic.username() {
+            return Ok(session);
+        }
+    }
+
+    let Some(user) = state
+        .db
+        .user_get_by_username(basic.username())
+        .await
+        .map_err(|_| SessionRejection::Unauthorized)?
+    else {
+        return Err(SessionRejection::Unauthorized);
+    };
+
+    if !user.verify(basic.password()) {
+        return Err(SessionRejection::Unauthorized);
+    }
+
+    Ok(Session {
+        expires: OffsetDateTime::now_utc() + Duration::minutes(5),
+        user,
+    })
+}
+
+/// Reads the session token from the `Authorization` header used by API clients, falling back to
+/// the private session cookie set by the web login.
+async fn session_token(
+    parts: &mut Parts,
+    state: &crate::SyncState,
+) -> Result<String, SessionRejection> {
+    if let Ok(header) = TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state).await
+    {
+        return Ok(header.0.token().to_string());
+    }
+
+    let Ok(jar) = PrivateCookieJar::<Key>::from_request_parts(parts, state).await;
+
+    jar.get(&state.cfg.session_name)
+        .map(|cookie| cookie.value().to_string())
+        .ok_or(SessionRejection::MissingHeader)
+}
+
 #[async_trait::async_trait]
 impl FromRequestParts<crate::SyncState> for Session {
     type Rejection = SessionRejection;
@@ -52,13 +130,11 @@ impl FromRequestParts<crate::SyncState> for Session {
         parts: &mut Parts,
         state: &crate::SyncState,
     ) -> Result<Self, Self::Rejection> {
-        let header = TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state)
-            .await
-            .map_err(|_| SessionRejection::MissingHeader)?;
+        let token = session_token(parts, state).await?;
 
         let Some(session) = state
             .db
-            .session_get_by_token(header.0.token())
+            .session_get_by_token(&token)
             .await
             .map_err(|_| SessionRejection::Unauthorized)?
         else {
@@ -68,3 +144,27 @@ impl FromRequestParts<crate::SyncState> for Session {
         Ok(session)
     }
 }
+
+/// A [`Session`] that can also be authenticated with HTTP basic auth, only for the gpodder.net and
+/// Nextcloud compatibility routes since every basic auth request hashes the password.
+pub struct BasicSession(pub Session);
+
+#[async_trait::async_trait]
+impl FromRequestParts<crate::SyncState> for BasicSession {
+    type Rejection = SessionRejection;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &crate::SyncState,
+    ) -> Result<Self, Self::Rejection> {
+        if let Ok(TypedHeader(Authorization(basic))) =
+            TypedHeader::<Authorization<Basic>>::from_request_parts(parts, state).await
+        {
+            return basic_session(state, &basic).await.map(BasicSession);
+        }
+
+        Session::from_request_parts(parts, state)
+            .await
+            .map(BasicSession)
+    }
+}
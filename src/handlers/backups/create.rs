@@ -0,0 +1,84 @@
+use axum::extract::State;
+use axum_extra::either::Either;
+
+use crate::{
+    models::{backups::Backup, InternalError},
+    SyncState,
+};
+
+/// Takes a backup of the database while the server keeps running, removing the backups past the
+/// configured retention.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn create(State(sync): State<SyncState>) -> Either<Backup, InternalError> {
+    match sync.db.backup(&sync.cfg.backup).await {
+        Ok(backup) => Either::E1(backup),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to back up database");
+
+            Either::E2(InternalError)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Method, Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use http_body_util::BodyExt as _;
+    use tower::ServiceExt as _;
+
+    use crate::{
+        handlers::{backups::list, test_app},
+        models::backups::{Backup, Backups},
+    };
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router.route("/backups", get(list::list).post(super::create))
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    async fn request<T: serde::de::DeserializeOwned>(app: &Router, method: Method) -> T {
+        let request = Request::builder()
+            .method(method)
+            .uri("/backups")
+            .body(Body::empty())
+            .expect("Failed to build request");
+        let response = app
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+
+        serde_json::from_slice(&body).expect("Failed to deserialize response body")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn create(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+
+        let backup: Backup = request(&app, Method::POST).await;
+        assert!(backup.size > 0);
+
+        let Backups { backups } = request(&app, Method::GET).await;
+        assert!(backups.contains(&backup));
+    }
+}
@@ -0,0 +1,22 @@
+use axum::extract::State;
+use axum_extra::either::Either;
+
+use crate::{
+    database::backup,
+    models::{backups::Backups, InternalError},
+    SyncState,
+};
+
+/// Lists the backups in the backup directory, newest first.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn list(State(sync): State<SyncState>) -> Either<Backups, InternalError> {
+    match backup::list(&sync.cfg.backup).await {
+        Ok(backups) => Either::E1(Backups { backups }),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to list backups");
+
+            Either::E2(InternalError)
+        }
+    }
+}
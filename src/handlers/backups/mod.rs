@@ -0,0 +1,12 @@
+//! Operator endpoints for database backups, only served on the private listener.
+
+pub mod create;
+pub mod list;
+
+use axum::routing;
+
+#[rustfmt::skip]
+pub fn app() -> axum::Router<crate::SyncState> {
+    axum::Router::new()
+        .route("/backups", routing::get(list::list).post(create::create))
+}
@@ -0,0 +1,152 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse as _, Response},
+};
+use axum_extra::extract::PrivateCookieJar;
+
+use crate::{
+    extractor::auth::{session_cookie, BasicSession},
+    handlers::gpodder::authorize,
+    SyncState,
+};
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn login(
+    State(sync): State<SyncState>,
+    jar: PrivateCookieJar,
+    session: Option<BasicSession>,
+    Path(username): Path<String>,
+) -> Response {
+    let session = match authorize(session, &username) {
+        Ok(session) => session,
+        Err(err) => return err.into_response(),
+    };
+
+    let (token, expires) = match sync.db.session_crate(&session.user).await {
+        Ok(pair) => pair,
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to create user session");
+
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let cookie = session_cookie(sync.cfg.session_name.clone(), token, expires);
+
+    (jar.add(cookie), StatusCode::OK).into_response()
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn logout(State(sync): State<SyncState>, jar: PrivateCookieJar) -> Response {
+    let Some(session) = jar.get(&sync.cfg.session_name) else {
+        return StatusCode::OK.into_response();
+    };
+
+    // Otherwise the token keeps working until it expires.
+    if let Err(err) = sync.db.session_delete_by_token(session.value()).await {
+        tracing::error!(err = ?err, "Failed to delete user session");
+
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    (jar.remove(session), StatusCode::OK).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+        routing::post,
+        Router,
+    };
+    use data_encoding::BASE64;
+    use tower::ServiceExt as _;
+
+    use crate::{database::Database, handlers::test_app};
+
+    const PASSWORD: &str = "correct horse battery staple";
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router
+                .route("/api/2/auth/:username/login.json", post(super::login))
+                .route("/api/2/auth/:username/logout.json", post(super::logout))
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn logout(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool.clone())
+            .await
+            .expect("Failed to create database");
+        db.user_set_password("example", PASSWORD)
+            .await
+            .expect("Failed to set password");
+        let user = db
+            .user_get_by_id(Database::USER_ID)
+            .await
+            .expect("Failed to get user")
+            .expect("Test user is missing");
+        let app = setup_app(pool).await;
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/api/2/auth/example/login.json")
+            .header(
+                header::AUTHORIZATION,
+                format!(
+                    "Basic {}",
+                    BASE64.encode(format!("example:{}", PASSWORD).as_bytes())
+                ),
+            )
+            .body(Body::empty())
+            .expect("Failed to build request");
+        let response = app
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("Failed to run request");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let cookie = response
+            .headers()
+            .get(header::SET_COOKIE)
+            .expect("Missing session cookie")
+            .to_str()
+            .expect("Session cookie is not text")
+            .split(';')
+            .next()
+            .expect("Session cookie is empty")
+            .to_string();
+        assert_eq!(
+            db.sessions_get_all(&user)
+                .await
+                .expect("Failed to get sessions")
+                .len(),
+            1
+        );
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/api/2/auth/example/logout.json")
+            .header(header::COOKIE, cookie)
+            .body(Body::empty())
+            .expect("Failed to build request");
+        let response = app.oneshot(request).await.expect("Failed to run request");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        assert!(db
+            .sessions_get_all(&user)
+            .await
+            .expect("Failed to get sessions")
+            .is_empty());
+    }
+}
@@ -0,0 +1,147 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse as _, Response},
+};
+
+use crate::{
+    extractor::auth::BasicSession,
+    handlers::gpodder::{authorize, strip_format},
+    models::{
+        gpodder::{Device, DeviceUpdate, Devices},
+        NotFound,
+    },
+    utils::json::Json,
+    SyncState,
+};
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn list(
+    State(sync): State<SyncState>,
+    session: Option<BasicSession>,
+    Path(username): Path<String>,
+) -> Response {
+    let Some(username) = strip_format(&username) else {
+        return NotFound.into_response();
+    };
+    let session = match authorize(session, username) {
+        Ok(session) => session,
+        Err(err) => return err.into_response(),
+    };
+
+    let devices = sync.db.devices_get_all(&session.user).await;
+    let changes = sync
+        .db
+        .subscriptions_get_changes(&session.user, None, None)
+        .await;
+
+    match (devices, changes) {
+        (Ok(devices), Ok(changes)) => {
+            let subscriptions = changes.iter().filter(|c| c.is_subscribed).count() as i64;
+
+            Devices(
+                devices
+                    .into_iter()
+                    .map(|device| Device::from_row(device, subscriptions))
+                    .collect(),
+            )
+            .into_response()
+        }
+        (Err(err), _) | (_, Err(err)) => {
+            tracing::error!(err = ?err, "Failed to retrieve user devices");
+
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn update(
+    State(sync): State<SyncState>,
+    session: Option<BasicSession>,
+    Path((username, device)): Path<(String, String)>,
+    Json(update): Json<DeviceUpdate>,
+) -> Response {
+    let Some(device) = strip_format(&device) else {
+        return NotFound.into_response();
+    };
+    let session = match authorize(session, &username) {
+        Ok(session) => session,
+        Err(err) => return err.into_response(),
+    };
+
+    let result = sync
+        .db
+        .device_upsert(
+            &session.user,
+            device,
+            update.caption.as_deref(),
+            update.kind.as_deref(),
+        )
+        .await;
+
+    match result {
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to update user device");
+
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        http::{Method, StatusCode},
+        routing::get,
+        Router,
+    };
+
+    use crate::{
+        handlers::test_app,
+        models::{gpodder::Devices, ApiError},
+        utils::test::TestBuilder,
+    };
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router.route("/api/2/devices/:username", get(super::list))
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn ok(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/api/2/devices/example.json";
+        let expected = Devices(vec![]);
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::GET)
+            .authorization(true)
+            .status(StatusCode::OK)
+            .run()
+            .await;
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn unauthorized(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/api/2/devices/example.json";
+        let expected = ApiError::unauthorized();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::GET)
+            .status(StatusCode::UNAUTHORIZED)
+            .run()
+            .await;
+    }
+}
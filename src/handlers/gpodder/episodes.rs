@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse as _, Response},
+};
+use time::OffsetDateTime;
+
+use crate::{
+    database::{
+        episode::{EpisodeActionFilter, NewEpisodeAction},
+        user::User,
+    },
+    extractor::auth::BasicSession,
+    handlers::gpodder::{authorize, strip_format},
+    models::{
+        gpodder::{parse_timestamp, EpisodeAction, EpisodeActions, UpdateUrls},
+        NotFound,
+    },
+    utils::json::Json,
+    SyncState,
+};
+
+/// Stores the episode actions uploaded by a client, registering any devices it hasn't seen yet.
+pub async fn store_episode_actions(
+    sync: &SyncState,
+    user: &User,
+    actions: Vec<EpisodeAction>,
+) -> anyhow::Result<()> {
+    let now = OffsetDateTime::now_utc();
+    let mut devices = HashMap::new();
+    let mut new_actions = Vec::with_capacity(actions.len());
+
+    for action in actions {
+        let device = match action.device {
+            Some(identifier) => match devices.get(&identifier) {
+                Some(id) => Some(*id),
+                None => {
+                    let device = sync.db.device_upsert(user, &identifier, None, None).await?;
+
+                    devices.insert(identifier, device.id);
+
+                    Some(device.id)
+                }
+            },
+            None => None,
+        };
+
+        new_actions.push(NewEpisodeAction {
+            podcast: action.podcast,
+            episode: action.episode,
+            guid: action.guid,
+            device,
+            action: action.action,
+            timestamp: action
+                .timestamp
+                .as_deref()
+                .and_then(parse_timestamp)
+                .unwrap_or(now),
+            started: action.started,
+            position: action.position,
+            total: action.total,
+        });
+    }
+
+    sync.db.episode_actions_create(user, new_actions).await
+}
+
+#[derive(serde::Deserialize)]
+pub struct GetParams {
+    pub podcast: Option<String>,
+    pub device: Option<String>,
+    pub since: Option<i64>,
+    #[serde(default)]
+    pub aggregated: bool,
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn get(
+    State(sync): State<SyncState>,
+    session: Option<BasicSession>,
+    Path(username): Path<String>,
+    Query(params): Query<GetParams>,
+) -> Response {
+    let Some(username) = strip_format(&username) else {
+        return NotFound.into_response();
+    };
+    let session = match authorize(session, username) {
+        Ok(session) => session,
+        Err(err) => return err.into_response(),
+    };
+
+    let device = match params.device {
+        Some(device) => match sync
+            .db
+            .device_get_by_identifier(&session.user, &device)
+            .await
+        {
+            Ok(Some(device)) => Some(device.id),
+            Ok(None) => return NotFound.into_response(),
+            Err(err) => {
+                tracing::error!(err = ?err, "Failed to retrieve user device");
+
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        },
+        None => None,
+    };
+
+    let now = OffsetDateTime::now_utc();
+    let filter = EpisodeActionFilter {
+        since: params
+            .since
+            .and_then(|since| OffsetDateTime::from_unix_timestamp(since).ok()),
+        podcast: params.podcast.as_deref(),
+        device,
+        // gpodder.net uses `device` to filter, not to skip the requesting device
+        exclude: None,
+        aggregated: params.aggregated,
+    };
+
+    match sync.db.episode_actions_get(&session.user, filter).await {
+        Ok(actions) => EpisodeActions {
+            actions: actions.into_iter().map(EpisodeAction::from).collect(),
+            timestamp: now.unix_timestamp(),
+        }
+        .into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user episode actions");
+
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn upload(
+    State(sync): State<SyncState>,
+    session: Option<BasicSession>,
+    Path(username): Path<String>,
+    Json(actions): Json<Vec<EpisodeAction>>,
+) -> Response {
+    let Some(username) = strip_format(&username) else {
+        return NotFound.into_response();
+    };
+    let session = match authorize(session, username) {
+        Ok(session) => session,
+        Err(err) => return err.into_response(),
+    };
+
+    let now = OffsetDateTime::now_utc();
+
+    if let Err(err) = store_episode_actions(&sync, &session.user, actions).await {
+        tracing::error!(err = ?err, "Failed to create user episode actions");
+
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    UpdateUrls {
+        timestamp: now.unix_timestamp(),
+        update_urls: vec![],
+    }
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use http_body_util::BodyExt as _;
+    use tower::ServiceExt as _;
+
+    use crate::{
+        database::Database,
+        handlers::test_app,
+        models::{gpodder::EpisodeActions, ApiError},
+        utils::test::TestBuilder,
+    };
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router.route(
+                "/api/2/episodes/:username",
+                get(super::get).post(super::upload),
+            )
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn unauthorized(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/api/2/episodes/example.json";
+        let expected = ApiError::unauthorized();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::GET)
+            .status(StatusCode::UNAUTHORIZED)
+            .run()
+            .await;
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn get_device(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+
+        let body = format!(
+            r#"[
+                {{"podcast":"{feed}","episode":"http://one.example.com/1.mp3","device":"phone","action":"download"}},
+                {{"podcast":"{feed}","episode":"http://one.example.com/2.mp3","device":"tablet","action":"download"}}
+            ]"#,
+            feed = Database::SUBSCRIPTION_1_FEED,
+        );
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/api/2/episodes/example.json")
+            .header(header::AUTHORIZATION, Database::test_token())
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .expect("Failed to build request");
+        let response = app
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("Failed to run request");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("/api/2/episodes/example.json?device=phone")
+            .header(header::AUTHORIZATION, Database::test_token())
+            .body(Body::empty())
+            .expect("Failed to build request");
+        let response = app.oneshot(request).await.expect("Failed to run request");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let actions: EpisodeActions =
+            serde_json::from_slice(&body).expect("Failed to deserialize response body");
+
+        assert_eq!(actions.actions.len(), 1);
+        assert_eq!(actions.actions[0].device.as_deref(), Some("phone"));
+    }
+}
@@ -0,0 +1,45 @@
+//! A compatibility layer for clients that speak the gpodder.net API instead of the Open Podcast
+//! API.
+//!
+//! Devices are tracked, but subscriptions are shared between all of a user's devices.
+
+pub mod auth;
+pub mod devices;
+pub mod episodes;
+pub mod subscriptions;
+
+use axum::routing;
+
+use crate::{
+    extractor::auth::{BasicSession, Session},
+    models::Unauthorized,
+};
+
+/// gpodder.net puts the response format at the end of the last path segment, only JSON is
+/// supported.
+fn strip_format(segment: &str) -> Option<&str> {
+    segment.strip_suffix(".json")
+}
+
+/// Checks the session is valid and belongs to the user named in the path.
+fn authorize(session: Option<BasicSession>, username: &str) -> Result<Session, Unauthorized> {
+    match session.map(|session| session.0) {
+        Some(session) if session.validate() && session.user.username == username => Ok(session),
+        _ => Err(Unauthorized),
+    }
+}
+
+#[rustfmt::skip]
+pub fn app() -> axum::Router<crate::SyncState> {
+    axum::Router::new()
+        // Authentication
+        .route("/api/2/auth/:username/login.json", routing::post(auth::login))
+        .route("/api/2/auth/:username/logout.json", routing::post(auth::logout))
+        // Devices
+        .route("/api/2/devices/:username", routing::get(devices::list))
+        .route("/api/2/devices/:username/:device", routing::post(devices::update))
+        // Subscriptions
+        .route("/api/2/subscriptions/:username/:device", routing::get(subscriptions::get).post(subscriptions::upload))
+        // Episode Actions
+        .route("/api/2/episodes/:username", routing::get(episodes::get).post(episodes::upload))
+}
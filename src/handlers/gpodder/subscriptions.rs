@@ -0,0 +1,328 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse as _, Response},
+};
+use time::OffsetDateTime;
+use url::Url;
+
+use crate::{
+    database::{device::DeviceId, subscription::history::Origin, user::User},
+    extractor::auth::BasicSession,
+    handlers::gpodder::{authorize, strip_format},
+    models::{
+        gpodder::{SubscriptionChanges, SubscriptionUpload, UpdateUrls},
+        subscriptions::Client,
+        NotFound, Validation,
+    },
+    utils::json::Json,
+    SyncState,
+};
+
+/// Lists the feeds the user subscribed to and unsubscribed from since the given unix timestamp,
+/// a missing (or zero) timestamp lists every current subscription.
+///
+/// Changes made by the requesting device are left out of a delta sync.
+pub async fn subscription_changes(
+    sync: &SyncState,
+    user: &User,
+    device: Option<DeviceId>,
+    since: Option<i64>,
+) -> anyhow::Result<SubscriptionChanges> {
+    let now = OffsetDateTime::now_utc();
+    let since = since
+        .filter(|since| *since > 0)
+        .and_then(|since| OffsetDateTime::from_unix_timestamp(since).ok());
+
+    let changes = sync
+        .db
+        .subscriptions_get_changes(user, since, device)
+        .await?;
+
+    let (add, remove): (Vec<_>, Vec<_>) =
+        changes.into_iter().partition(|change| change.is_subscribed);
+
+    Ok(SubscriptionChanges {
+        add: add.into_iter().map(|change| change.feed).collect(),
+        // a full sync only lists the current subscriptions
+        remove: match since {
+            Some(_) => remove.into_iter().map(|change| change.feed).collect(),
+            None => vec![],
+        },
+        timestamp: now.unix_timestamp(),
+    })
+}
+
+/// Applies the subscription changes uploaded by a client, returning the feed URLs that were
+/// rewritten while doing so.
+pub async fn apply_subscription_upload(
+    sync: &SyncState,
+    user: &User,
+    origin: Origin,
+    upload: SubscriptionUpload,
+) -> anyhow::Result<Vec<(String, String)>> {
+    let mut update_urls = Vec::new();
+
+    for feed in upload.add {
+        let Ok(feed_url) = Url::parse(&feed) else {
+            // an empty url tells the client the feed was ignored
+            update_urls.push((feed, String::new()));
+
+            continue;
+        };
+
+        sync.db
+            .subscription_create(user, origin, &feed_url, None)
+            .await?;
+
+        if feed_url.as_str() != feed {
+            update_urls.push((feed, feed_url.to_string()));
+        }
+    }
+
+    for feed in upload.remove {
+        let Some(row) = sync.db.subscription_get_id_by_feed(&feed).await? else {
+            continue;
+        };
+
+        sync.db
+            .subscription_set_subscribed(user, origin, row.subscription_id, false)
+            .await?;
+    }
+
+    Ok(update_urls)
+}
+
+#[derive(serde::Deserialize)]
+pub struct GetParams {
+    pub since: Option<i64>,
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn get(
+    State(sync): State<SyncState>,
+    session: Option<BasicSession>,
+    Path((username, device)): Path<(String, String)>,
+    Query(params): Query<GetParams>,
+) -> Response {
+    let Some(device) = strip_format(&device) else {
+        return NotFound.into_response();
+    };
+    let session = match authorize(session, &username) {
+        Ok(session) => session,
+        Err(err) => return err.into_response(),
+    };
+
+    // gpodder.net creates devices the first time they're used
+    let device = match sync
+        .db
+        .device_upsert(&session.user, device, None, None)
+        .await
+    {
+        Ok(device) => device,
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to register user device");
+
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    match subscription_changes(&sync, &session.user, Some(device.id), params.since).await {
+        Ok(changes) => changes.into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user subscription changes");
+
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn upload(
+    State(sync): State<SyncState>,
+    session: Option<BasicSession>,
+    Path((username, device)): Path<(String, String)>,
+    Json(upload): Json<SubscriptionUpload>,
+) -> Response {
+    let Some(device) = strip_format(&device) else {
+        return NotFound.into_response();
+    };
+    let session = match authorize(session, &username) {
+        Ok(session) => session,
+        Err(err) => return err.into_response(),
+    };
+
+    if upload.add.iter().any(|feed| upload.remove.contains(feed)) {
+        return Validation.into_response();
+    }
+
+    let device = match sync
+        .db
+        .device_upsert(&session.user, device, None, None)
+        .await
+    {
+        Ok(device) => device,
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to register user device");
+
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let now = OffsetDateTime::now_utc();
+
+    match apply_subscription_upload(
+        &sync,
+        &session.user,
+        Origin::device(Client::Gpodder, Some(device.id)),
+        upload,
+    )
+    .await
+    {
+        Ok(update_urls) => UpdateUrls {
+            timestamp: now.unix_timestamp(),
+            update_urls,
+        }
+        .into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to apply user subscription changes");
+
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use http_body_util::BodyExt as _;
+    use pretty_assertions::assert_eq;
+    use tower::ServiceExt as _;
+
+    use crate::{
+        database::Database,
+        handlers::test_app,
+        models::{gpodder::SubscriptionChanges, ApiError},
+        utils::test::TestBuilder,
+    };
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router.route(
+                "/api/2/subscriptions/:username/:device",
+                get(super::get).post(super::upload),
+            )
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    async fn changes(app: Router, device: &str, since: i64) -> SubscriptionChanges {
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(format!(
+                "/api/2/subscriptions/example/{}.json?since={}",
+                device, since
+            ))
+            .header(header::AUTHORIZATION, Database::test_token())
+            .body(Body::empty())
+            .expect("Failed to build request");
+
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+
+        serde_json::from_slice(&body).expect("Failed to deserialize response body")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn full_sync(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let changes = changes(app, "phone", 0).await;
+
+        assert_eq!(
+            changes.add,
+            vec![
+                Database::SUBSCRIPTION_3_FEED,
+                Database::SUBSCRIPTION_1_FEED,
+                Database::SUBSCRIPTION_2_FEED_NEW,
+            ]
+        );
+        assert!(changes.remove.is_empty());
+    }
+
+    async fn upload_remove(app: Router, device: &str) {
+        let body = format!(
+            r#"{{"add":[],"remove":["{}"]}}"#,
+            Database::SUBSCRIPTION_1_FEED
+        );
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("/api/2/subscriptions/example/{}.json", device))
+            .header(header::AUTHORIZATION, Database::test_token())
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .expect("Failed to build request");
+
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn upload_remove_other_device(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+
+        upload_remove(app.clone(), "phone").await;
+
+        let changes = changes(app, "tablet", 1).await;
+
+        assert_eq!(changes.remove, vec![Database::SUBSCRIPTION_1_FEED]);
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn upload_remove_same_device(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+
+        upload_remove(app.clone(), "phone").await;
+
+        let changes = changes(app, "phone", 1).await;
+
+        assert!(changes.remove.is_empty());
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn unauthorized(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/api/2/subscriptions/example/phone.json";
+        let expected = ApiError::unauthorized();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::GET)
+            .status(StatusCode::UNAUTHORIZED)
+            .run()
+            .await;
+    }
+}
@@ -0,0 +1,134 @@
+use axum::extract::{Path, State};
+use axum_extra::either::Either4;
+
+use crate::{
+    database::jobs::{self, JobId},
+    models::{jobs::Job, Conflict, InternalError, NotFound},
+    SyncState,
+};
+
+/// Drops a job that hasn't started yet, running jobs can't be interrupted.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn cancel(
+    State(sync): State<SyncState>,
+    Path(job_id): Path<i64>,
+) -> Either4<Job, NotFound, Conflict, InternalError> {
+    let id = JobId(job_id);
+
+    let row = match sync.db.job_cancel(id).await {
+        Ok(Some(row)) => row,
+        Ok(None) => {
+            return match sync.db.job_get(id).await {
+                Ok(Some(_)) => Either4::E3(Conflict),
+                Ok(None) => Either4::E2(NotFound),
+                Err(err) => {
+                    tracing::error!(err = ?err, "Failed to retrieve job");
+
+                    Either4::E4(InternalError)
+                }
+            };
+        }
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to cancel job");
+
+            return Either4::E4(InternalError);
+        }
+    };
+
+    // The subscription stays unsubscribed, but it won't be purged.
+    if let Ok(jobs::Job::Deletion { deletion_id }) = row.job() {
+        if let Err(err) = sync.db.deletion_fail(deletion_id).await {
+            tracing::error!(err = ?err, "Failed to mark deletion as failed");
+
+            return Either4::E4(InternalError);
+        }
+    }
+
+    Either4::E1(Job::from(row))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Method, Request, StatusCode},
+        routing::delete,
+        Router,
+    };
+    use time::OffsetDateTime;
+    use tower::ServiceExt as _;
+
+    use crate::{
+        database::{jobs::Job, tasks::DeletionId, Database},
+        handlers::test_app,
+        models::{
+            jobs::{JobKind, JobStatus},
+            subscriptions::DeletionStatus,
+            ApiError,
+        },
+        utils::test::TestBuilder,
+    };
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router.route("/jobs/:job_id", delete(super::cancel))
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn cancel(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool.clone()).await.unwrap();
+        let deletion_id = DeletionId(Database::DELETION_PENDING_ID);
+        db.job_enqueue(
+            &Job::Deletion { deletion_id },
+            OffsetDateTime::now_utc() + time::Duration::days(1),
+        )
+        .await
+        .unwrap();
+        let row = db
+            .jobs_list(Some(JobKind::Deletion), None, None, None)
+            .await
+            .unwrap()
+            .rows
+            .pop()
+            .expect("Job was not queued");
+
+        let app = setup_app(pool).await;
+        let url = format!("/jobs/{}", row.id.0);
+
+        let request = Request::builder()
+            .method(Method::DELETE)
+            .uri(&url)
+            .body(Body::empty())
+            .expect("Failed to build request");
+        let response = app
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let row = db.job_get(row.id).await.unwrap().expect("Job is missing");
+        assert_eq!(row.status, JobStatus::Cancelled);
+
+        let deletion = db
+            .deletion_get_row(deletion_id)
+            .await
+            .unwrap()
+            .expect("Deletion is missing");
+        assert_eq!(deletion.status, DeletionStatus::Failure);
+
+        // Only queued jobs can be cancelled.
+        TestBuilder::new(app, url, ApiError::conflict())
+            .method(Method::DELETE)
+            .status(StatusCode::CONFLICT)
+            .run()
+            .await;
+    }
+}
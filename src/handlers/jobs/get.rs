@@ -0,0 +1,26 @@
+use axum::extract::{Path, State};
+use axum_extra::either::Either3;
+
+use crate::{
+    database::jobs::JobId,
+    models::{jobs::Job, InternalError, NotFound},
+    SyncState,
+};
+
+/// Shows a job along with the error of its last failed attempt.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn get(
+    State(sync): State<SyncState>,
+    Path(job_id): Path<i64>,
+) -> Either3<Job, NotFound, InternalError> {
+    match sync.db.job_get(JobId(job_id)).await {
+        Ok(Some(row)) => Either3::E1(Job::from(row)),
+        Ok(None) => Either3::E2(NotFound),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve job");
+
+            Either3::E3(InternalError)
+        }
+    }
+}
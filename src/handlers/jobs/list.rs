@@ -0,0 +1,121 @@
+use axum::extract::{Query, State};
+use axum_extra::either::Either;
+
+use crate::{
+    models::{
+        jobs::{Job, JobKind, JobStatus, Jobs},
+        InternalError,
+    },
+    SyncState,
+};
+
+#[derive(serde::Deserialize)]
+pub struct ListParams {
+    pub kind: Option<JobKind>,
+    pub status: Option<JobStatus>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+/// Lists the jobs, newest first, so failures can be looked into.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn list(
+    State(sync): State<SyncState>,
+    Query(params): Query<ListParams>,
+) -> Either<Jobs, InternalError> {
+    match sync
+        .db
+        .jobs_list(params.kind, params.status, params.page, params.per_page)
+        .await
+    {
+        Ok(page) => Either::E1(Jobs {
+            total: page.total,
+            page: page.page,
+            per_page: page.per_page,
+            jobs: page.rows.into_iter().map(Job::from).collect(),
+        }),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to list jobs");
+
+            Either::E2(InternalError)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Method, Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use http_body_util::BodyExt as _;
+    use time::OffsetDateTime;
+    use tower::ServiceExt as _;
+
+    use crate::{
+        database::{jobs::Job, Database},
+        handlers::test_app,
+        models::jobs::{JobKind, JobStatus, Jobs},
+    };
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| router.route("/jobs", get(super::list)))
+            .await
+            .expect("failed to setup app")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn list(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool.clone()).await.unwrap();
+        let now = OffsetDateTime::now_utc();
+        db.job_enqueue(
+            &Job::Refresh {
+                feed: Database::SUBSCRIPTION_1_FEED.to_string(),
+            },
+            now,
+        )
+        .await
+        .unwrap();
+        db.job_enqueue(
+            &Job::Identification {
+                subscription_id: Database::SUBSCRIPTION_1_ID.into(),
+            },
+            now,
+        )
+        .await
+        .unwrap();
+
+        let app = setup_app(pool).await;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("/jobs?kind=refresh&status=queued")
+            .body(Body::empty())
+            .expect("Failed to build request");
+
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let jobs: Jobs =
+            serde_json::from_slice(&body).expect("Failed to deserialize response body");
+
+        assert_eq!(jobs.total, 1);
+        assert_eq!(jobs.jobs[0].kind, JobKind::Refresh);
+        assert_eq!(jobs.jobs[0].status, JobStatus::Queued);
+        assert_eq!(
+            jobs.jobs[0].payload["feed"],
+            serde_json::json!(Database::SUBSCRIPTION_1_FEED)
+        );
+    }
+}
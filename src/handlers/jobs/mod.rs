@@ -0,0 +1,20 @@
+//! Operator endpoints for the background job queue, only served on the private listener.
+
+pub mod cancel;
+pub mod get;
+pub mod list;
+pub mod retry;
+pub mod workers;
+
+use axum::routing;
+
+#[rustfmt::skip]
+pub fn app() -> axum::Router<crate::SyncState> {
+    axum::Router::new()
+        .route("/jobs", routing::get(list::list))
+        .route("/jobs/:job_id", routing::get(get::get).delete(cancel::cancel))
+        .route("/jobs/:job_id/retry", routing::post(retry::retry))
+        .route("/workers", routing::get(workers::list))
+        .route("/workers/:kind/pause", routing::post(workers::pause))
+        .route("/workers/:kind/resume", routing::post(workers::resume))
+}
@@ -0,0 +1,217 @@
+use axum::extract::{Path, State};
+use axum_extra::either::Either4;
+
+use crate::{
+    database::jobs::{self, JobId},
+    models::{
+        jobs::{Job, JobStatus},
+        Conflict, InternalError, NotFound,
+    },
+    SyncState,
+};
+
+/// Runs a dead-lettered job again straight away with all its attempts, once whatever made it
+/// fail has been fixed.
+///
+/// Only dead jobs can be retried, the others are still going to run, have run or were cancelled
+/// on purpose.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn retry(
+    State(sync): State<SyncState>,
+    Path(job_id): Path<i64>,
+) -> Either4<Job, NotFound, Conflict, InternalError> {
+    let id = JobId(job_id);
+
+    match sync.db.job_get(id).await {
+        Ok(Some(row)) if row.status != JobStatus::Dead => return Either4::E3(Conflict),
+        Ok(Some(_)) => {}
+        Ok(None) => return Either4::E2(NotFound),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve job");
+
+            return Either4::E4(InternalError);
+        }
+    }
+
+    let row = match sync.db.job_requeue(id).await {
+        Ok(Some(row)) => row,
+        // Retried in the meantime, or the same work is already queued.
+        Ok(None) => return Either4::E3(Conflict),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to requeue job");
+
+            return Either4::E4(InternalError);
+        }
+    };
+
+    // The user was told their deletion failed when the job was dead-lettered.
+    if let Ok(jobs::Job::Deletion { deletion_id }) = row.job() {
+        if let Err(err) = sync.db.deletion_reset(deletion_id).await {
+            tracing::error!(err = ?err, "Failed to reset deletion");
+
+            return Either4::E4(InternalError);
+        }
+    }
+
+    Either4::E1(Job::from(row))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Method, Request, StatusCode},
+        routing::post,
+        Router,
+    };
+    use http_body_util::BodyExt as _;
+    use time::OffsetDateTime;
+    use tower::ServiceExt as _;
+
+    use crate::{
+        database::{jobs::Job, tasks::DeletionId, Database},
+        handlers::test_app,
+        models::{
+            jobs::{self, JobKind, JobStatus},
+            subscriptions::DeletionStatus,
+            ApiError,
+        },
+        utils::test::TestBuilder,
+    };
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router.route("/jobs/:job_id/retry", post(super::retry))
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn dead_deletion(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool.clone()).await.unwrap();
+        let deletion_id = DeletionId(Database::DELETION_FAILURE_ID);
+        db.job_enqueue(&Job::Deletion { deletion_id }, OffsetDateTime::now_utc())
+            .await
+            .unwrap();
+        let row = db
+            .job_lease(JobKind::Deletion, time::Duration::minutes(1))
+            .await
+            .unwrap()
+            .expect("Job was not queued");
+        db.job_dead(&row, "Database is locked").await.unwrap();
+
+        let app = setup_app(pool).await;
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("/jobs/{}/retry", row.id.0))
+            .body(Body::empty())
+            .expect("Failed to build request");
+
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let job: jobs::Job =
+            serde_json::from_slice(&body).expect("Failed to deserialize response body");
+
+        assert_eq!(job.status, JobStatus::Queued);
+        assert_eq!(job.attempts, 0);
+        assert_eq!(job.last_error.as_deref(), Some("Database is locked"));
+
+        let deletion = db
+            .deletion_get_row(deletion_id)
+            .await
+            .unwrap()
+            .expect("Deletion is missing");
+        assert_eq!(deletion.status, DeletionStatus::Pending);
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn running(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool.clone()).await.unwrap();
+        db.job_enqueue(
+            &Job::Refresh {
+                feed: Database::SUBSCRIPTION_1_FEED.to_string(),
+            },
+            OffsetDateTime::now_utc(),
+        )
+        .await
+        .unwrap();
+        let row = db
+            .job_lease(JobKind::Refresh, time::Duration::minutes(1))
+            .await
+            .unwrap()
+            .expect("Job was not queued");
+
+        let app = setup_app(pool).await;
+        let url = format!("/jobs/{}/retry", row.id.0);
+        let expected = ApiError::conflict();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::POST)
+            .status(StatusCode::CONFLICT)
+            .run()
+            .await;
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn done(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool.clone()).await.unwrap();
+        db.job_enqueue(
+            &Job::Refresh {
+                feed: Database::SUBSCRIPTION_1_FEED.to_string(),
+            },
+            OffsetDateTime::now_utc(),
+        )
+        .await
+        .unwrap();
+        let row = db
+            .job_lease(JobKind::Refresh, time::Duration::minutes(1))
+            .await
+            .unwrap()
+            .expect("Job was not queued");
+        db.job_complete(&row).await.unwrap();
+
+        let app = setup_app(pool).await;
+        let url = format!("/jobs/{}/retry", row.id.0);
+        let expected = ApiError::conflict();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::POST)
+            .status(StatusCode::CONFLICT)
+            .run()
+            .await;
+
+        let row = db.job_get(row.id).await.unwrap().expect("Job is missing");
+        assert_eq!(row.status, JobStatus::Done);
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn missing(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/jobs/404/retry";
+        let expected = ApiError::not_found();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::POST)
+            .status(StatusCode::NOT_FOUND)
+            .run()
+            .await;
+    }
+}
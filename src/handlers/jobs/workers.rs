@@ -0,0 +1,173 @@
+use axum::extract::{Path, State};
+use axum_extra::either::Either;
+
+use crate::{
+    models::{
+        jobs::{JobKind, JobStatus, Worker, Workers},
+        InternalError,
+    },
+    SyncState,
+};
+
+/// Lists the workers of each kind of job, whether they are paused and how many jobs they have
+/// left.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn list(State(sync): State<SyncState>) -> Either<Workers, InternalError> {
+    let (counts, paused) = match tokio::try_join!(sync.db.jobs_count(), sync.db.job_kinds_paused())
+    {
+        Ok(result) => result,
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve job counts");
+
+            return Either::E2(InternalError);
+        }
+    };
+
+    let count = |kind: JobKind, status: JobStatus| {
+        counts
+            .iter()
+            .find(|row| row.kind == kind && row.status == status)
+            .map_or(0, |row| row.total)
+    };
+
+    let workers = JobKind::ALL
+        .into_iter()
+        .map(|kind| Worker {
+            kind,
+            workers: sync.cfg.jobs.workers(kind),
+            paused: paused.contains(&kind),
+            queued: count(kind, JobStatus::Queued),
+            running: count(kind, JobStatus::Running),
+            dead: count(kind, JobStatus::Dead),
+        })
+        .collect();
+
+    Either::E1(Workers { workers })
+}
+
+/// Stops the workers of the kind from starting new jobs, the jobs they are running are finished.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn pause(
+    State(sync): State<SyncState>,
+    Path(kind): Path<JobKind>,
+) -> Either<Workers, InternalError> {
+    if let Err(err) = sync.db.job_kind_set_paused(kind, true).await {
+        tracing::error!(err = ?err, "Failed to pause workers");
+
+        return Either::E2(InternalError);
+    }
+
+    list(State(sync)).await
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn resume(
+    State(sync): State<SyncState>,
+    Path(kind): Path<JobKind>,
+) -> Either<Workers, InternalError> {
+    if let Err(err) = sync.db.job_kind_set_paused(kind, false).await {
+        tracing::error!(err = ?err, "Failed to resume workers");
+
+        return Either::E2(InternalError);
+    }
+
+    list(State(sync)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Method, Request, StatusCode},
+        routing::post,
+        Router,
+    };
+    use http_body_util::BodyExt as _;
+    use time::OffsetDateTime;
+    use tower::ServiceExt as _;
+
+    use crate::{
+        database::{jobs::Job, Database},
+        handlers::test_app,
+        models::jobs::{JobKind, Workers},
+    };
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router
+                .route("/workers/:kind/pause", post(super::pause))
+                .route("/workers/:kind/resume", post(super::resume))
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    async fn request(app: &Router, uri: &str) -> Workers {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .body(Body::empty())
+            .expect("Failed to build request");
+        let response = app
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+
+        serde_json::from_slice(&body).expect("Failed to deserialize response body")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn pause(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool.clone()).await.unwrap();
+        let lease = time::Duration::minutes(1);
+        db.job_enqueue(
+            &Job::Refresh {
+                feed: Database::SUBSCRIPTION_1_FEED.to_string(),
+            },
+            OffsetDateTime::now_utc(),
+        )
+        .await
+        .unwrap();
+
+        let app = setup_app(pool).await;
+
+        let workers = request(&app, "/workers/refresh/pause").await;
+        let refresh = workers
+            .workers
+            .iter()
+            .find(|worker| worker.kind == JobKind::Refresh)
+            .expect("Refresh workers are missing");
+        assert!(refresh.paused);
+        assert_eq!(refresh.queued, 1);
+
+        assert!(db
+            .job_lease(JobKind::Refresh, lease)
+            .await
+            .unwrap()
+            .is_none());
+
+        let workers = request(&app, "/workers/refresh/resume").await;
+        assert!(workers.workers.iter().all(|worker| !worker.paused));
+
+        assert!(db
+            .job_lease(JobKind::Refresh, lease)
+            .await
+            .unwrap()
+            .is_some());
+    }
+}
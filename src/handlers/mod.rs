@@ -1,5 +1,13 @@
+mod backups;
+mod gpodder;
+mod jobs;
+mod nextcloud;
+mod podcasts;
 mod subscriptions;
+mod tags;
+mod takeout;
 mod web;
+mod websub;
 
 use crate::SyncState;
 
@@ -7,10 +15,25 @@ use crate::SyncState;
 pub fn app(state: SyncState) -> axum::Router {
     axum::Router::new()
         .merge(subscriptions::app())
+        .merge(tags::app())
+        .merge(takeout::app())
+        .merge(podcasts::app())
+        .merge(gpodder::app())
+        .merge(nextcloud::app())
         .merge(web::app())
+        .merge(websub::app())
         .with_state(state.clone())
 }
 
+/// The operator endpoints, served next to the metrics on the private listener.
+#[rustfmt::skip]
+pub fn private_app(state: SyncState) -> axum::Router {
+    axum::Router::new()
+        .merge(jobs::app())
+        .merge(backups::app())
+        .with_state(state)
+}
+
 #[cfg(test)]
 pub async fn test_app<B>(
     pool: sqlx::SqlitePool,
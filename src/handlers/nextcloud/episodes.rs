@@ -0,0 +1,219 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse as _, Response},
+};
+use time::OffsetDateTime;
+
+use crate::{
+    database::episode::EpisodeActionFilter,
+    extractor::auth::BasicSession,
+    handlers::{gpodder::episodes::store_episode_actions, nextcloud::authorize},
+    models::{
+        gpodder::{EpisodeAction, EpisodeActions},
+        nextcloud::Timestamp,
+        NotFound,
+    },
+    utils::json::Json,
+    SyncState,
+};
+
+#[derive(serde::Deserialize)]
+pub struct GetParams {
+    pub since: Option<i64>,
+    /// The device asking, its own actions are left out.
+    pub device: Option<String>,
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn get(
+    State(sync): State<SyncState>,
+    session: Option<BasicSession>,
+    Query(params): Query<GetParams>,
+) -> Response {
+    let session = match authorize(session) {
+        Ok(session) => session,
+        Err(err) => return err.into_response(),
+    };
+
+    let exclude = match params.device {
+        Some(device) => match sync
+            .db
+            .device_get_by_identifier(&session.user, &device)
+            .await
+        {
+            Ok(Some(device)) => Some(device.id),
+            Ok(None) => return NotFound.into_response(),
+            Err(err) => {
+                tracing::error!(err = ?err, "Failed to retrieve user device");
+
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        },
+        None => None,
+    };
+
+    let now = OffsetDateTime::now_utc();
+    let filter = EpisodeActionFilter {
+        since: params
+            .since
+            .filter(|since| *since > 0)
+            .and_then(|since| OffsetDateTime::from_unix_timestamp(since).ok()),
+        podcast: None,
+        device: None,
+        exclude,
+        aggregated: false,
+    };
+
+    match sync.db.episode_actions_get(&session.user, filter).await {
+        Ok(actions) => EpisodeActions {
+            actions: actions.into_iter().map(EpisodeAction::from).collect(),
+            timestamp: now.unix_timestamp(),
+        }
+        .into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user episode actions");
+
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn create(
+    State(sync): State<SyncState>,
+    session: Option<BasicSession>,
+    Json(actions): Json<Vec<EpisodeAction>>,
+) -> Response {
+    let session = match authorize(session) {
+        Ok(session) => session,
+        Err(err) => return err.into_response(),
+    };
+
+    let now = OffsetDateTime::now_utc();
+
+    if let Err(err) = store_episode_actions(&sync, &session.user, actions).await {
+        tracing::error!(err = ?err, "Failed to create user episode actions");
+
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    Timestamp {
+        timestamp: now.unix_timestamp(),
+    }
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+        routing::{get, post},
+        Router,
+    };
+    use http_body_util::BodyExt as _;
+    use tower::ServiceExt as _;
+
+    use crate::{
+        database::Database,
+        handlers::test_app,
+        models::{gpodder::EpisodeActions, ApiError},
+        utils::test::TestBuilder,
+    };
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router
+                .route(
+                    "/index.php/apps/gpoddersync/episode_action",
+                    get(super::get),
+                )
+                .route(
+                    "/index.php/apps/gpoddersync/episode_action/create",
+                    post(super::create),
+                )
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn get_exclude_device(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+
+        let body = format!(
+            r#"[
+                {{"podcast":"{feed}","episode":"http://one.example.com/1.mp3","device":"phone","action":"download"}},
+                {{"podcast":"{feed}","episode":"http://one.example.com/2.mp3","device":"tablet","action":"download"}}
+            ]"#,
+            feed = Database::SUBSCRIPTION_1_FEED,
+        );
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/index.php/apps/gpoddersync/episode_action/create")
+            .header(header::AUTHORIZATION, Database::test_token())
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .expect("Failed to build request");
+        let response = app
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("Failed to run request");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("/index.php/apps/gpoddersync/episode_action?device=phone")
+            .header(header::AUTHORIZATION, Database::test_token())
+            .body(Body::empty())
+            .expect("Failed to build request");
+        let response = app
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("Failed to run request");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let actions: EpisodeActions =
+            serde_json::from_slice(&body).expect("Failed to deserialize response body");
+
+        assert_eq!(actions.actions.len(), 1);
+        assert_eq!(actions.actions[0].device.as_deref(), Some("tablet"));
+
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("/index.php/apps/gpoddersync/episode_action?device=missing")
+            .header(header::AUTHORIZATION, Database::test_token())
+            .body(Body::empty())
+            .expect("Failed to build request");
+        let response = app.oneshot(request).await.expect("Failed to run request");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn unauthorized(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/index.php/apps/gpoddersync/episode_action";
+        let expected = ApiError::unauthorized();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::GET)
+            .status(StatusCode::UNAUTHORIZED)
+            .run()
+            .await;
+    }
+}
@@ -0,0 +1,164 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse as _, Redirect, Response},
+    Form,
+};
+use axum_extra::TypedHeader;
+use headers::Host;
+
+use crate::{
+    extractor::auth::Session,
+    handlers::web::{Base, Template},
+    models::{
+        nextcloud::{LoginCredentials, LoginFlow, LoginFlowPoll},
+        NotFound,
+    },
+    SyncState,
+};
+
+/// Returns the URL clients should use to reach the server, without a trailing slash.
+fn server_url(sync: &SyncState, host: &Host) -> String {
+    match &sync.cfg.public_url {
+        Some(url) => url.as_str().trim_end_matches('/').to_string(),
+        None => format!("http://{}", host),
+    }
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn start(
+    State(sync): State<SyncState>,
+    TypedHeader(host): TypedHeader<Host>,
+) -> Response {
+    let flow = match sync.db.login_flow_create().await {
+        Ok(flow) => flow,
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to create login flow");
+
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let server = server_url(&sync, &host);
+
+    LoginFlow {
+        poll: LoginFlowPoll {
+            token: flow.poll_token,
+            endpoint: format!("{}/index.php/login/v2/poll", server),
+        },
+        login: format!("{}/index.php/login/v2/flow/{}", server, flow.login_token),
+    }
+    .into_response()
+}
+
+#[derive(serde::Deserialize)]
+pub struct PollForm {
+    token: String,
+}
+
+/// Nextcloud answers polls with a 404 until the user has granted access.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn poll(
+    State(sync): State<SyncState>,
+    TypedHeader(host): TypedHeader<Host>,
+    Form(form): Form<PollForm>,
+) -> Response {
+    match sync.db.login_flow_poll(&form.token).await {
+        Ok(Some(credentials)) => LoginCredentials {
+            server: server_url(&sync, &host),
+            login_name: credentials.username,
+            app_password: credentials.app_password,
+        }
+        .into_response(),
+        Ok(None) => NotFound.into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to poll login flow");
+
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[derive(askama::Template)]
+#[template(path = "nextcloud/flow.html")]
+struct Flow {
+    base: Base,
+    token: String,
+    granted: bool,
+    error: Option<&'static str>,
+}
+
+impl Flow {
+    fn new(session: Session, token: String) -> Self {
+        Self {
+            base: Base::new(Some(session)),
+            token,
+            granted: false,
+            error: None,
+        }
+    }
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn get_flow(
+    State(sync): State<SyncState>,
+    session: Option<Session>,
+    Path(token): Path<String>,
+) -> Response {
+    let Some(session) = session.filter(Session::validate) else {
+        return Redirect::to("/login").into_response();
+    };
+
+    match sync.db.login_flow_get_by_login_token(&token).await {
+        Ok(Some(_)) => Template(Flow::new(session, token)).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Template(Flow {
+                error: Some("This login request has expired or was already used."),
+                ..Flow::new(session, token)
+            }),
+        )
+            .into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve login flow");
+
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn post_flow(
+    State(sync): State<SyncState>,
+    session: Option<Session>,
+    Path(token): Path<String>,
+) -> Response {
+    let Some(session) = session.filter(Session::validate) else {
+        return Redirect::to("/login").into_response();
+    };
+
+    match sync.db.login_flow_grant(&token, &session.user).await {
+        Ok(true) => Template(Flow {
+            granted: true,
+            ..Flow::new(session, token)
+        })
+        .into_response(),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Template(Flow {
+                error: Some("This login request has expired or was already used."),
+                ..Flow::new(session, token)
+            }),
+        )
+            .into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to grant login flow");
+
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -0,0 +1,38 @@
+//! Emulates the Nextcloud gpoddersync app, which AntennaPod and others use as a simpler
+//! alternative to the gpodder.net API.
+//!
+//! Clients log in through Nextcloud's login flow (v2) and then use HTTP Basic authentication with
+//! the app password it hands out.
+
+pub mod episodes;
+pub mod login;
+pub mod subscriptions;
+
+use axum::routing;
+
+use crate::{
+    extractor::auth::{BasicSession, Session},
+    models::Unauthorized,
+};
+
+fn authorize(session: Option<BasicSession>) -> Result<Session, Unauthorized> {
+    match session.map(|session| session.0) {
+        Some(session) if session.validate() => Ok(session),
+        _ => Err(Unauthorized),
+    }
+}
+
+#[rustfmt::skip]
+pub fn app() -> axum::Router<crate::SyncState> {
+    axum::Router::new()
+        // Login Flow
+        .route("/index.php/login/v2", routing::post(login::start))
+        .route("/index.php/login/v2/poll", routing::post(login::poll))
+        .route("/index.php/login/v2/flow/:token", routing::get(login::get_flow).post(login::post_flow))
+        // Subscriptions
+        .route("/index.php/apps/gpoddersync/subscriptions", routing::get(subscriptions::get))
+        .route("/index.php/apps/gpoddersync/subscription_change/create", routing::post(subscriptions::create))
+        // Episode Actions
+        .route("/index.php/apps/gpoddersync/episode_action", routing::get(episodes::get))
+        .route("/index.php/apps/gpoddersync/episode_action/create", routing::post(episodes::create))
+}
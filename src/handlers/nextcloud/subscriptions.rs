@@ -0,0 +1,159 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse as _, Response},
+};
+use time::OffsetDateTime;
+
+use crate::{
+    database::subscription::history::Origin,
+    extractor::auth::BasicSession,
+    handlers::{
+        gpodder::subscriptions::{apply_subscription_upload, subscription_changes},
+        nextcloud::authorize,
+    },
+    models::{gpodder::SubscriptionUpload, nextcloud::Timestamp, subscriptions::Client},
+    utils::json::Json,
+    SyncState,
+};
+
+#[derive(serde::Deserialize)]
+pub struct GetParams {
+    pub since: Option<i64>,
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn get(
+    State(sync): State<SyncState>,
+    session: Option<BasicSession>,
+    Query(params): Query<GetParams>,
+) -> Response {
+    let session = match authorize(session) {
+        Ok(session) => session,
+        Err(err) => return err.into_response(),
+    };
+
+    match subscription_changes(&sync, &session.user, None, params.since).await {
+        Ok(changes) => changes.into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user subscription changes");
+
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn create(
+    State(sync): State<SyncState>,
+    session: Option<BasicSession>,
+    Json(upload): Json<SubscriptionUpload>,
+) -> Response {
+    let session = match authorize(session) {
+        Ok(session) => session,
+        Err(err) => return err.into_response(),
+    };
+
+    let now = OffsetDateTime::now_utc();
+
+    // gpoddersync has no way to tell the client about rewritten URLs
+    if let Err(err) =
+        apply_subscription_upload(&sync, &session.user, Origin::new(Client::Nextcloud), upload)
+            .await
+    {
+        tracing::error!(err = ?err, "Failed to apply user subscription changes");
+
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    Timestamp {
+        timestamp: now.unix_timestamp(),
+    }
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+        routing::{get, post},
+        Router,
+    };
+    use http_body_util::BodyExt as _;
+    use pretty_assertions::assert_eq;
+    use tower::ServiceExt as _;
+
+    use crate::{
+        database::Database,
+        handlers::test_app,
+        models::{gpodder::SubscriptionChanges, ApiError},
+        utils::test::TestBuilder,
+    };
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router
+                .route("/index.php/apps/gpoddersync/subscriptions", get(super::get))
+                .route(
+                    "/index.php/apps/gpoddersync/subscription_change/create",
+                    post(super::create),
+                )
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn full_sync(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("/index.php/apps/gpoddersync/subscriptions?since=0")
+            .header(header::AUTHORIZATION, Database::test_token())
+            .body(Body::empty())
+            .expect("Failed to build request");
+
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let changes: SubscriptionChanges =
+            serde_json::from_slice(&body).expect("Failed to deserialize response body");
+
+        assert_eq!(
+            changes.add,
+            vec![
+                Database::SUBSCRIPTION_3_FEED,
+                Database::SUBSCRIPTION_1_FEED,
+                Database::SUBSCRIPTION_2_FEED_NEW,
+            ]
+        );
+        assert!(changes.remove.is_empty());
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn unauthorized(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/index.php/apps/gpoddersync/subscriptions";
+        let expected = ApiError::unauthorized();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::GET)
+            .status(StatusCode::UNAUTHORIZED)
+            .run()
+            .await;
+    }
+}
@@ -0,0 +1,232 @@
+use axum::extract::{Path, Query, State};
+use axum_extra::either::Either4;
+
+use crate::{
+    database::podcast::{EpisodeId, PodcastId},
+    extractor::auth::Session,
+    handlers::podcasts::PageParams,
+    models::{
+        podcasts::{Episode, Episodes, Value},
+        InternalError, NotFound, Unauthorized,
+    },
+    utils::serde::{Accepts, Serializable},
+    SyncState,
+};
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn list(
+    State(sync): State<SyncState>,
+    session: Option<Session>,
+    Accepts(encoding): Accepts,
+    Path(podcast_id): Path<i64>,
+    Query(params): Query<PageParams>,
+) -> Either4<Serializable<Episodes>, Unauthorized, NotFound, InternalError> {
+    let Some(session) = session else {
+        return Either4::E2(Unauthorized);
+    };
+    if !session.validate() {
+        return Either4::E2(Unauthorized);
+    }
+
+    let id = PodcastId(podcast_id);
+
+    match sync.db.podcast_get_by_id(&session.user, id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Either4::E3(NotFound),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve podcast");
+
+            return Either4::E4(InternalError);
+        }
+    }
+
+    match sync
+        .db
+        .podcast_episodes_get_all(id, params.page, params.per_page)
+        .await
+    {
+        Ok(page) => Either4::E1(Serializable(
+            encoding,
+            Episodes {
+                total: page.total,
+                page: page.page,
+                per_page: page.per_page,
+                episodes: page.rows.into_iter().map(Episode::from).collect(),
+            },
+        )),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve podcast episodes");
+
+            Either4::E4(InternalError)
+        }
+    }
+}
+
+/// Returns a single episode along with its transcripts, persons and value block.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn get(
+    State(sync): State<SyncState>,
+    session: Option<Session>,
+    Accepts(encoding): Accepts,
+    Path((podcast_id, episode_id)): Path<(i64, i64)>,
+) -> Either4<Serializable<Episode>, Unauthorized, NotFound, InternalError> {
+    let Some(session) = session else {
+        return Either4::E2(Unauthorized);
+    };
+    if !session.validate() {
+        return Either4::E2(Unauthorized);
+    }
+
+    let podcast = PodcastId(podcast_id);
+    let id = EpisodeId(episode_id);
+
+    match sync.db.podcast_get_by_id(&session.user, podcast).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Either4::E3(NotFound),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve podcast");
+
+            return Either4::E4(InternalError);
+        }
+    }
+
+    let row = match sync.db.podcast_episode_get_by_id(podcast, id).await {
+        Ok(Some(row)) => row,
+        Ok(None) => return Either4::E3(NotFound),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve podcast episode");
+
+            return Either4::E4(InternalError);
+        }
+    };
+
+    let details = tokio::try_join!(
+        sync.db.episode_transcripts_get(id),
+        sync.db.podcast_persons_get(podcast, Some(id)),
+        sync.db.podcast_value_get(podcast, Some(id)),
+    );
+    let (transcripts, persons, value) = match details {
+        Ok(details) => details,
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve podcast episode details");
+
+            return Either4::E4(InternalError);
+        }
+    };
+
+    let mut episode = Episode::from(row);
+    episode.transcripts = transcripts.into_iter().map(Into::into).collect();
+    episode.persons = persons.into_iter().map(Into::into).collect();
+    episode.value = value.map(|(value, recipients)| Value::from_rows(value, recipients));
+
+    Either4::E1(Serializable(encoding, episode))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use http_body_util::BodyExt as _;
+    use tower::ServiceExt as _;
+    use url::Url;
+
+    use crate::{
+        database::Database,
+        handlers::test_app,
+        models::{podcasts::Episode, rss::Rss},
+    };
+
+    const FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <rss version="2.0" xmlns:podcast="https://podcastindex.org/namespace/1.0">
+            <channel>
+                <title>Example</title>
+                <item>
+                    <title>Episode 1</title>
+                    <guid>episode-1</guid>
+                    <podcast:transcript url="https://example.com/1.vtt" type="text/vtt" language="en" />
+                    <podcast:chapters url="https://example.com/1.json" type="application/json+chapters" />
+                    <podcast:season name="The Beginning">1</podcast:season>
+                    <podcast:person role="guest">John Doe</podcast:person>
+                    <podcast:value type="lightning" method="keysend">
+                        <podcast:valueRecipient name="Guest" type="node" address="02d5c1" split="100" />
+                    </podcast:value>
+                </item>
+            </channel>
+        </rss>"#;
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router.route(
+                "/v1/podcasts/:podcast_id/episodes/:episode_id",
+                get(super::get),
+            )
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn get_namespace(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool.clone()).await.unwrap();
+        let rss: Rss = quick_xml::de::from_str(FEED).unwrap();
+        db.feed_store(Database::SUBSCRIPTION_1_FEED, &rss.channel)
+            .await
+            .expect("Failed to store feed");
+
+        let podcast = db
+            .podcast_get_by_feed(&Url::parse(Database::SUBSCRIPTION_1_FEED).unwrap())
+            .await
+            .unwrap()
+            .expect("Podcast was not stored");
+        let episodes = db
+            .podcast_episodes_get_all(podcast.id, None, None)
+            .await
+            .unwrap();
+        let episode = episodes.rows.first().expect("Episode was not stored");
+
+        let app = setup_app(pool).await;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(format!(
+                "/v1/podcasts/{}/episodes/{}",
+                podcast.id.0, episode.id.0
+            ))
+            .header(header::AUTHORIZATION, Database::test_token())
+            .body(Body::empty())
+            .expect("Failed to build request");
+
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let episode: Episode =
+            serde_json::from_slice(&body).expect("Failed to deserialize response body");
+
+        assert_eq!(episode.season, Some(1));
+        assert_eq!(episode.season_name.as_deref(), Some("The Beginning"));
+        assert_eq!(
+            episode.chapters.map(|chapters| chapters.url),
+            Some("https://example.com/1.json".to_string())
+        );
+        assert_eq!(episode.transcripts.len(), 1);
+        assert_eq!(episode.transcripts[0].language.as_deref(), Some("en"));
+        assert_eq!(episode.persons[0].name, "John Doe");
+
+        let value = episode.value.expect("Missing value");
+        assert_eq!(value.recipients[0].split, 100);
+    }
+}
@@ -0,0 +1,98 @@
+use axum::extract::{Path, State};
+use axum_extra::either::Either4;
+
+use crate::{
+    database::podcast::PodcastId,
+    extractor::auth::Session,
+    models::{
+        podcasts::{Podcast, Value},
+        InternalError, NotFound, Unauthorized,
+    },
+    utils::serde::{Accepts, Serializable},
+    SyncState,
+};
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn get(
+    State(sync): State<SyncState>,
+    session: Option<Session>,
+    Accepts(encoding): Accepts,
+    Path(podcast_id): Path<i64>,
+) -> Either4<Serializable<Podcast>, Unauthorized, NotFound, InternalError> {
+    let Some(session) = session else {
+        return Either4::E2(Unauthorized);
+    };
+    if !session.validate() {
+        return Either4::E2(Unauthorized);
+    }
+
+    let id = PodcastId(podcast_id);
+
+    let row = match sync.db.podcast_get_by_id(&session.user, id).await {
+        Ok(Some(row)) => row,
+        Ok(None) => return Either4::E3(NotFound),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve podcast");
+
+            return Either4::E4(InternalError);
+        }
+    };
+
+    let details = tokio::try_join!(
+        sync.db.podcast_get_feeds(id),
+        sync.db.podcast_fundings_get(id),
+        sync.db.podcast_persons_get(id, None),
+        sync.db.podcast_value_get(id, None),
+    );
+    let (feed_urls, funding, persons, value) = match details {
+        Ok(details) => details,
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve podcast details");
+
+            return Either4::E4(InternalError);
+        }
+    };
+
+    let mut podcast = Podcast::from_row(row, feed_urls);
+    podcast.funding = funding.into_iter().map(Into::into).collect();
+    podcast.persons = persons.into_iter().map(Into::into).collect();
+    podcast.value = value.map(|(value, recipients)| Value::from_rows(value, recipients));
+
+    Either4::E1(Serializable(encoding, podcast))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        http::{Method, StatusCode},
+        routing::get,
+        Router,
+    };
+
+    use crate::{handlers::test_app, models::ApiError, utils::test::TestBuilder};
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router.route("/v1/podcasts/:podcast_id", get(super::get))
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn not_found(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/v1/podcasts/404";
+        let expected = ApiError::not_found();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::GET)
+            .authorization(true)
+            .status(StatusCode::NOT_FOUND)
+            .run()
+            .await;
+    }
+}
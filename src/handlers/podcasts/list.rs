@@ -0,0 +1,198 @@
+use axum::extract::{Query, State};
+use axum_extra::either::Either3;
+
+use crate::{
+    extractor::auth::Session,
+    handlers::podcasts::PageParams,
+    models::{
+        podcasts::{Podcast, Podcasts},
+        InternalError, Unauthorized,
+    },
+    utils::serde::{Accepts, Serializable},
+    SyncState,
+};
+
+/// Lists the podcasts behind the feeds the user is subscribed to.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn list(
+    State(sync): State<SyncState>,
+    session: Option<Session>,
+    Accepts(encoding): Accepts,
+    Query(params): Query<PageParams>,
+) -> Either3<Serializable<Podcasts>, Unauthorized, InternalError> {
+    let Some(session) = session else {
+        return Either3::E2(Unauthorized);
+    };
+    if !session.validate() {
+        return Either3::E2(Unauthorized);
+    }
+
+    let page = match sync
+        .db
+        .podcasts_get_all(&session.user, params.page, params.per_page)
+        .await
+    {
+        Ok(page) => page,
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user podcasts");
+
+            return Either3::E3(InternalError);
+        }
+    };
+
+    let ids = page.rows.iter().map(|row| row.id).collect::<Vec<_>>();
+    let mut feeds = match sync.db.podcasts_get_feeds(&ids).await {
+        Ok(feeds) => feeds,
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve podcast feeds");
+
+            return Either3::E3(InternalError);
+        }
+    };
+
+    let podcasts = page
+        .rows
+        .into_iter()
+        .map(|row| {
+            let feed_urls = feeds.remove(&row.id).unwrap_or_default();
+
+            Podcast::from_row(row, feed_urls)
+        })
+        .collect();
+
+    Either3::E1(Serializable(
+        encoding,
+        Podcasts {
+            total: page.total,
+            page: page.page,
+            per_page: page.per_page,
+            podcasts,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        http::{Method, StatusCode},
+        routing::get,
+        Router,
+    };
+
+    use crate::{
+        database::Database,
+        handlers::test_app,
+        models::{
+            podcasts::{Podcast, Podcasts},
+            ApiError,
+        },
+        utils::test::TestBuilder,
+    };
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router.route("/v1/podcasts", get(super::list))
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn empty(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/v1/podcasts";
+        let expected = Podcasts {
+            total: 0,
+            page: 1,
+            per_page: 50,
+            podcasts: vec![],
+        };
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::GET)
+            .authorization(true)
+            .status(StatusCode::OK)
+            .run()
+            .await;
+    }
+
+    fn podcast(id: i64, title: &str, description: Option<&str>, feed_urls: Vec<&str>) -> Podcast {
+        Podcast {
+            id,
+            title: title.to_string(),
+            feed_urls: feed_urls.into_iter().map(str::to_string).collect(),
+            description: description.map(str::to_string),
+            image: None,
+            language: None,
+            link: None,
+            copyright: None,
+            author: None,
+            category: None,
+            subcategory: None,
+            owner_name: None,
+            owner_email: None,
+            kind: None,
+            summary: None,
+            locked: None,
+            locked_owner: None,
+            funding: vec![],
+            persons: vec![],
+            value: None,
+        }
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql", "../../../fixtures/podcasts.sql"))]
+    async fn ok(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/v1/podcasts";
+        let expected = Podcasts {
+            total: 2,
+            page: 1,
+            per_page: 50,
+            podcasts: vec![
+                podcast(
+                    11,
+                    "Podcast One",
+                    Some("The first podcast"),
+                    vec![Database::SUBSCRIPTION_1_FEED],
+                ),
+                podcast(
+                    12,
+                    "Podcast Two",
+                    None,
+                    vec![
+                        Database::SUBSCRIPTION_2_FEED_NEW,
+                        Database::SUBSCRIPTION_2_FEED_OLD,
+                    ],
+                ),
+            ],
+        };
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::GET)
+            .authorization(true)
+            .status(StatusCode::OK)
+            .run()
+            .await;
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn unauthorized(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/v1/podcasts";
+        let expected = ApiError::unauthorized();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::GET)
+            .status(StatusCode::UNAUTHORIZED)
+            .run()
+            .await;
+    }
+}
@@ -0,0 +1,24 @@
+pub mod episodes;
+pub mod get;
+pub mod list;
+pub mod search;
+
+use axum::routing;
+
+#[derive(serde::Deserialize)]
+pub struct PageParams {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[rustfmt::skip]
+pub fn app() -> axum::Router<crate::SyncState> {
+    axum::Router::new()
+        // Podcasts
+        .route("/v1/podcasts", routing::get(list::list))
+        .route("/v1/podcasts/:podcast_id", routing::get(get::get))
+        .route("/v1/podcasts/:podcast_id/episodes", routing::get(episodes::list))
+        .route("/v1/podcasts/:podcast_id/episodes/:episode_id", routing::get(episodes::get))
+        // Search
+        .route("/v1/search", routing::get(search::search))
+}
@@ -0,0 +1,194 @@
+use axum::extract::{Query, State};
+use axum_extra::either::Either4;
+
+use crate::{
+    database::search::match_query,
+    extractor::auth::Session,
+    models::{
+        podcasts::{SearchResult, SearchResults},
+        InternalError, Unauthorized, Validation,
+    },
+    utils::serde::{Accepts, Serializable},
+    SyncState,
+};
+
+#[derive(serde::Deserialize)]
+pub struct SearchParams {
+    #[serde(default)]
+    pub q: String,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+/// Searches the podcasts the user is subscribed to and their episodes, best matches first.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn search(
+    State(sync): State<SyncState>,
+    session: Option<Session>,
+    Accepts(encoding): Accepts,
+    Query(params): Query<SearchParams>,
+) -> Either4<Serializable<SearchResults>, Unauthorized, Validation, InternalError> {
+    let Some(session) = session else {
+        return Either4::E2(Unauthorized);
+    };
+    if !session.validate() {
+        return Either4::E2(Unauthorized);
+    }
+
+    let Some(query) = match_query(&params.q) else {
+        return Either4::E3(Validation);
+    };
+
+    match sync
+        .db
+        .search(&session.user, &query, params.page, params.per_page)
+        .await
+    {
+        Ok(page) => Either4::E1(Serializable(
+            encoding,
+            SearchResults {
+                total: page.total,
+                page: page.page,
+                per_page: page.per_page,
+                results: page.rows.into_iter().map(SearchResult::from).collect(),
+            },
+        )),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to search user podcasts");
+
+            Either4::E4(InternalError)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use http_body_util::BodyExt as _;
+    use tower::ServiceExt as _;
+
+    use crate::{
+        database::Database,
+        handlers::test_app,
+        models::{podcasts::SearchResults, rss::Rss, ApiError},
+        utils::test::TestBuilder,
+    };
+
+    const FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <rss version="2.0">
+            <channel>
+                <title>Systems Weekly</title>
+                <description>Talking about operating systems.</description>
+                <item>
+                    <title>Episode 1</title>
+                    <guid>episode-1</guid>
+                    <description>Why everyone is rewriting things in Rust.</description>
+                </item>
+                <item>
+                    <title>Episode 2</title>
+                    <guid>episode-2</guid>
+                    <description>Schedulers, again.</description>
+                </item>
+            </channel>
+        </rss>"#;
+
+    const OTHER_FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <rss version="2.0">
+            <channel>
+                <title>Rustacean Hour</title>
+                <item>
+                    <title>Rust 2024</title>
+                    <guid>episode-1</guid>
+                </item>
+            </channel>
+        </rss>"#;
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router.route("/v1/search", get(super::search))
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    async fn store(db: &Database, feed: &str, body: &str) {
+        let rss: Rss = quick_xml::de::from_str(body).unwrap();
+
+        db.feed_store(feed, &rss.channel)
+            .await
+            .expect("Failed to store feed");
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn search(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool.clone()).await.unwrap();
+        store(&db, Database::SUBSCRIPTION_1_FEED, FEED).await;
+        // Nobody is subscribed to this one.
+        store(&db, "http://other.example.com/feed.rss", OTHER_FEED).await;
+
+        let app = setup_app(pool).await;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("/v1/search?q=rus")
+            .header(header::AUTHORIZATION, Database::test_token())
+            .body(Body::empty())
+            .expect("Failed to build request");
+
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let results: SearchResults =
+            serde_json::from_slice(&body).expect("Failed to deserialize response body");
+
+        assert_eq!(results.total, 1);
+        assert_eq!(results.results[0].podcast_title, "Systems Weekly");
+        assert_eq!(results.results[0].title.as_deref(), Some("Episode 1"));
+        assert!(results.results[0].episode_id.is_some());
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn empty_query(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/v1/search?q=%20";
+        let expected = ApiError::validation();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::GET)
+            .authorization(true)
+            .status(StatusCode::BAD_REQUEST)
+            .run()
+            .await;
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn unauthorized(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/v1/search?q=rust";
+        let expected = ApiError::unauthorized();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::GET)
+            .status(StatusCode::UNAUTHORIZED)
+            .run()
+            .await;
+    }
+}
@@ -1,20 +1,70 @@
 use axum::extract::State;
 use axum_extra::either::Either3;
+use url::Url;
 
 use crate::{
+    database::{subscription::history::Origin, user::User},
     extractor::auth::Session,
     models::{
-        subscriptions::{AddSubscriptions, NewSubscriptions},
+        subscriptions::{AddSubscriptions, Client, FailedSubscription, Feed, NewSubscriptions},
         Unauthorized, Validation,
     },
     utils::serde::Deserializable,
     SyncState,
 };
 
+/// Subscribes the user to each feed, collecting the ones that couldn't be added instead of failing
+/// the whole request.
+pub async fn subscribe_feeds(
+    sync: &SyncState,
+    user: &User,
+    origin: Origin,
+    feeds: Vec<Feed>,
+) -> NewSubscriptions {
+    let mut success = Vec::with_capacity(feeds.len());
+    let mut failure = Vec::new();
+
+    for feed in feeds {
+        let feed_url = match Url::parse(&feed.feed_url) {
+            Ok(feed_url) => feed_url,
+            Err(err) => {
+                tracing::debug!(url = %feed.feed_url, err = %err, "Feed URL is not valid");
+
+                failure.push(FailedSubscription {
+                    feed_url: feed.feed_url,
+                    message: "Feed URL is not valid".to_string(),
+                });
+
+                continue;
+            }
+        };
+
+        match sync
+            .db
+            .subscription_create(user, origin, &feed_url, feed.guid)
+            .await
+        {
+            Ok(subscription) => success.push(subscription),
+            Err(err) => {
+                tracing::error!(err = ?err, "Failed to create user subscription");
+
+                failure.push(FailedSubscription {
+                    feed_url: feed.feed_url,
+                    message: "Subscription could not be created".to_string(),
+                });
+            }
+        }
+    }
+
+    NewSubscriptions { success, failure }
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
 pub async fn add(
     State(sync): State<SyncState>,
     session: Option<Session>,
-    Deserializable(encoding, add): Deserializable<AddSubscriptions>,
+    Deserializable(_encoding, add): Deserializable<AddSubscriptions>,
 ) -> Either3<NewSubscriptions, Unauthorized, Validation> {
     let Some(session) = session else {
         return Either3::E2(Unauthorized);
@@ -23,15 +73,81 @@ pub async fn add(
         return Either3::E2(Unauthorized);
     }
 
-    for feed in add.subscriptions {
-        tracing::info!(url = %feed.feed_url, "Feed");
-    }
-
-    Either3::E1(NewSubscriptions {
-        success: vec![],
-        failure: vec![],
-    })
+    Either3::E1(
+        subscribe_feeds(
+            &sync,
+            &session.user,
+            Origin::new(Client::Api),
+            add.subscriptions,
+        )
+        .await,
+    )
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Method, StatusCode},
+        routing::post,
+        Router,
+    };
+
+    use crate::{
+        handlers::test_app,
+        models::{
+            subscriptions::{FailedSubscription, NewSubscriptions},
+            ApiError,
+        },
+        utils::test::{Format, TestBuilder},
+    };
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router.route("/v1/subscriptions", post(super::add))
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn invalid_feed(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/v1/subscriptions";
+        let body = r#"{"subscriptions":[{"feed_url":"not a feed"}]}"#;
+        let expected = NewSubscriptions {
+            success: vec![],
+            failure: vec![FailedSubscription {
+                feed_url: "not a feed".to_string(),
+                message: "Feed URL is not valid".to_string(),
+            }],
+        };
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::POST)
+            .authorization(true)
+            .body(Format::Json, Body::from(body))
+            .status(StatusCode::OK)
+            .run()
+            .await;
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn unauthorized(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/v1/subscriptions";
+        let body = r#"{"subscriptions":[{"feed_url":"http://four.example.com/feed.rss"}]}"#;
+        let expected = ApiError::unauthorized();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::POST)
+            .body(Format::Json, Body::from(body))
+            .status(StatusCode::UNAUTHORIZED)
+            .run()
+            .await;
+    }
+}
@@ -3,8 +3,12 @@ use axum_extra::either::Either5;
 use uuid::Uuid;
 
 use crate::{
+    database::subscription::history::Origin,
     extractor::auth::Session,
-    models::{subscriptions::DeletionReceived, InternalError, NotFound, Unauthorized, Validation},
+    models::{
+        subscriptions::{Client, DeletionReceived},
+        InternalError, NotFound, Unauthorized, Validation,
+    },
     SyncState,
 };
 
@@ -22,7 +26,15 @@ pub async fn delete(
         return Either5::E2(Unauthorized);
     }
 
-    let id = sync.db.deletion_create(&session.user, guid).await;
+    let id = sync
+        .db
+        .deletion_create(
+            &session.user,
+            Origin::new(Client::Api),
+            guid,
+            sync.cfg.deletion_grace(),
+        )
+        .await;
 
     match id {
         Ok(Some(id)) => Either5::E1(DeletionReceived::new(id)),
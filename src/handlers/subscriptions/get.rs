@@ -1,4 +1,4 @@
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum_extra::either::Either6;
 use uuid::Uuid;
 
@@ -10,12 +10,20 @@ use crate::{
     SyncState,
 };
 
+#[derive(serde::Deserialize)]
+pub struct GetParams {
+    /// Include the feed fetch health of the subscription.
+    #[serde(default)]
+    pub health: bool,
+}
+
 #[tracing::instrument(skip_all)]
 #[autometrics::autometrics]
 pub async fn get(
     State(sync): State<SyncState>,
     session: Option<Session>,
     Path(guid): Path<Uuid>,
+    Query(params): Query<GetParams>,
 ) -> Either6<Subscription, Unauthorized, NotFound, Validation, Gone, InternalError> {
     let Some(session) = session else {
         return Either6::E2(Unauthorized);
@@ -24,7 +32,7 @@ pub async fn get(
         return Either6::E2(Unauthorized);
     }
 
-    let subscription = match sync.db.subscription_get_by_guid(&session.user, guid).await {
+    let mut subscription = match sync.db.subscription_get_by_guid(&session.user, guid).await {
         Ok(Some(subscription)) => subscription,
         Ok(None) => return Either6::E3(NotFound),
         Err(err) => {
@@ -38,22 +46,44 @@ pub async fn get(
         return Either6::E5(Gone);
     }
 
+    if params.health {
+        match sync
+            .db
+            .feed_health_get(subscription.feed_url.as_str())
+            .await
+        {
+            Ok(health) => subscription.health = health,
+            Err(err) => {
+                tracing::error!(err = ?err, "Failed to retrieve feed health");
+
+                return Either6::E6(InternalError);
+            }
+        }
+    }
+
     Either6::E1(subscription)
 }
 
 #[cfg(test)]
 mod tests {
     use axum::{
-        http::{Method, StatusCode},
+        body::Body,
+        http::{header, Method, Request, StatusCode},
         routing::get,
         Router,
     };
+    use http_body_util::BodyExt as _;
+    use time::OffsetDateTime;
+    use tower::ServiceExt as _;
     use url::Url;
 
     use crate::{
-        database::Database,
+        database::{feed::FeedFetchResult, Database},
         handlers::test_app,
-        models::{subscriptions::Subscription, ApiError},
+        models::{
+            subscriptions::{FeedStatus, Subscription},
+            ApiError,
+        },
         utils::test::TestBuilder,
     };
 
@@ -79,6 +109,8 @@ mod tests {
             new_guid: None,
             guid_changed: None,
             deleted: None,
+            tags: vec![],
+            health: None,
         };
 
         TestBuilder::new(app, url, expected)
@@ -89,6 +121,70 @@ mod tests {
             .await;
     }
 
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn health(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool.clone())
+            .await
+            .expect("Failed to create database");
+        db.feed_fetches_enqueue(3600)
+            .await
+            .expect("Failed to enqueue feed fetches");
+        let fetch = db
+            .feed_fetches_get_due(10)
+            .await
+            .expect("Failed to get due feed fetches")
+            .into_iter()
+            .find(|fetch| fetch.feed == Database::SUBSCRIPTION_1_FEED)
+            .expect("Feed was not enqueued");
+        let result = FeedFetchResult {
+            status: FeedStatus::Failure,
+            http_code: Some(404),
+            error: Some("Not Found"),
+            etag: None,
+            last_modified: None,
+        };
+        let suspended = db
+            .feed_fetch_record(fetch.id, &result, 3600, OffsetDateTime::now_utc(), 1)
+            .await
+            .expect("Failed to record feed fetch");
+
+        assert!(suspended);
+
+        let app = setup_app(pool).await;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(format!(
+                "/v1/subscriptions/{}?health=true",
+                Database::SUBSCRIPTION_1_GUID
+            ))
+            .header(header::AUTHORIZATION, Database::test_token())
+            .body(Body::empty())
+            .expect("Failed to build request");
+
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let subscription: Subscription =
+            serde_json::from_slice(&body).expect("Failed to deserialize response body");
+        let health = subscription.health.expect("Health was not included");
+
+        assert_eq!(health.status, Some(FeedStatus::Failure));
+        assert_eq!(health.http_code, Some(404));
+        assert_eq!(health.error.as_deref(), Some("Not Found"));
+        assert_eq!(health.consecutive_failures, 1);
+        assert!(health.last_success.is_none());
+        assert!(health.suspended.is_some());
+    }
+
     #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
     async fn unauthorized(pool: sqlx::SqlitePool) {
         let _ = tracing_subscriber::fmt().with_test_writer().try_init();
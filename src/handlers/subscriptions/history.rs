@@ -0,0 +1,185 @@
+use axum::extract::{Path, State};
+use axum_extra::either::Either4;
+use uuid::Uuid;
+
+use crate::{
+    extractor::auth::Session,
+    models::{
+        subscriptions::{SubscriptionEvent, SubscriptionHistory},
+        InternalError, NotFound, Unauthorized,
+    },
+    SyncState,
+};
+
+/// Lists every change made to the subscription, oldest first.
+///
+/// Unlike fetching the subscription this still works once it has been deleted, which is mostly
+/// useful to figure out how a client ended up out of sync.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn history(
+    State(sync): State<SyncState>,
+    session: Option<Session>,
+    Path(guid): Path<Uuid>,
+) -> Either4<SubscriptionHistory, Unauthorized, NotFound, InternalError> {
+    let Some(session) = session else {
+        return Either4::E2(Unauthorized);
+    };
+    if !session.validate() {
+        return Either4::E2(Unauthorized);
+    }
+
+    let id = match sync.db.subscription_get_id_by_guid(guid).await {
+        Ok(Some(row)) => row.subscription_id,
+        Ok(None) => return Either4::E3(NotFound),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve subscription id");
+
+            return Either4::E4(InternalError);
+        }
+    };
+
+    match sync.db.subscription_get_by_id(&session.user, id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Either4::E3(NotFound),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user subscription");
+
+            return Either4::E4(InternalError);
+        }
+    }
+
+    match sync.db.subscription_history_get(&session.user, id).await {
+        Ok(events) => Either4::E1(SubscriptionHistory {
+            events: events.into_iter().map(SubscriptionEvent::from).collect(),
+        }),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve subscription history");
+
+            Either4::E4(InternalError)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+        routing::{delete, get},
+        Router,
+    };
+    use http_body_util::BodyExt as _;
+    use tower::ServiceExt as _;
+
+    use crate::{
+        database::Database,
+        handlers::{subscriptions, test_app},
+        models::{
+            subscriptions::{Client, EventKind, SubscriptionHistory},
+            ApiError,
+        },
+        utils::test::TestBuilder,
+    };
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router
+                .route(
+                    "/v1/subscriptions/:guid",
+                    delete(subscriptions::delete::delete),
+                )
+                .route("/v1/subscriptions/:guid/history", get(super::history))
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn deletion(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+
+        let request = Request::builder()
+            .method(Method::DELETE)
+            .uri(format!(
+                "/v1/subscriptions/{}",
+                Database::SUBSCRIPTION_1_GUID
+            ))
+            .header(header::AUTHORIZATION, Database::test_token())
+            .body(Body::empty())
+            .expect("Failed to build request");
+        let response = app
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(format!(
+                "/v1/subscriptions/{}/history",
+                Database::SUBSCRIPTION_1_GUID
+            ))
+            .header(header::AUTHORIZATION, Database::test_token())
+            .body(Body::empty())
+            .expect("Failed to build request");
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let history: SubscriptionHistory =
+            serde_json::from_slice(&body).expect("Failed to deserialize response body");
+        let event = history.events.last().expect("Deletion was not recorded");
+
+        assert_eq!(event.kind, EventKind::Deleted);
+        assert_eq!(event.client, Some(Client::Api));
+        assert_eq!(event.device, None);
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn missing(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = format!(
+            "/v1/subscriptions/{}/history",
+            Database::SUBSCRIPTION_MISSING_GUID
+        );
+        let expected = ApiError::not_found();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::GET)
+            .authorization(true)
+            .status(StatusCode::NOT_FOUND)
+            .run()
+            .await;
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn unauthorized(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = format!(
+            "/v1/subscriptions/{}/history",
+            Database::SUBSCRIPTION_1_GUID
+        );
+        let expected = ApiError::unauthorized();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::GET)
+            .status(StatusCode::UNAUTHORIZED)
+            .run()
+            .await;
+    }
+}
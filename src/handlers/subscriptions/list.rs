@@ -1,16 +1,23 @@
 use axum::extract::{Query, State};
-use axum_extra::either::Either3;
+use axum_extra::either::Either4;
 use time::OffsetDateTime;
 
 use crate::{
     extractor::auth::Session,
-    models::{subscriptions::Subscriptions, InternalError, Unauthorized},
+    models::{subscriptions::Subscriptions, InternalError, NotFound, Unauthorized},
     SyncState,
 };
 
 #[derive(serde::Deserialize)]
 pub struct ListParams {
     pub since: Option<OffsetDateTime>,
+    /// The device asking, its own changes are left out of a `since` listing.
+    pub device: Option<String>,
+    /// Only list subscriptions with this tag.
+    pub tag: Option<String>,
+    /// Include the feed fetch health of each subscription.
+    #[serde(default)]
+    pub health: bool,
     pub page: Option<i64>,
     pub per_page: Option<i64>,
 }
@@ -21,42 +28,92 @@ pub async fn list(
     State(sync): State<SyncState>,
     session: Option<Session>,
     Query(params): Query<ListParams>,
-) -> Either3<Subscriptions, Unauthorized, InternalError> {
+) -> Either4<Subscriptions, Unauthorized, NotFound, InternalError> {
     let Some(session) = session else {
         tracing::info!("no session");
-        return Either3::E2(Unauthorized);
+        return Either4::E2(Unauthorized);
     };
     if !session.validate() {
         tracing::info!("session invalid");
-        return Either3::E2(Unauthorized);
+        return Either4::E2(Unauthorized);
     }
 
     let ListParams {
         since,
+        device,
+        tag,
+        health,
         page,
         per_page,
     } = params;
 
+    let exclude = match device {
+        Some(device) => match sync
+            .db
+            .device_get_by_identifier(&session.user, &device)
+            .await
+        {
+            Ok(Some(device)) => Some(device.id),
+            Ok(None) => return Either4::E3(NotFound),
+            Err(err) => {
+                tracing::error!(err = ?err, "Failed to retrieve user device");
+
+                return Either4::E4(InternalError);
+            }
+        },
+        None => None,
+    };
+
     let subscriptions = match since {
         Some(since) => {
             sync.db
-                .subscriptions_get_all_since(&session.user, since, page, per_page)
+                .subscriptions_get_all_since(
+                    &session.user,
+                    tag.as_deref(),
+                    since,
+                    exclude,
+                    page,
+                    per_page,
+                )
                 .await
         }
         None => {
             sync.db
-                .subscriptions_get_all(&session.user, page, per_page)
+                .subscriptions_get_all(&session.user, tag.as_deref(), page, per_page)
                 .await
         }
     };
 
     match subscriptions {
-        Ok(Some(subscriptions)) => Either3::E1(subscriptions),
-        Ok(None) => Either3::E1(Subscriptions::empty()),
+        Ok(Some(mut subscriptions)) => {
+            if health {
+                let feeds = subscriptions
+                    .subscriptions
+                    .iter()
+                    .map(|subscription| subscription.feed_url.as_str())
+                    .collect::<Vec<_>>();
+
+                let mut healths = match sync.db.feeds_health_get(&feeds).await {
+                    Ok(healths) => healths,
+                    Err(err) => {
+                        tracing::error!(err = ?err, "Failed to retrieve feed health");
+
+                        return Either4::E4(InternalError);
+                    }
+                };
+
+                for subscription in &mut subscriptions.subscriptions {
+                    subscription.health = healths.remove(subscription.feed_url.as_str());
+                }
+            }
+
+            Either4::E1(subscriptions)
+        }
+        Ok(None) => Either4::E1(Subscriptions::empty()),
         Err(err) => {
             tracing::error!(err = ?err, "Failed to retrieve user subscriptions");
 
-            Either3::E3(InternalError)
+            Either4::E4(InternalError)
         }
     }
 }
@@ -109,6 +166,8 @@ mod tests {
                     new_guid: Some(Database::SUBSCRIPTION_3_GUID_NEW),
                     guid_changed: None,
                     deleted: None,
+                    tags: vec![],
+                    health: None,
                 },
                 Subscription {
                     feed_url: Url::parse(Database::SUBSCRIPTION_1_FEED).unwrap(),
@@ -118,6 +177,8 @@ mod tests {
                     new_guid: None,
                     guid_changed: None,
                     deleted: None,
+                    tags: vec![],
+                    health: None,
                 },
                 Subscription {
                     feed_url: Url::parse(Database::SUBSCRIPTION_2_FEED_NEW).unwrap(),
@@ -127,6 +188,8 @@ mod tests {
                     new_guid: None,
                     guid_changed: None,
                     deleted: None,
+                    tags: vec![],
+                    health: None,
                 },
             ],
         };
@@ -139,6 +202,160 @@ mod tests {
             .await;
     }
 
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn health(pool: sqlx::SqlitePool) {
+        use axum::{
+            body::Body,
+            http::{header, Request},
+        };
+        use http_body_util::BodyExt as _;
+        use time::OffsetDateTime;
+        use tower::ServiceExt as _;
+
+        use crate::{database::feed::FeedFetchResult, models::subscriptions::FeedStatus};
+
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool.clone())
+            .await
+            .expect("Failed to create database");
+        db.feed_fetches_enqueue(3600)
+            .await
+            .expect("Failed to enqueue feed fetches");
+        let fetch = db
+            .feed_fetch_get(Database::SUBSCRIPTION_1_FEED)
+            .await
+            .expect("Failed to get feed fetch")
+            .expect("Feed was not enqueued");
+        let result = FeedFetchResult {
+            status: FeedStatus::Failure,
+            http_code: Some(404),
+            error: Some("Not Found"),
+            etag: None,
+            last_modified: None,
+        };
+        db.feed_fetch_record(fetch.id, &result, 3600, OffsetDateTime::now_utc(), 5)
+            .await
+            .expect("Failed to record feed fetch");
+
+        let app = setup_app(pool).await;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("/v1/subscriptions?health=true")
+            .header(header::AUTHORIZATION, Database::test_token())
+            .body(Body::empty())
+            .expect("Failed to build request");
+
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let subscriptions: Subscriptions =
+            serde_json::from_slice(&body).expect("Failed to deserialize response body");
+
+        assert_eq!(subscriptions.subscriptions.len(), 3);
+        for subscription in subscriptions.subscriptions {
+            let health = subscription.health.expect("Health was not included");
+
+            if subscription.guid == Database::SUBSCRIPTION_1_GUID {
+                assert_eq!(health.status, Some(FeedStatus::Failure));
+                assert_eq!(health.http_code, Some(404));
+                assert_eq!(health.consecutive_failures, 1);
+            } else {
+                assert_eq!(health.status, None);
+                assert!(health.last_fetch.is_none());
+            }
+        }
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn since_device(pool: sqlx::SqlitePool) {
+        use axum::{
+            body::Body,
+            http::{header, Request},
+        };
+        use http_body_util::BodyExt as _;
+        use time::OffsetDateTime;
+        use tower::ServiceExt as _;
+
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool.clone())
+            .await
+            .expect("Failed to create database");
+        let user = db
+            .user_get_by_id(Database::USER_ID)
+            .await
+            .expect("Failed to get user")
+            .expect("Test user is missing");
+        let device = db
+            .device_upsert(&user, "phone", None, None)
+            .await
+            .expect("Failed to create device");
+        sqlx::query!(
+            r#"--sql
+                UPDATE user_subscriptions
+                SET device_id = ?1
+                WHERE subscription_id = ?2
+            "#,
+            device.id,
+            Database::SUBSCRIPTION_1_ID,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to set subscription device");
+
+        let app = setup_app(pool).await;
+        let since = serde_json::to_value(OffsetDateTime::now_utc() - time::Duration::hours(1))
+            .expect("Failed to serialize since");
+        let since = since.as_str().expect("Since is not a string");
+
+        for (device, expected) in [(None, 3), (Some("phone"), 2)] {
+            let mut query = url::form_urlencoded::Serializer::new(String::new());
+            query.append_pair("since", since);
+            if let Some(device) = device {
+                query.append_pair("device", device);
+            }
+
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(format!("/v1/subscriptions?{}", query.finish()))
+                .header(header::AUTHORIZATION, Database::test_token())
+                .body(Body::empty())
+                .expect("Failed to build request");
+            let response = app
+                .clone()
+                .oneshot(request)
+                .await
+                .expect("Failed to run request");
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let body = response
+                .into_body()
+                .collect()
+                .await
+                .expect("Failed to collect response body")
+                .to_bytes();
+            let subscriptions: Subscriptions =
+                serde_json::from_slice(&body).expect("Failed to deserialize response body");
+
+            assert_eq!(subscriptions.subscriptions.len(), expected);
+            assert!(
+                device.is_none()
+                    || subscriptions
+                        .subscriptions
+                        .iter()
+                        .all(|subscription| subscription.guid != Database::SUBSCRIPTION_1_GUID)
+            );
+        }
+    }
+
     #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
     async fn unauthorized(pool: sqlx::SqlitePool) {
         let _ = tracing_subscriber::fmt().with_test_writer().try_init();
@@ -153,4 +370,29 @@ mod tests {
             .run()
             .await;
     }
+
+    /// Basic auth is only for the gpodder.net and Nextcloud routes, even with a valid app password.
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn basic_auth(pool: sqlx::SqlitePool) {
+        use axum::{body::Body, http::Request};
+        use headers::{authorization::Credentials as _, Authorization};
+        use tower::ServiceExt as _;
+
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("/v1/subscriptions")
+            .header(
+                axum::http::header::AUTHORIZATION,
+                Authorization::basic("example", Database::TOKEN).0.encode(),
+            )
+            .body(Body::empty())
+            .expect("Failed to build request");
+
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
 }
@@ -1,8 +1,12 @@
 pub mod add;
 pub mod delete;
 pub mod get;
+pub mod history;
 pub mod list;
+pub mod opml;
+pub mod restore;
 pub mod status;
+pub mod tags;
 pub mod update;
 
 use axum::routing;
@@ -12,6 +16,10 @@ pub fn app() -> axum::Router<crate::SyncState> {
     axum::Router::new()
         // Subscriptions
         .route("/v1/subscriptions", routing::get(list::list).post(add::add))
+        .route("/v1/subscriptions/opml", routing::get(opml::export).post(opml::import))
         .route("/v1/subscriptions/:guid", routing::get(get::get).patch(update::update).delete(delete::delete))
+        .route("/v1/subscriptions/:guid/tags", routing::put(tags::set))
+        .route("/v1/subscriptions/:guid/history", routing::get(history::history))
+        .route("/v1/subscriptions/:guid/restore", routing::post(restore::restore))
         .route("/v1/deletions/:deletion_id", routing::get(status::status))
 }
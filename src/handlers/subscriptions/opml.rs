@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+
+use axum::extract::State;
+use axum_extra::either::Either3;
+use url::Url;
+use uuid::Uuid;
+
+use crate::{
+    database::{subscription::history::Origin, user::User},
+    extractor::auth::Session,
+    handlers::subscriptions::add::subscribe_feeds,
+    models::{
+        opml::Opml,
+        subscriptions::{Client, FailedSubscription, Feed, NewSubscriptions},
+        InternalError, Unauthorized, Validation,
+    },
+    utils::opml::OpmlDocument,
+    SyncState,
+};
+
+/// Subscribes the user to every feed in the document, outlines without a feed URL are reported as
+/// failures.
+///
+/// The folders and categories of each feed are added to the subscription's tags.
+pub async fn import_opml(
+    sync: &SyncState,
+    user: &User,
+    origin: Origin,
+    opml: &Opml,
+) -> NewSubscriptions {
+    let mut feeds = Vec::new();
+    let mut tags = HashMap::new();
+    let mut failure = Vec::new();
+
+    for feed in opml.feeds() {
+        match &feed.outline.xml_url {
+            Some(xml_url) => {
+                if let Ok(url) = Url::parse(xml_url) {
+                    tags.insert(url, feed.tags);
+                }
+
+                feeds.push(Feed {
+                    feed_url: xml_url.clone(),
+                    guid: None,
+                });
+            }
+            None => failure.push(FailedSubscription {
+                feed_url: feed.outline.name().unwrap_or_default().to_string(),
+                message: "Outline is missing a feed URL".to_string(),
+            }),
+        }
+    }
+
+    let mut report = subscribe_feeds(sync, user, origin, feeds).await;
+    report.failure.extend(failure);
+
+    for subscription in &report.success {
+        let Some(tags) = tags
+            .get(&subscription.feed_url)
+            .filter(|tags| !tags.is_empty())
+        else {
+            continue;
+        };
+
+        if let Err(err) = tag_subscription(sync, user, subscription.guid, tags).await {
+            tracing::error!(err = ?err, "Failed to tag imported user subscription");
+        }
+    }
+
+    report
+}
+
+/// Adds the tags to the subscription, keeping the tags it already has.
+pub async fn tag_subscription(
+    sync: &SyncState,
+    user: &User,
+    guid: Uuid,
+    tags: &[String],
+) -> anyhow::Result<()> {
+    let Some(row) = sync.db.subscription_get_id_by_guid(guid).await? else {
+        return Ok(());
+    };
+
+    let mut merged = sync
+        .db
+        .subscription_get_tags(user, row.subscription_id)
+        .await?;
+    merged.extend(tags.iter().cloned());
+    merged.sort();
+    merged.dedup();
+
+    sync.db
+        .subscription_set_tags(user, row.subscription_id, &merged)
+        .await
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn import(
+    State(sync): State<SyncState>,
+    session: Option<Session>,
+    OpmlDocument(opml): OpmlDocument<Opml>,
+) -> Either3<NewSubscriptions, Unauthorized, Validation> {
+    let Some(session) = session else {
+        return Either3::E2(Unauthorized);
+    };
+    if !session.validate() {
+        return Either3::E2(Unauthorized);
+    }
+
+    Either3::E1(import_opml(&sync, &session.user, Origin::new(Client::Api), &opml).await)
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn export(
+    State(sync): State<SyncState>,
+    session: Option<Session>,
+) -> Either3<Opml, Unauthorized, InternalError> {
+    let Some(session) = session else {
+        return Either3::E2(Unauthorized);
+    };
+    if !session.validate() {
+        return Either3::E2(Unauthorized);
+    }
+
+    match Opml::export(&sync.db, &session.user).await {
+        Ok(opml) => Either3::E1(opml),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to export user subscriptions");
+
+            Either3::E3(InternalError)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use http_body_util::BodyExt as _;
+    use pretty_assertions::assert_eq;
+    use tower::ServiceExt as _;
+
+    use crate::{
+        database::Database,
+        handlers::test_app,
+        models::{
+            opml::Opml,
+            subscriptions::{FailedSubscription, NewSubscriptions},
+            ApiError,
+        },
+        utils::test::{Format, TestBuilder},
+    };
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router.route(
+                "/v1/subscriptions/opml",
+                get(super::export).post(super::import),
+            )
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn import_missing_feed(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/v1/subscriptions/opml";
+        let body = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <opml version="2.0">
+                <head><title>Podcasts</title></head>
+                <body>
+                    <outline text="Folder">
+                        <outline text="Missing" type="rss" />
+                    </outline>
+                </body>
+            </opml>"#;
+        let expected = NewSubscriptions {
+            success: vec![],
+            failure: vec![FailedSubscription {
+                feed_url: "Missing".to_string(),
+                message: "Outline is missing a feed URL".to_string(),
+            }],
+        };
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::POST)
+            .authorization(true)
+            .body(Format::Xml, Body::from(body))
+            .status(StatusCode::OK)
+            .run()
+            .await;
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn export(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("/v1/subscriptions/opml")
+            .header(header::AUTHORIZATION, Database::test_token())
+            .body(Body::empty())
+            .expect("Failed to build request");
+
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/x-opml"
+        );
+
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let text = String::from_utf8(body.to_vec()).expect("Failed to convert body into UTF-8");
+        let opml: Opml = quick_xml::de::from_str(&text).expect("Failed to deserialize OPML body");
+
+        let feeds = opml
+            .feeds()
+            .into_iter()
+            .filter_map(|feed| feed.outline.xml_url.as_deref())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            feeds,
+            vec![
+                Database::SUBSCRIPTION_3_FEED,
+                Database::SUBSCRIPTION_1_FEED,
+                Database::SUBSCRIPTION_2_FEED_NEW,
+            ]
+        );
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn unauthorized(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/v1/subscriptions/opml";
+        let expected = ApiError::unauthorized();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::GET)
+            .status(StatusCode::UNAUTHORIZED)
+            .run()
+            .await;
+    }
+}
@@ -0,0 +1,181 @@
+use axum::extract::{Path, State};
+use axum_extra::either::Either4;
+use uuid::Uuid;
+
+use crate::{
+    database::subscription::history::Origin,
+    extractor::auth::Session,
+    models::{
+        subscriptions::{Client, Subscription},
+        InternalError, NotFound, Unauthorized,
+    },
+    SyncState,
+};
+
+/// Undoes the deletion of a subscription that has not been purged yet.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn restore(
+    State(sync): State<SyncState>,
+    session: Option<Session>,
+    Path(guid): Path<Uuid>,
+) -> Either4<Subscription, Unauthorized, NotFound, InternalError> {
+    let Some(session) = session else {
+        return Either4::E2(Unauthorized);
+    };
+    if !session.validate() {
+        return Either4::E2(Unauthorized);
+    }
+
+    let id = match sync.db.subscription_get_id_by_guid(guid).await {
+        Ok(Some(row)) => row.subscription_id,
+        Ok(None) => return Either4::E3(NotFound),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve subscription id");
+
+            return Either4::E4(InternalError);
+        }
+    };
+
+    match sync
+        .db
+        .deletion_restore(&session.user, Origin::new(Client::Api), id)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => return Either4::E3(NotFound),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to restore subscription");
+
+            return Either4::E4(InternalError);
+        }
+    }
+
+    match sync.db.subscription_get_by_id(&session.user, id).await {
+        Ok(Some(subscription)) => Either4::E1(subscription),
+        Ok(None) => Either4::E3(NotFound),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user subscription");
+
+            Either4::E4(InternalError)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+        routing::{delete, post},
+        Router,
+    };
+    use http_body_util::BodyExt as _;
+    use tower::ServiceExt as _;
+
+    use crate::{
+        database::Database,
+        handlers::{subscriptions, test_app},
+        models::{subscriptions::Subscription, ApiError},
+        utils::test::TestBuilder,
+    };
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router
+                .route(
+                    "/v1/subscriptions/:guid",
+                    delete(subscriptions::delete::delete),
+                )
+                .route("/v1/subscriptions/:guid/restore", post(super::restore))
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn restore(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+
+        let request = Request::builder()
+            .method(Method::DELETE)
+            .uri(format!(
+                "/v1/subscriptions/{}",
+                Database::SUBSCRIPTION_1_GUID
+            ))
+            .header(header::AUTHORIZATION, Database::test_token())
+            .body(Body::empty())
+            .expect("Failed to build request");
+        let response = app
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(format!(
+                "/v1/subscriptions/{}/restore",
+                Database::SUBSCRIPTION_1_GUID
+            ))
+            .header(header::AUTHORIZATION, Database::test_token())
+            .body(Body::empty())
+            .expect("Failed to build request");
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let subscription: Subscription =
+            serde_json::from_slice(&body).expect("Failed to deserialize response body");
+
+        assert_eq!(subscription.guid, Database::SUBSCRIPTION_1_GUID);
+        assert!(subscription.is_subscribed);
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn not_deleted(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = format!(
+            "/v1/subscriptions/{}/restore",
+            Database::SUBSCRIPTION_2_GUID
+        );
+        let expected = ApiError::not_found();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::POST)
+            .authorization(true)
+            .status(StatusCode::NOT_FOUND)
+            .run()
+            .await;
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn unauthorized(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = format!(
+            "/v1/subscriptions/{}/restore",
+            Database::SUBSCRIPTION_1_GUID
+        );
+        let expected = ApiError::unauthorized();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::POST)
+            .status(StatusCode::UNAUTHORIZED)
+            .run()
+            .await;
+    }
+}
@@ -0,0 +1,137 @@
+use axum::extract::{Path, State};
+use axum_extra::either::Either5;
+use uuid::Uuid;
+
+use crate::{
+    extractor::auth::Session,
+    models::{tags::SubscriptionTags, InternalError, NotFound, Unauthorized, Validation},
+    utils::serde::Deserializable,
+    SyncState,
+};
+
+/// Replaces the tags of one of the user's subscriptions, tags that don't exist yet are created.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn set(
+    State(sync): State<SyncState>,
+    session: Option<Session>,
+    Path(guid): Path<Uuid>,
+    Deserializable(_encoding, body): Deserializable<SubscriptionTags>,
+) -> Either5<SubscriptionTags, Unauthorized, NotFound, Validation, InternalError> {
+    let Some(session) = session else {
+        return Either5::E2(Unauthorized);
+    };
+    if !session.validate() {
+        return Either5::E2(Unauthorized);
+    }
+
+    let mut tags = Vec::with_capacity(body.tags.len());
+    for tag in body.tags {
+        let tag = tag.trim();
+        if tag.is_empty() || tag.chars().count() > 64 {
+            return Either5::E4(Validation);
+        }
+
+        tags.push(tag.to_string());
+    }
+    tags.sort();
+    tags.dedup();
+
+    let id = match sync.db.subscription_get_id_by_guid(guid).await {
+        Ok(Some(row)) => row.subscription_id,
+        Ok(None) => return Either5::E3(NotFound),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve subscription");
+
+            return Either5::E5(InternalError);
+        }
+    };
+
+    match sync.db.subscription_get_by_id(&session.user, id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Either5::E3(NotFound),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user subscription");
+
+            return Either5::E5(InternalError);
+        }
+    }
+
+    if let Err(err) = sync
+        .db
+        .subscription_set_tags(&session.user, id, &tags)
+        .await
+    {
+        tracing::error!(err = ?err, "Failed to set user subscription tags");
+
+        return Either5::E5(InternalError);
+    }
+
+    Either5::E1(SubscriptionTags { tags })
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Method, StatusCode},
+        routing::put,
+        Router,
+    };
+
+    use crate::{
+        database::Database,
+        handlers::test_app,
+        models::{tags::SubscriptionTags, ApiError},
+        utils::test::{Format, TestBuilder},
+    };
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router.route("/v1/subscriptions/:guid/tags", put(super::set))
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn ok(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = format!("/v1/subscriptions/{}/tags", Database::SUBSCRIPTION_1_GUID);
+        let body = r#"{"tags":["News"," Tech ","News"]}"#;
+        let expected = SubscriptionTags {
+            tags: vec!["News".to_string(), "Tech".to_string()],
+        };
+
+        TestBuilder::new(app, url.as_str(), expected)
+            .method(Method::PUT)
+            .authorization(true)
+            .body(Format::Json, Body::from(body))
+            .status(StatusCode::OK)
+            .run()
+            .await;
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn not_found(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = format!(
+            "/v1/subscriptions/{}/tags",
+            Database::SUBSCRIPTION_MISSING_GUID
+        );
+        let body = r#"{"tags":["News"]}"#;
+        let expected = ApiError::not_found();
+
+        TestBuilder::new(app, url.as_str(), expected)
+            .method(Method::PUT)
+            .authorization(true)
+            .body(Format::Json, Body::from(body))
+            .status(StatusCode::NOT_FOUND)
+            .run()
+            .await;
+    }
+}
@@ -1,11 +1,15 @@
 use axum::extract::{Path, State};
-use axum_extra::either::Either4;
+use axum_extra::either::Either5;
 use url::Url;
 use uuid::Uuid;
 
 use crate::{
+    database::subscription::{history::Origin, mutation::UpdatedSubscription},
     extractor::auth::Session,
-    models::{subscriptions::SubscriptionUpdate, NotFound, Unauthorized, Validation},
+    models::{
+        subscriptions::{Client, SubscriptionUpdate},
+        Conflict, InternalError, NotFound, Unauthorized,
+    },
     utils::serde::Deserializable,
     SyncState,
 };
@@ -17,21 +21,184 @@ pub struct UpdateBody {
     pub is_subscribed: bool,
 }
 
+/// Moves the subscription to a new feed and GUID, the feed or GUID can't belong to another
+/// subscription.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
 pub async fn update(
     State(sync): State<SyncState>,
     session: Option<Session>,
     Path(guid): Path<Uuid>,
-    Deserializable(encoding, request): Deserializable<UpdateBody>,
-) -> Either4<SubscriptionUpdate, Unauthorized, NotFound, Validation> {
+    Deserializable(_encoding, request): Deserializable<UpdateBody>,
+) -> Either5<SubscriptionUpdate, Unauthorized, NotFound, Conflict, InternalError> {
     let Some(session) = session else {
-        return Either4::E2(Unauthorized);
+        return Either5::E2(Unauthorized);
     };
     if !session.validate() {
-        return Either4::E2(Unauthorized);
+        return Either5::E2(Unauthorized);
     }
 
-    todo!()
+    let id = match sync.db.subscription_get_id_by_guid(guid).await {
+        Ok(Some(row)) => row.subscription_id,
+        Ok(None) => return Either5::E3(NotFound),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve subscription");
+
+            return Either5::E5(InternalError);
+        }
+    };
+
+    let updated = sync
+        .db
+        .subscription_update(
+            &session.user,
+            Origin::new(Client::Api),
+            id,
+            &request.new_feed_url,
+            request.new_guid,
+            request.is_subscribed,
+        )
+        .await;
+
+    match updated {
+        Ok(UpdatedSubscription::Updated) => Either5::E1(SubscriptionUpdate {
+            new_feed_url: request.new_feed_url,
+            guid: request.new_guid,
+            is_subscribed: request.is_subscribed,
+        }),
+        Ok(UpdatedSubscription::NotFound) => Either5::E3(NotFound),
+        Ok(UpdatedSubscription::Conflict) => Either5::E4(Conflict),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to update subscription");
+
+            Either5::E5(InternalError)
+        }
+    }
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Method, StatusCode},
+        routing::patch,
+        Router,
+    };
+    use url::Url;
+    use uuid::Uuid;
+
+    use crate::{
+        database::{subscription::SubscriptionId, Database},
+        handlers::test_app,
+        models::{
+            subscriptions::{EventKind, SubscriptionUpdate},
+            ApiError,
+        },
+        utils::test::{Format, TestBuilder},
+    };
+
+    const NEW_FEED: &str = "http://one-new.example.com/feed.rss";
+    const NEW_GUID: Uuid = uuid::uuid!("0a5b9a1e-3f2d-4c41-9d0e-6b7a2e4f8c11");
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router.route("/v1/subscriptions/:guid", patch(super::update))
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn ok(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool.clone()).await.unwrap();
+        let app = setup_app(pool).await;
+        let url = format!("/v1/subscriptions/{}", Database::SUBSCRIPTION_1_GUID);
+        let body = format!(
+            r#"{{"new_feed_url":"{}","new_guid":"{}","is_subscribed":true}}"#,
+            NEW_FEED, NEW_GUID,
+        );
+        let expected = SubscriptionUpdate {
+            new_feed_url: Url::parse(NEW_FEED).unwrap(),
+            guid: NEW_GUID,
+            is_subscribed: true,
+        };
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::PATCH)
+            .authorization(true)
+            .body(Format::Json, Body::from(body))
+            .status(StatusCode::OK)
+            .run()
+            .await;
+
+        let user = db.user_get_by_id(Database::USER_ID).await.unwrap().unwrap();
+        let kinds = db
+            .subscription_history_get(&user, SubscriptionId(Database::SUBSCRIPTION_1_ID))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|event| event.kind)
+            .collect::<Vec<_>>();
+
+        // The user was already subscribed, so only the moves are recorded.
+        assert_eq!(kinds, [EventKind::FeedMoved, EventKind::GuidChanged]);
+
+        let feeds = db
+            .subscription_get_feeds(SubscriptionId(Database::SUBSCRIPTION_1_ID))
+            .await
+            .unwrap();
+
+        assert_eq!(feeds.len(), 2);
+        assert!(feeds
+            .iter()
+            .any(|feed| feed.feed == NEW_FEED && feed.deleted.is_none()));
+        assert!(feeds
+            .iter()
+            .any(|feed| feed.feed == Database::SUBSCRIPTION_1_FEED && feed.deleted.is_some()));
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn conflict(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = format!("/v1/subscriptions/{}", Database::SUBSCRIPTION_1_GUID);
+        let body = format!(
+            r#"{{"new_feed_url":"{}","new_guid":"{}","is_subscribed":true}}"#,
+            Database::SUBSCRIPTION_3_FEED,
+            NEW_GUID,
+        );
+        let expected = ApiError::conflict();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::PATCH)
+            .authorization(true)
+            .body(Format::Json, Body::from(body))
+            .status(StatusCode::CONFLICT)
+            .run()
+            .await;
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn not_found(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = format!("/v1/subscriptions/{}", Database::SUBSCRIPTION_MISSING_GUID);
+        let body = format!(
+            r#"{{"new_feed_url":"{}","new_guid":"{}","is_subscribed":true}}"#,
+            NEW_FEED, NEW_GUID,
+        );
+        let expected = ApiError::not_found();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::PATCH)
+            .authorization(true)
+            .body(Format::Json, Body::from(body))
+            .status(StatusCode::NOT_FOUND)
+            .run()
+            .await;
+    }
+}
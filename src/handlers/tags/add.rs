@@ -0,0 +1,114 @@
+use axum::extract::State;
+use axum_extra::either::Either4;
+
+use crate::{
+    extractor::auth::Session,
+    models::{
+        tags::{Tag, TagName},
+        InternalError, Unauthorized, Validation,
+    },
+    utils::serde::Deserializable,
+    SyncState,
+};
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn add(
+    State(sync): State<SyncState>,
+    session: Option<Session>,
+    Deserializable(_encoding, body): Deserializable<TagName>,
+) -> Either4<Tag, Unauthorized, Validation, InternalError> {
+    let Some(session) = session else {
+        return Either4::E2(Unauthorized);
+    };
+    if !session.validate() {
+        return Either4::E2(Unauthorized);
+    }
+
+    let Some(name) = body.name() else {
+        return Either4::E3(Validation);
+    };
+
+    match sync.db.tag_create(&session.user, name).await {
+        Ok(Some(tag)) => Either4::E1(Tag::from(tag)),
+        // the user already has a tag with this name
+        Ok(None) => Either4::E3(Validation),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to create user tag");
+
+            Either4::E4(InternalError)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Method, StatusCode},
+        routing::post,
+        Router,
+    };
+
+    use crate::{
+        handlers::test_app,
+        models::{tags::Tag, ApiError},
+        utils::test::{Format, TestBuilder},
+    };
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| router.route("/v1/tags", post(super::add)))
+            .await
+            .expect("failed to setup app")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn duplicate(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/v1/tags";
+        let body = r#"{"name":"News"}"#;
+
+        TestBuilder::new(
+            app.clone(),
+            url,
+            Tag {
+                id: 1,
+                name: "News".to_string(),
+            },
+        )
+        .method(Method::POST)
+        .authorization(true)
+        .body(Format::Json, Body::from(body))
+        .status(StatusCode::OK)
+        .run()
+        .await;
+
+        TestBuilder::new(app, url, ApiError::validation())
+            .method(Method::POST)
+            .authorization(true)
+            .body(Format::Json, Body::from(body))
+            .status(StatusCode::BAD_REQUEST)
+            .run()
+            .await;
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn empty_name(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/v1/tags";
+        let body = r#"{"name":"  "}"#;
+        let expected = ApiError::validation();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::POST)
+            .authorization(true)
+            .body(Format::Json, Body::from(body))
+            .status(StatusCode::BAD_REQUEST)
+            .run()
+            .await;
+    }
+}
@@ -0,0 +1,37 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use axum_extra::either::Either4;
+
+use crate::{
+    database::tag::TagId,
+    extractor::auth::Session,
+    models::{InternalError, NotFound, Unauthorized},
+    SyncState,
+};
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn delete(
+    State(sync): State<SyncState>,
+    session: Option<Session>,
+    Path(tag_id): Path<i64>,
+) -> Either4<StatusCode, Unauthorized, NotFound, InternalError> {
+    let Some(session) = session else {
+        return Either4::E2(Unauthorized);
+    };
+    if !session.validate() {
+        return Either4::E2(Unauthorized);
+    }
+
+    match sync.db.tag_delete(&session.user, TagId(tag_id)).await {
+        Ok(true) => Either4::E1(StatusCode::NO_CONTENT),
+        Ok(false) => Either4::E3(NotFound),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to delete user tag");
+
+            Either4::E4(InternalError)
+        }
+    }
+}
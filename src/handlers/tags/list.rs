@@ -0,0 +1,88 @@
+use axum::extract::State;
+use axum_extra::either::Either3;
+
+use crate::{
+    extractor::auth::Session,
+    models::{
+        tags::{Tag, Tags},
+        InternalError, Unauthorized,
+    },
+    SyncState,
+};
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn list(
+    State(sync): State<SyncState>,
+    session: Option<Session>,
+) -> Either3<Tags, Unauthorized, InternalError> {
+    let Some(session) = session else {
+        return Either3::E2(Unauthorized);
+    };
+    if !session.validate() {
+        return Either3::E2(Unauthorized);
+    }
+
+    match sync.db.tags_get_all(&session.user).await {
+        Ok(tags) => Either3::E1(Tags {
+            tags: tags.into_iter().map(Tag::from).collect(),
+        }),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user tags");
+
+            Either3::E3(InternalError)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        http::{Method, StatusCode},
+        routing::get,
+        Router,
+    };
+
+    use crate::{
+        handlers::test_app,
+        models::{tags::Tags, ApiError},
+        utils::test::TestBuilder,
+    };
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| router.route("/v1/tags", get(super::list)))
+            .await
+            .expect("failed to setup app")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn empty(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/v1/tags";
+        let expected = Tags { tags: vec![] };
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::GET)
+            .authorization(true)
+            .status(StatusCode::OK)
+            .run()
+            .await;
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn unauthorized(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/v1/tags";
+        let expected = ApiError::unauthorized();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::GET)
+            .status(StatusCode::UNAUTHORIZED)
+            .run()
+            .await;
+    }
+}
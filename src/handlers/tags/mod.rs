@@ -0,0 +1,14 @@
+pub mod add;
+pub mod delete;
+pub mod list;
+pub mod update;
+
+use axum::routing;
+
+#[rustfmt::skip]
+pub fn app() -> axum::Router<crate::SyncState> {
+    axum::Router::new()
+        // Tags
+        .route("/v1/tags", routing::get(list::list).post(add::add))
+        .route("/v1/tags/:tag_id", routing::patch(update::update).delete(delete::delete))
+}
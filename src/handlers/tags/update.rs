@@ -0,0 +1,56 @@
+use axum::extract::{Path, State};
+use axum_extra::either::Either5;
+
+use crate::{
+    database::tag::TagId,
+    extractor::auth::Session,
+    models::{
+        tags::{Tag, TagName},
+        InternalError, NotFound, Unauthorized, Validation,
+    },
+    utils::serde::Deserializable,
+    SyncState,
+};
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn update(
+    State(sync): State<SyncState>,
+    session: Option<Session>,
+    Path(tag_id): Path<i64>,
+    Deserializable(_encoding, body): Deserializable<TagName>,
+) -> Either5<Tag, Unauthorized, NotFound, Validation, InternalError> {
+    let Some(session) = session else {
+        return Either5::E2(Unauthorized);
+    };
+    if !session.validate() {
+        return Either5::E2(Unauthorized);
+    }
+
+    let Some(name) = body.name() else {
+        return Either5::E4(Validation);
+    };
+
+    let id = TagId(tag_id);
+
+    match sync.db.tag_get_by_id(&session.user, id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Either5::E3(NotFound),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user tag");
+
+            return Either5::E5(InternalError);
+        }
+    }
+
+    match sync.db.tag_rename(&session.user, id, name).await {
+        Ok(Some(tag)) => Either5::E1(Tag::from(tag)),
+        // the user already has a tag with the new name
+        Ok(None) => Either5::E4(Validation),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to rename user tag");
+
+            Either5::E5(InternalError)
+        }
+    }
+}
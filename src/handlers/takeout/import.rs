@@ -0,0 +1,328 @@
+use axum::extract::State;
+use axum_extra::either::Either3;
+use bytes::Bytes;
+use url::Url;
+
+use crate::{
+    database::{subscription::history::Origin, user::User},
+    extractor::auth::Session,
+    handlers::subscriptions::opml::tag_subscription,
+    models::{
+        subscriptions::{Client, FailedSubscription},
+        takeout::{Takeout, TakeoutImport},
+        Unauthorized, Validation,
+    },
+    SyncState,
+};
+
+/// Restores the takeout's subscriptions, along with their feed and GUID history, and tags into the
+/// user's account.
+///
+/// Subscriptions the user already has are merged, keeping their state and history.
+pub async fn import_takeout(
+    sync: &SyncState,
+    user: &User,
+    origin: Origin,
+    takeout: &Takeout,
+) -> TakeoutImport {
+    let mut report = TakeoutImport {
+        imported: vec![],
+        merged: vec![],
+        tags: vec![],
+        failure: vec![],
+    };
+
+    // Tags only used by subscriptions are created along with them, so they're reported too.
+    let mut tags = takeout
+        .tags
+        .iter()
+        .map(|tag| tag.name.as_str())
+        .chain(
+            takeout
+                .subscriptions
+                .iter()
+                .flat_map(|subscription| subscription.tags.iter().map(String::as_str)),
+        )
+        .collect::<Vec<_>>();
+    tags.sort_unstable();
+    tags.dedup();
+
+    for name in tags {
+        match sync.db.tag_create(user, name).await {
+            Ok(Some(tag)) => report.tags.push(tag.name),
+            Ok(None) => {}
+            Err(err) => {
+                tracing::error!(err = ?err, "Failed to create imported user tag");
+            }
+        }
+    }
+
+    for subscription in &takeout.subscriptions {
+        if Url::parse(&subscription.feed_url).is_err() {
+            report.failure.push(FailedSubscription {
+                feed_url: subscription.feed_url.clone(),
+                message: "Feed URL is not valid".to_string(),
+            });
+
+            continue;
+        }
+
+        let imported = match sync
+            .db
+            .subscription_import(user, origin, subscription)
+            .await
+        {
+            Ok(imported) => imported,
+            Err(err) => {
+                tracing::error!(err = ?err, "Failed to import user subscription");
+
+                report.failure.push(FailedSubscription {
+                    feed_url: subscription.feed_url.clone(),
+                    message: "Subscription could not be imported".to_string(),
+                });
+
+                continue;
+            }
+        };
+
+        if !subscription.tags.is_empty() {
+            if let Err(err) = tag_subscription(sync, user, imported.guid, &subscription.tags).await
+            {
+                tracing::error!(err = ?err, "Failed to tag imported user subscription");
+            }
+        }
+
+        if imported.merged {
+            report.merged.push(imported.guid);
+        } else {
+            report.imported.push(imported.guid);
+        }
+    }
+
+    report
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn import(
+    State(sync): State<SyncState>,
+    session: Option<Session>,
+    body: Bytes,
+) -> Either3<TakeoutImport, Unauthorized, Validation> {
+    let Some(session) = session else {
+        return Either3::E2(Unauthorized);
+    };
+    if !session.validate() {
+        return Either3::E2(Unauthorized);
+    }
+
+    let takeout = match Takeout::from_archive(&body) {
+        Ok(takeout) => takeout,
+        Err(err) => {
+            tracing::warn!(err = ?err, "Failed to read takeout archive");
+
+            return Either3::E3(Validation);
+        }
+    };
+
+    Either3::E1(import_takeout(&sync, &session.user, Origin::new(Client::Api), &takeout).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+        routing::post,
+        Router,
+    };
+    use http_body_util::BodyExt as _;
+    use pretty_assertions::assert_eq;
+    use time::OffsetDateTime;
+    use tower::ServiceExt as _;
+
+    use crate::{
+        database::Database,
+        handlers::test_app,
+        models::{
+            takeout::{
+                Takeout, TakeoutFeed, TakeoutGuid, TakeoutImport, TakeoutProfile,
+                TakeoutSubscription, TAKEOUT_VERSION,
+            },
+            ApiError,
+        },
+        utils::test::{Format, TestBuilder},
+    };
+
+    const NEW_FEED_OLD: &str = "http://four-old.example.com/feed.rss";
+    const NEW_FEED: &str = "http://four.example.com/feed.rss";
+    const NEW_GUID_OLD: uuid::Uuid = uuid::uuid!("5b6c3d9e-0f1a-5b2c-8d3e-4f5a6b7c8d9e");
+    const NEW_GUID: uuid::Uuid = uuid::uuid!("9a8b7c6d-5e4f-4a3b-9c2d-1e0f9a8b7c6d");
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router.route("/v1/takeout", post(super::import))
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    fn takeout() -> Takeout {
+        let now = OffsetDateTime::now_utc();
+        let before = now - time::Duration::days(7);
+
+        Takeout {
+            version: TAKEOUT_VERSION,
+            exported: now,
+            profile: TakeoutProfile {
+                username: "elsewhere".to_string(),
+                email: "elsewhere@example.com".to_string(),
+                created: now,
+            },
+            devices: vec![],
+            sessions: vec![],
+            tags: vec![],
+            subscriptions: vec![
+                TakeoutSubscription {
+                    guid: NEW_GUID_OLD,
+                    feed_url: NEW_FEED.to_string(),
+                    is_subscribed: true,
+                    subscription_changed: Some(now),
+                    tags: vec!["News".to_string()],
+                    feeds: vec![
+                        TakeoutFeed {
+                            feed: NEW_FEED_OLD.to_string(),
+                            created: before,
+                            deleted: Some(now),
+                        },
+                        TakeoutFeed {
+                            feed: NEW_FEED.to_string(),
+                            created: now,
+                            deleted: None,
+                        },
+                    ],
+                    guids: vec![
+                        TakeoutGuid {
+                            guid: NEW_GUID_OLD,
+                            created: before,
+                        },
+                        TakeoutGuid {
+                            guid: NEW_GUID,
+                            created: now,
+                        },
+                    ],
+                    history: vec![],
+                },
+                // Known by its GUID, even though the feed moved on the other server.
+                TakeoutSubscription {
+                    guid: Database::SUBSCRIPTION_1_GUID,
+                    feed_url: "http://one-new.example.com/feed.rss".to_string(),
+                    is_subscribed: true,
+                    subscription_changed: Some(now),
+                    tags: vec![],
+                    feeds: vec![],
+                    guids: vec![],
+                    history: vec![],
+                },
+            ],
+            deletions: vec![],
+            episode_actions: vec![],
+        }
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn import(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool.clone()).await.unwrap();
+        let app = setup_app(pool).await;
+        let archive = takeout().to_archive("").unwrap();
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/v1/takeout")
+            .header(header::AUTHORIZATION, Database::test_token())
+            .header(header::CONTENT_TYPE, "application/zip")
+            .body(Body::from(archive))
+            .expect("Failed to build request");
+
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let report: TakeoutImport =
+            serde_json::from_slice(&body).expect("Failed to deserialize response body");
+
+        assert_eq!(
+            report,
+            TakeoutImport {
+                imported: vec![NEW_GUID_OLD],
+                merged: vec![Database::SUBSCRIPTION_1_GUID],
+                tags: vec!["News".to_string()],
+                failure: vec![],
+            }
+        );
+
+        let id = db
+            .subscription_get_id_by_guid(NEW_GUID)
+            .await
+            .unwrap()
+            .expect("Imported subscription is missing")
+            .subscription_id;
+
+        assert_eq!(db.subscription_get_guids(id).await.unwrap().len(), 2);
+        assert_eq!(db.subscription_get_feeds(id).await.unwrap().len(), 2);
+
+        // Shared subscriptions are only matched, the archive can't move their feed.
+        assert!(db
+            .subscription_get_id_by_feed("http://one-new.example.com/feed.rss")
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            db.subscription_get_feeds(Database::SUBSCRIPTION_1_ID.into())
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn invalid(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/v1/takeout";
+        let expected = ApiError::validation();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::POST)
+            .authorization(true)
+            .body(Format::Json, Body::from("{}"))
+            .status(StatusCode::BAD_REQUEST)
+            .run()
+            .await;
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn unauthorized(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = setup_app(pool).await;
+        let url = "/v1/takeout";
+        let expected = ApiError::unauthorized();
+
+        TestBuilder::new(app, url, expected)
+            .method(Method::POST)
+            .status(StatusCode::UNAUTHORIZED)
+            .run()
+            .await;
+    }
+}
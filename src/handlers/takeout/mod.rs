@@ -0,0 +1,13 @@
+//! Moving accounts between servers, archives are exported from the web account page.
+
+pub mod import;
+
+use axum::{extract::DefaultBodyLimit, routing};
+
+use crate::models::takeout::TAKEOUT_LIMIT;
+
+#[rustfmt::skip]
+pub fn app() -> axum::Router<crate::SyncState> {
+    axum::Router::new()
+        .route("/v1/takeout", routing::post(import::import).layer(DefaultBodyLimit::max(TAKEOUT_LIMIT)))
+}
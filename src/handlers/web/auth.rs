@@ -4,15 +4,13 @@ use axum::{
     response::{IntoResponse as _, Redirect, Response},
     Form,
 };
-use axum_extra::extract::{
-    cookie::{Cookie, SameSite},
-    PrivateCookieJar,
-};
+use axum_extra::extract::PrivateCookieJar;
 use validator::{Validate as _, ValidationErrors};
 
 use crate::{
-    extractor::auth::Session,
+    extractor::auth::{session_cookie, Session},
     handlers::web::{Base, Template},
+    models::users::NewUser,
     SyncState,
 };
 
@@ -36,22 +34,12 @@ pub async fn get_register(session: Option<Session>, State(_sync): State<SyncStat
     Template(Register::new(session, None)).into_response()
 }
 
-#[derive(Debug, validator::Validate, serde::Deserialize)]
-pub struct RegisterForm {
-    #[validate(length(min = 6, max = 23))]
-    username: String,
-    #[validate(email)]
-    email: String,
-    #[validate(length(min = 8, max = 64))]
-    password: String,
-}
-
 #[tracing::instrument(skip_all)]
 #[autometrics::autometrics]
 pub async fn post_register(
     session: Option<Session>,
     State(sync): State<SyncState>,
-    Form(form): Form<RegisterForm>,
+    Form(form): Form<NewUser>,
 ) -> Response {
     if let Err(errors) = form.validate() {
         tracing::error!("{}", errors);
@@ -162,6 +150,10 @@ pub async fn post_login(
         }
     };
 
+    if !user.verify(&form.password) {
+        return (StatusCode::BAD_REQUEST, Template(Login::new(session, None))).into_response();
+    }
+
     let (token, expires) = match sync.db.session_crate(&user).await {
         Ok(pair) => pair,
         Err(err) => {
@@ -175,11 +167,7 @@ pub async fn post_login(
         }
     };
 
-    let mut cookie = Cookie::new(sync.cfg.session_name.clone(), token);
-    cookie.set_http_only(true);
-    cookie.set_path("/");
-    cookie.set_same_site(SameSite::Strict);
-    cookie.set_expires(expires);
+    let cookie = session_cookie(sync.cfg.session_name.clone(), token, expires);
 
     (jar.add(cookie), Redirect::to("/")).into_response()
 }
@@ -193,3 +181,66 @@ pub async fn get_logout(State(sync): State<SyncState>, jar: PrivateCookieJar) ->
 
     (jar.remove(session), Redirect::to("/")).into_response()
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+        routing::post,
+        Router,
+    };
+    use tower::ServiceExt as _;
+
+    use crate::{database::Database, handlers::test_app};
+
+    const PASSWORD: &str = "correct horse battery staple";
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router.route("/login", post(super::post_login))
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    fn request(password: &str) -> Request<Body> {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/login")
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(format!(
+                "username=example&password={}",
+                password.replace(' ', "+")
+            )))
+            .expect("Failed to build request")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn login(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool.clone())
+            .await
+            .expect("Failed to create database");
+        db.user_set_password("example", PASSWORD)
+            .await
+            .expect("Failed to set password");
+        let app = setup_app(pool).await;
+
+        let response = app
+            .clone()
+            .oneshot(request("not the password"))
+            .await
+            .expect("Failed to run request");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(response.headers().get(header::SET_COOKIE).is_none());
+
+        let response = app
+            .oneshot(request(PASSWORD))
+            .await
+            .expect("Failed to run request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert!(response.headers().get(header::SET_COOKIE).is_some());
+    }
+}
@@ -0,0 +1,104 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse as _, Redirect, Response},
+    Form,
+};
+
+use crate::{
+    database::device::{DeviceId, RowDevice},
+    extractor::auth::Session,
+    handlers::web::{Base, Template},
+    SyncState,
+};
+
+#[derive(askama::Template)]
+#[template(path = "devices/index.html")]
+struct DeviceList {
+    base: Base,
+    username: String,
+    devices: Vec<RowDevice>,
+    error: Option<&'static str>,
+}
+
+async fn render_list(sync: &SyncState, session: Session, error: Option<&'static str>) -> Response {
+    let devices = match sync.db.devices_get_all(&session.user).await {
+        Ok(devices) => devices,
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user devices");
+
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let status = if error.is_some() {
+        StatusCode::BAD_REQUEST
+    } else {
+        StatusCode::OK
+    };
+
+    let template = DeviceList {
+        username: session.user.username.clone(),
+        base: Base::new(Some(session)),
+        devices,
+        error,
+    };
+
+    (status, Template(template)).into_response()
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn list(
+    State(sync): State<SyncState>,
+    session: Session,
+    Path(username): Path<String>,
+) -> Response {
+    if username != session.user.username || !session.validate() {
+        return (StatusCode::UNAUTHORIZED).into_response();
+    }
+
+    render_list(&sync, session, None).await
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct RenameForm {
+    caption: String,
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn rename(
+    State(sync): State<SyncState>,
+    session: Session,
+    Path((username, id)): Path<(String, i64)>,
+    Form(form): Form<RenameForm>,
+) -> Response {
+    if username != session.user.username || !session.validate() {
+        return (StatusCode::UNAUTHORIZED).into_response();
+    }
+
+    let caption = form.caption.trim();
+    if caption.is_empty() || caption.len() > 64 {
+        return render_list(
+            &sync,
+            session,
+            Some("Device name must be 1 to 64 characters"),
+        )
+        .await;
+    }
+
+    match sync
+        .db
+        .device_rename(&session.user, DeviceId(id), caption)
+        .await
+    {
+        Ok(true) => Redirect::to(&format!("/user/{}/devices", username)).into_response(),
+        Ok(false) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to rename user device");
+
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
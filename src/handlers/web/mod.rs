@@ -1,14 +1,18 @@
 mod auth;
+mod devices;
+mod search;
+mod subscriptions;
 mod user;
 
 use axum::{
+    extract::DefaultBodyLimit,
     response::{IntoResponse, Response},
     routing,
 };
 use axum_extra::response::{Css, Html};
 use tower_helmet::HelmetLayer;
 
-use crate::extractor::auth::Session;
+use crate::{extractor::auth::Session, models::takeout::TAKEOUT_LIMIT};
 
 static STYLE: &str = include_str!("../../../public/style.css");
 
@@ -48,6 +52,22 @@ pub fn app() -> axum::Router<crate::SyncState> {
         .route("/login", routing::get(auth::get_login).post(auth::post_login))
         .route("/logout", routing::get(auth::get_logout))
         .route("/user/:username", routing::get(user::account))
+        .route("/user/:username/devices", routing::get(devices::list))
+        .route("/user/:username/devices/:id/rename", routing::post(devices::rename))
+        .route("/user/:username/delete", routing::post(user::delete))
+        .route("/user/:username/export", routing::post(user::export))
+        .route("/user/:username/export/:token", routing::get(user::download))
+        .route("/user/:username/import", routing::post(user::import).layer(DefaultBodyLimit::max(TAKEOUT_LIMIT)))
+        .route("/user/:username/search", routing::get(search::search))
+        .route("/user/:username/subscriptions", routing::get(subscriptions::list).post(subscriptions::add))
+        .route("/user/:username/subscriptions/opml", routing::get(subscriptions::export).post(subscriptions::import))
+        .route("/user/:username/subscriptions/:guid", routing::get(subscriptions::detail))
+        .route("/user/:username/subscriptions/:guid/subscribe", routing::post(subscriptions::subscribe))
+        .route("/user/:username/subscriptions/:guid/unsubscribe", routing::post(subscriptions::unsubscribe))
+        .route("/user/:username/subscriptions/:guid/delete", routing::post(subscriptions::delete))
+        .route("/user/:username/subscriptions/:guid/restore", routing::post(subscriptions::restore))
+        .route("/user/:username/subscriptions/:guid/resume", routing::post(subscriptions::resume))
+        .route("/user/:username/subscriptions/:guid/history", routing::get(subscriptions::history))
         .layer((
             HelmetLayer::with_defaults(),
         ))
@@ -0,0 +1,83 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse as _, Response},
+};
+
+use crate::{
+    database::{
+        podcast::RowPage,
+        search::{match_query, RowSearchHit},
+    },
+    extractor::auth::Session,
+    handlers::{
+        podcasts::search::SearchParams,
+        web::{Base, Template},
+    },
+    SyncState,
+};
+
+#[derive(askama::Template)]
+#[template(path = "search/index.html")]
+struct Search {
+    base: Base,
+    username: String,
+    query: String,
+    results: Option<RowPage<RowSearchHit>>,
+}
+
+impl Search {
+    fn has_previous(&self) -> bool {
+        self.results
+            .as_ref()
+            .is_some_and(|results| results.page > 1)
+    }
+
+    fn has_next(&self) -> bool {
+        self.results
+            .as_ref()
+            .is_some_and(|results| results.page * results.per_page < results.total)
+    }
+
+    fn page(&self) -> i64 {
+        self.results.as_ref().map_or(1, |results| results.page)
+    }
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn search(
+    State(sync): State<SyncState>,
+    session: Session,
+    Path(username): Path<String>,
+    Query(params): Query<SearchParams>,
+) -> Response {
+    if username != session.user.username || !session.validate() {
+        return (StatusCode::UNAUTHORIZED).into_response();
+    }
+
+    let results = match match_query(&params.q) {
+        Some(query) => match sync
+            .db
+            .search(&session.user, &query, params.page, params.per_page)
+            .await
+        {
+            Ok(results) => Some(results),
+            Err(err) => {
+                tracing::error!(err = ?err, "Failed to search user podcasts");
+
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        },
+        None => None,
+    };
+
+    let template = Search {
+        username: session.user.username.clone(),
+        base: Base::new(Some(session)),
+        query: params.q.trim().to_string(),
+        results,
+    };
+
+    (StatusCode::OK, Template(template)).into_response()
+}
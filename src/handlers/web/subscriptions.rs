@@ -0,0 +1,565 @@
+use axum::{
+    extract::{Multipart, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse as _, Redirect, Response},
+    Form,
+};
+use url::Url;
+use uuid::Uuid;
+
+use crate::{
+    database::{
+        podcast::RowPodcast,
+        subscription::{
+            history::{Origin, RowSubscriptionEvent},
+            RowSubscriptionFeed, RowSubscriptionGuid, SubscriptionId,
+        },
+        tasks::RowDeletion,
+    },
+    extractor::auth::Session,
+    handlers::{
+        subscriptions::opml::import_opml,
+        web::{Base, Template},
+    },
+    models::{
+        opml::Opml,
+        subscriptions::{
+            Client, DeletionStatus, EventKind, FeedHealth, NewSubscriptions, Subscription,
+        },
+    },
+    utils::opml::OpmlDocument,
+    SyncState,
+};
+
+struct Entry {
+    subscription: Subscription,
+    podcast: Option<RowPodcast>,
+    health: Option<FeedHealth>,
+}
+
+#[derive(askama::Template)]
+#[template(path = "subscriptions/index.html")]
+struct SubscriptionList {
+    base: Base,
+    username: String,
+    entries: Vec<Entry>,
+    page: i64,
+    next: Option<i64>,
+    previous: Option<i64>,
+    error: Option<&'static str>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ListParams {
+    pub page: Option<i64>,
+}
+
+const PER_PAGE: i64 = 25;
+
+async fn render_list(
+    sync: &SyncState,
+    session: Session,
+    page: i64,
+    error: Option<&'static str>,
+) -> Response {
+    let subscriptions = match sync
+        .db
+        .subscriptions_get_all(&session.user, None, Some(page), Some(PER_PAGE))
+        .await
+    {
+        Ok(subscriptions) => subscriptions.map(|s| s.subscriptions).unwrap_or_default(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user subscriptions");
+
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let more = subscriptions.len() as i64 == PER_PAGE;
+
+    let feeds = subscriptions
+        .iter()
+        .map(|subscription| subscription.feed_url.as_str())
+        .collect::<Vec<_>>();
+    let mut healths = match sync.db.feeds_health_get(&feeds).await {
+        Ok(healths) => healths,
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve feed health");
+
+            Default::default()
+        }
+    };
+
+    let mut entries = Vec::with_capacity(subscriptions.len());
+    for subscription in subscriptions {
+        let podcast = match sync.db.podcast_get_by_feed(&subscription.feed_url).await {
+            Ok(podcast) => podcast,
+            Err(err) => {
+                tracing::error!(err = ?err, "Failed to retrieve subscription podcast");
+
+                None
+            }
+        };
+        let health = healths.remove(subscription.feed_url.as_str());
+
+        entries.push(Entry {
+            subscription,
+            podcast,
+            health,
+        });
+    }
+
+    let status = if error.is_some() {
+        StatusCode::BAD_REQUEST
+    } else {
+        StatusCode::OK
+    };
+
+    let template = SubscriptionList {
+        username: session.user.username.clone(),
+        base: Base::new(Some(session)),
+        entries,
+        page,
+        next: more.then_some(page + 1),
+        previous: (page > 1).then_some(page - 1),
+        error,
+    };
+
+    (status, Template(template)).into_response()
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn list(
+    State(sync): State<SyncState>,
+    session: Session,
+    Path(username): Path<String>,
+    Query(params): Query<ListParams>,
+) -> Response {
+    if username != session.user.username || !session.validate() {
+        return (StatusCode::UNAUTHORIZED).into_response();
+    }
+
+    let page = params.page.unwrap_or(1).max(1);
+
+    render_list(&sync, session, page, None).await
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct AddForm {
+    feed_url: String,
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn add(
+    State(sync): State<SyncState>,
+    session: Session,
+    Path(username): Path<String>,
+    Form(form): Form<AddForm>,
+) -> Response {
+    if username != session.user.username || !session.validate() {
+        return (StatusCode::UNAUTHORIZED).into_response();
+    }
+
+    let Ok(feed_url) = Url::parse(&form.feed_url) else {
+        return render_list(&sync, session, 1, Some("Feed URL is not valid")).await;
+    };
+
+    if let Err(err) = sync
+        .db
+        .subscription_create(&session.user, Origin::new(Client::Web), &feed_url, None)
+        .await
+    {
+        tracing::error!(err = ?err, "Failed to create user subscription");
+
+        return render_list(&sync, session, 1, Some("Subscription could not be created")).await;
+    }
+
+    Redirect::to(&format!("/user/{}/subscriptions", username)).into_response()
+}
+
+#[derive(askama::Template)]
+#[template(path = "subscriptions/detail.html")]
+struct SubscriptionDetail {
+    base: Base,
+    username: String,
+    subscription: Subscription,
+    podcast: Option<RowPodcast>,
+    health: Option<FeedHealth>,
+    feeds: Vec<RowSubscriptionFeed>,
+    guids: Vec<RowSubscriptionGuid>,
+    deletions: Vec<RowDeletion>,
+}
+
+impl SubscriptionDetail {
+    /// Whether a deletion is waiting for its grace period to end, and so can still be undone.
+    fn restorable(&self) -> bool {
+        self.deletions.iter().any(|deletion| {
+            deletion.status == DeletionStatus::Pending && deletion.deleted.is_none()
+        })
+    }
+}
+
+#[derive(askama::Template)]
+#[template(path = "subscriptions/history.html")]
+struct SubscriptionHistory {
+    base: Base,
+    username: String,
+    subscription: Subscription,
+    events: Vec<RowSubscriptionEvent>,
+}
+
+/// Looks up the id of one of the user's subscriptions from any of its GUIDs.
+async fn subscription_id(
+    sync: &SyncState,
+    session: &Session,
+    guid: Uuid,
+) -> anyhow::Result<Option<(SubscriptionId, Subscription)>> {
+    let Some(row) = sync.db.subscription_get_id_by_guid(guid).await? else {
+        return Ok(None);
+    };
+
+    let subscription = sync
+        .db
+        .subscription_get_by_id(&session.user, row.subscription_id)
+        .await?;
+
+    Ok(subscription.map(|subscription| (row.subscription_id, subscription)))
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn detail(
+    State(sync): State<SyncState>,
+    session: Session,
+    Path((username, guid)): Path<(String, Uuid)>,
+) -> Response {
+    if username != session.user.username || !session.validate() {
+        return (StatusCode::UNAUTHORIZED).into_response();
+    }
+
+    let (id, subscription) = match subscription_id(&sync, &session, guid).await {
+        Ok(Some(pair)) => pair,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user subscription");
+
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let history = tokio::try_join!(
+        sync.db.podcast_get_by_feed(&subscription.feed_url),
+        sync.db.feed_health_get(subscription.feed_url.as_str()),
+        sync.db.subscription_get_feeds(id),
+        sync.db.subscription_get_guids(id),
+        sync.db.deletions_get_by_subscription(&session.user, id),
+    );
+    let (podcast, health, feeds, guids, deletions) = match history {
+        Ok(history) => history,
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve subscription history");
+
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let template = SubscriptionDetail {
+        username: session.user.username.clone(),
+        base: Base::new(Some(session)),
+        subscription,
+        podcast,
+        health,
+        feeds,
+        guids,
+        deletions,
+    };
+
+    (StatusCode::OK, Template(template)).into_response()
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn history(
+    State(sync): State<SyncState>,
+    session: Session,
+    Path((username, guid)): Path<(String, Uuid)>,
+) -> Response {
+    if username != session.user.username || !session.validate() {
+        return (StatusCode::UNAUTHORIZED).into_response();
+    }
+
+    let (id, subscription) = match subscription_id(&sync, &session, guid).await {
+        Ok(Some(pair)) => pair,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user subscription");
+
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let events = match sync.db.subscription_history_get(&session.user, id).await {
+        Ok(events) => events,
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve subscription history");
+
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let template = SubscriptionHistory {
+        username: session.user.username.clone(),
+        base: Base::new(Some(session)),
+        subscription,
+        events,
+    };
+
+    (StatusCode::OK, Template(template)).into_response()
+}
+
+async fn set_subscribed(
+    sync: SyncState,
+    session: Session,
+    username: String,
+    guid: Uuid,
+    is_subscribed: bool,
+) -> Response {
+    if username != session.user.username || !session.validate() {
+        return (StatusCode::UNAUTHORIZED).into_response();
+    }
+
+    let id = match subscription_id(&sync, &session, guid).await {
+        Ok(Some((id, _))) => id,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user subscription");
+
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    if let Err(err) = sync
+        .db
+        .subscription_set_subscribed(&session.user, Origin::new(Client::Web), id, is_subscribed)
+        .await
+    {
+        tracing::error!(err = ?err, "Failed to update user subscription");
+
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    Redirect::to(&format!("/user/{}/subscriptions/{}", username, guid)).into_response()
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn subscribe(
+    State(sync): State<SyncState>,
+    session: Session,
+    Path((username, guid)): Path<(String, Uuid)>,
+) -> Response {
+    set_subscribed(sync, session, username, guid, true).await
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn unsubscribe(
+    State(sync): State<SyncState>,
+    session: Session,
+    Path((username, guid)): Path<(String, Uuid)>,
+) -> Response {
+    set_subscribed(sync, session, username, guid, false).await
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn delete(
+    State(sync): State<SyncState>,
+    session: Session,
+    Path((username, guid)): Path<(String, Uuid)>,
+) -> Response {
+    if username != session.user.username || !session.validate() {
+        return (StatusCode::UNAUTHORIZED).into_response();
+    }
+
+    match subscription_id(&sync, &session, guid).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user subscription");
+
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    }
+
+    match sync
+        .db
+        .deletion_create(
+            &session.user,
+            Origin::new(Client::Web),
+            guid,
+            sync.cfg.deletion_grace(),
+        )
+        .await
+    {
+        Ok(Some(_)) => {
+            Redirect::to(&format!("/user/{}/subscriptions/{}", username, guid)).into_response()
+        }
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to create deletion task");
+
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Undoes a deletion that is still within its grace period.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn restore(
+    State(sync): State<SyncState>,
+    session: Session,
+    Path((username, guid)): Path<(String, Uuid)>,
+) -> Response {
+    if username != session.user.username || !session.validate() {
+        return (StatusCode::UNAUTHORIZED).into_response();
+    }
+
+    let id = match subscription_id(&sync, &session, guid).await {
+        Ok(Some((id, _))) => id,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user subscription");
+
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    match sync
+        .db
+        .deletion_restore(&session.user, Origin::new(Client::Web), id)
+        .await
+    {
+        Ok(true) => {
+            Redirect::to(&format!("/user/{}/subscriptions/{}", username, guid)).into_response()
+        }
+        Ok(false) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to restore subscription");
+
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Clears the failures of the subscription's feed so it gets fetched again, even if it was
+/// suspended.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn resume(
+    State(sync): State<SyncState>,
+    session: Session,
+    Path((username, guid)): Path<(String, Uuid)>,
+) -> Response {
+    if username != session.user.username || !session.validate() {
+        return (StatusCode::UNAUTHORIZED).into_response();
+    }
+
+    let subscription = match subscription_id(&sync, &session, guid).await {
+        Ok(Some((_, subscription))) => subscription,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user subscription");
+
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    if let Err(err) = sync
+        .db
+        .feed_fetch_resume(subscription.feed_url.as_str())
+        .await
+    {
+        tracing::error!(err = ?err, "Failed to resume feed fetch");
+
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    Redirect::to(&format!("/user/{}/subscriptions/{}", username, guid)).into_response()
+}
+
+#[derive(askama::Template)]
+#[template(path = "subscriptions/import.html")]
+struct ImportReport {
+    base: Base,
+    username: String,
+    report: NewSubscriptions,
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn import(
+    State(sync): State<SyncState>,
+    session: Session,
+    Path(username): Path<String>,
+    mut multipart: Multipart,
+) -> Response {
+    if username != session.user.username || !session.validate() {
+        return (StatusCode::UNAUTHORIZED).into_response();
+    }
+
+    let mut document = None;
+    while let Ok(Some(field)) = multipart.next_field().await {
+        if field.name() == Some("opml") {
+            document = field.bytes().await.ok();
+
+            break;
+        }
+    }
+
+    let Some(Ok(OpmlDocument(opml))) = document.as_deref().map(OpmlDocument::<Opml>::from_bytes)
+    else {
+        return render_list(&sync, session, 1, Some("OPML file could not be read")).await;
+    };
+
+    let report = import_opml(&sync, &session.user, Origin::new(Client::Web), &opml).await;
+
+    let template = ImportReport {
+        username: session.user.username.clone(),
+        base: Base::new(Some(session)),
+        report,
+    };
+
+    (StatusCode::OK, Template(template)).into_response()
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn export(
+    State(sync): State<SyncState>,
+    session: Session,
+    Path(username): Path<String>,
+) -> Response {
+    if username != session.user.username || !session.validate() {
+        return (StatusCode::UNAUTHORIZED).into_response();
+    }
+
+    match Opml::export(&sync.db, &session.user).await {
+        Ok(opml) => (
+            [(
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"subscriptions.opml\"",
+            )],
+            opml,
+        )
+            .into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to export user subscriptions");
+
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -1,39 +1,315 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::{IntoResponse as _, Response},
+    extract::{Multipart, Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse as _, Redirect, Response},
+    Form,
 };
 
 use crate::{
+    database::{export::RowExport, subscription::history::Origin},
     extractor::auth::Session,
-    handlers::web::{Base, Template},
+    handlers::{
+        takeout::import::import_takeout,
+        web::{Base, Template},
+    },
+    models::{
+        subscriptions::Client,
+        takeout::{Takeout, TakeoutImport},
+    },
     SyncState,
 };
 
+/// How long a takeout archive can be downloaded for.
+const EXPORT_LIFETIME: time::Duration = time::Duration::days(7);
+
 #[derive(askama::Template)]
 #[template(path = "user/index.html")]
 struct Account {
     base: Base,
+    username: String,
+    exports: Vec<RowExport>,
+    error: Option<&'static str>,
 }
 
-impl Account {
-    fn new(session: Option<Session>) -> Self {
-        Self {
-            base: Base::new(session),
+async fn render_account(
+    sync: &SyncState,
+    session: Session,
+    error: Option<&'static str>,
+) -> Response {
+    let exports = match sync.db.exports_get_all(&session.user).await {
+        Ok(exports) => exports,
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user exports");
+
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
         }
-    }
+    };
+
+    let status = if error.is_some() {
+        StatusCode::BAD_REQUEST
+    } else {
+        StatusCode::OK
+    };
+
+    let template = Account {
+        username: session.user.username.clone(),
+        base: Base::new(Some(session)),
+        exports,
+        error,
+    };
+
+    (status, Template(template)).into_response()
 }
 
 #[tracing::instrument(skip_all)]
 #[autometrics::autometrics]
 pub async fn account(
-    State(_sync): State<SyncState>,
+    State(sync): State<SyncState>,
     session: Session,
     Path(username): Path<String>,
 ) -> Response {
-    if username != session.user.username {
+    if username != session.user.username || !session.validate() {
         return (StatusCode::UNAUTHORIZED).into_response();
     }
 
-    (StatusCode::OK, Template(Account::new(Some(session)))).into_response()
+    render_account(&sync, session, None).await
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn export(
+    State(sync): State<SyncState>,
+    session: Session,
+    Path(username): Path<String>,
+) -> Response {
+    if username != session.user.username || !session.validate() {
+        return (StatusCode::UNAUTHORIZED).into_response();
+    }
+
+    match sync.db.export_create(&session.user, EXPORT_LIFETIME).await {
+        Ok(Some(_)) => Redirect::to(&format!("/user/{}", username)).into_response(),
+        Ok(None) => {
+            render_account(
+                &sync,
+                session,
+                Some("You have too many exports, wait for one of them to expire"),
+            )
+            .await
+        }
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to queue user export");
+
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn download(
+    State(sync): State<SyncState>,
+    session: Session,
+    Path((username, token)): Path<(String, String)>,
+) -> Response {
+    if username != session.user.username || !session.validate() {
+        return (StatusCode::UNAUTHORIZED).into_response();
+    }
+
+    match sync.db.export_get_archive(&session.user, &token).await {
+        Ok(Some(archive)) => (
+            [
+                (header::CONTENT_TYPE, "application/zip"),
+                (
+                    header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"pod-sync-takeout.zip\"",
+                ),
+            ],
+            archive,
+        )
+            .into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve user export");
+
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[derive(askama::Template)]
+#[template(path = "user/import.html")]
+struct ImportReport {
+    base: Base,
+    username: String,
+    report: TakeoutImport,
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn import(
+    State(sync): State<SyncState>,
+    session: Session,
+    Path(username): Path<String>,
+    mut multipart: Multipart,
+) -> Response {
+    if username != session.user.username || !session.validate() {
+        return (StatusCode::UNAUTHORIZED).into_response();
+    }
+
+    let mut archive = None;
+    while let Ok(Some(field)) = multipart.next_field().await {
+        if field.name() == Some("archive") {
+            archive = field.bytes().await.ok();
+
+            break;
+        }
+    }
+
+    let Some(Ok(takeout)) = archive.as_deref().map(Takeout::from_archive) else {
+        return render_account(&sync, session, Some("Takeout archive could not be read")).await;
+    };
+
+    let report = import_takeout(&sync, &session.user, Origin::new(Client::Web), &takeout).await;
+
+    let template = ImportReport {
+        username: session.user.username.clone(),
+        base: Base::new(Some(session)),
+        report,
+    };
+
+    (StatusCode::OK, Template(template)).into_response()
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct DeleteForm {
+    password: String,
+}
+
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn delete(
+    State(sync): State<SyncState>,
+    session: Session,
+    Path(username): Path<String>,
+    Form(form): Form<DeleteForm>,
+) -> Response {
+    if username != session.user.username || !session.validate() {
+        return (StatusCode::UNAUTHORIZED).into_response();
+    }
+
+    if !session.user.verify(&form.password) {
+        return render_account(&sync, session, Some("Password is incorrect")).await;
+    }
+
+    match sync.db.user_delete_request(&session.user).await {
+        // The session is gone, the cookie goes with it.
+        Ok(()) => Redirect::to("/logout").into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to request user deletion");
+
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+        routing::post,
+        Router,
+    };
+    use tower::ServiceExt as _;
+
+    use crate::{database::Database, handlers::test_app, models::jobs::JobKind};
+
+    const PASSWORD: &str = "correct horse battery staple";
+
+    async fn setup_app(pool: sqlx::SqlitePool) -> Router {
+        test_app(pool, |router| {
+            router.route("/user/:username/delete", post(super::delete))
+        })
+        .await
+        .expect("failed to setup app")
+    }
+
+    async fn request(app: &Router, token: &str, username: &str, password: &str) -> StatusCode {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("/user/{}/delete", username))
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(format!(
+                "password={}",
+                password.replace(' ', "+")
+            )))
+            .expect("Failed to build request");
+        let response = app
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("Failed to run request");
+
+        if response.status() == StatusCode::SEE_OTHER {
+            assert_eq!(
+                response.headers().get(header::LOCATION),
+                Some(&header::HeaderValue::from_static("/logout"))
+            );
+        }
+
+        response.status()
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn delete(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let db = Database::new_test(pool.clone())
+            .await
+            .expect("Failed to create database");
+        let app = setup_app(pool).await;
+
+        let user = db
+            .user_get_by_id(Database::USER_ID)
+            .await
+            .expect("Failed to get user")
+            .expect("Test user is missing");
+        db.user_set_password(&user.username, PASSWORD)
+            .await
+            .expect("Failed to set password");
+        let (token, _) = db
+            .session_crate(&user)
+            .await
+            .expect("Failed to create session");
+
+        assert_eq!(
+            request(&app, &token, "elsewhere", PASSWORD).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            request(&app, &token, &user.username, "hunter2").await,
+            StatusCode::BAD_REQUEST
+        );
+        assert!(db
+            .user_get_by_username(&user.username)
+            .await
+            .expect("Failed to get user")
+            .is_some());
+
+        assert_eq!(
+            request(&app, &token, &user.username, PASSWORD).await,
+            StatusCode::SEE_OTHER
+        );
+        assert!(db
+            .session_get_by_token(&token)
+            .await
+            .expect("Failed to get session")
+            .is_none());
+        assert!(db
+            .job_lease(JobKind::AccountDeletion, time::Duration::minutes(1))
+            .await
+            .expect("Failed to lease job")
+            .is_some());
+    }
 }
@@ -0,0 +1,502 @@
+//! The callback WebSub hubs use to verify our subscriptions and to push feed updates.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse as _, Response},
+};
+use bytes::Bytes;
+use data_encoding::HEXLOWER_PERMISSIVE;
+use hmac::{digest::KeyInit, Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Sha256, Sha384, Sha512};
+use time::OffsetDateTime;
+
+use crate::{
+    database::websub::{RowWebSub, WebSubId, WebSubStatus},
+    models::rss::Rss,
+    SyncState,
+};
+
+/// The longest lease we accept from a hub, a longer one is shortened so a hub can't leave us
+/// thinking a subscription is active for years.
+const MAX_LEASE_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+#[derive(serde::Deserialize)]
+pub struct VerifyParams {
+    #[serde(rename = "hub.mode")]
+    pub mode: String,
+    #[serde(rename = "hub.topic")]
+    pub topic: Option<String>,
+    #[serde(rename = "hub.challenge")]
+    pub challenge: Option<String>,
+    #[serde(rename = "hub.lease_seconds")]
+    pub lease_seconds: Option<i64>,
+}
+
+async fn websub_get(sync: &SyncState, id: WebSubId) -> Result<RowWebSub, Response> {
+    match sync.db.websub_get_by_id(id).await {
+        Ok(Some(row)) => Ok(row),
+        Ok(None) => Err(StatusCode::NOT_FOUND.into_response()),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve websub subscription");
+
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
+
+/// Confirms a subscription we requested (by echoing the challenge) or records that the hub denied
+/// it.
+///
+/// We never unsubscribe, so those requests are refused, as are verifications and denials of
+/// subscriptions we aren't waiting on or for another topic.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn verify(
+    State(sync): State<SyncState>,
+    Path(id): Path<WebSubId>,
+    Query(params): Query<VerifyParams>,
+) -> Response {
+    let row = match websub_get(&sync, id).await {
+        Ok(row) => row,
+        Err(response) => return response,
+    };
+
+    match params.mode.as_str() {
+        "subscribe" => {
+            let (Some(topic), Some(challenge)) = (params.topic, params.challenge) else {
+                return StatusCode::BAD_REQUEST.into_response();
+            };
+            if row.status != WebSubStatus::Pending || topic != row.topic {
+                return StatusCode::NOT_FOUND.into_response();
+            }
+
+            let lease_expires = params.lease_seconds.map(|seconds| {
+                OffsetDateTime::now_utc()
+                    + time::Duration::seconds(seconds.clamp(0, MAX_LEASE_SECONDS))
+            });
+
+            if let Err(err) = sync.db.websub_verify(row.id, lease_expires).await {
+                tracing::error!(err = ?err, "Failed to verify websub subscription");
+
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+
+            (StatusCode::OK, challenge).into_response()
+        }
+        "denied" => {
+            let Some(topic) = params.topic else {
+                return StatusCode::BAD_REQUEST.into_response();
+            };
+            if row.status != WebSubStatus::Pending || topic != row.topic {
+                return StatusCode::NOT_FOUND.into_response();
+            }
+
+            if let Err(err) = sync.db.websub_deny(row.id).await {
+                tracing::error!(err = ?err, "Failed to deny websub subscription");
+
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+
+            StatusCode::OK.into_response()
+        }
+        _ => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Stores a pushed feed straight away.
+///
+/// Pushes without a valid signature, or to a subscription the hub hasn't verified, are
+/// acknowledged but ignored, as the spec asks, so a forged push can't tell whether it was
+/// accepted.
+#[tracing::instrument(skip_all)]
+#[autometrics::autometrics]
+pub async fn push(
+    State(sync): State<SyncState>,
+    Path(id): Path<WebSubId>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let row = match sync.db.websub_get_by_id(id).await {
+        Ok(Some(row)) => row,
+        // Tells the hub to stop pushing to a subscription we don't know about.
+        Ok(None) => return StatusCode::GONE.into_response(),
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve websub subscription");
+
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    if !row.is_active(OffsetDateTime::now_utc()) {
+        tracing::warn!("Ignoring websub push to an unverified subscription");
+
+        return StatusCode::ACCEPTED.into_response();
+    }
+
+    let signature = headers
+        .get("X-Hub-Signature")
+        .and_then(|value| value.to_str().ok());
+    let Some(signature) = signature else {
+        tracing::warn!("Ignoring websub push without a signature");
+
+        return StatusCode::ACCEPTED.into_response();
+    };
+    if !verify_signature(row.secret.as_bytes(), signature, &body) {
+        tracing::warn!("Ignoring websub push with an invalid signature");
+
+        return StatusCode::ACCEPTED.into_response();
+    }
+
+    let rss = match quick_xml::de::from_reader::<_, Rss>(body.as_ref()) {
+        Ok(rss) => rss,
+        Err(err) => {
+            tracing::warn!(err = ?err, "Failed to parse pushed feed");
+
+            return StatusCode::ACCEPTED.into_response();
+        }
+    };
+
+    match sync.db.feed_store(&row.feed, &rss.channel).await {
+        Ok(created) => {
+            tracing::debug!(created, "Stored pushed feed");
+
+            StatusCode::ACCEPTED.into_response()
+        }
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to store pushed feed");
+
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Checks an `X-Hub-Signature` header (`method=signature`) against the body.
+fn verify_signature(secret: &[u8], header: &str, body: &[u8]) -> bool {
+    let Some((method, signature)) = header.split_once('=') else {
+        return false;
+    };
+    let Ok(signature) = HEXLOWER_PERMISSIVE.decode(signature.trim().as_bytes()) else {
+        return false;
+    };
+
+    match method {
+        "sha1" => verify_mac::<Hmac<Sha1>>(secret, body, &signature),
+        "sha256" => verify_mac::<Hmac<Sha256>>(secret, body, &signature),
+        "sha384" => verify_mac::<Hmac<Sha384>>(secret, body, &signature),
+        "sha512" => verify_mac::<Hmac<Sha512>>(secret, body, &signature),
+        _ => false,
+    }
+}
+
+fn verify_mac<M: Mac + KeyInit>(secret: &[u8], body: &[u8], signature: &[u8]) -> bool {
+    let Ok(mut mac) = <M as Mac>::new_from_slice(secret) else {
+        return false;
+    };
+
+    mac.update(body);
+
+    mac.verify_slice(signature).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Method, Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use data_encoding::HEXLOWER;
+    use hmac::{Hmac, Mac};
+    use http_body_util::BodyExt as _;
+    use sha2::Sha256;
+    use time::OffsetDateTime;
+    use tower::ServiceExt as _;
+    use url::Url;
+
+    use crate::{
+        database::{
+            websub::{RowWebSub, WebSubStatus},
+            Database,
+        },
+        handlers::test_app,
+    };
+
+    const SECRET: &str = "secret";
+
+    const FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <rss version="2.0">
+            <channel>
+                <title>Pushed</title>
+                <item>
+                    <title>Episode 1</title>
+                    <guid>episode-1</guid>
+                </item>
+            </channel>
+        </rss>"#;
+
+    async fn setup(pool: sqlx::SqlitePool) -> (Router, Database, RowWebSub) {
+        let db = Database::new_test(pool.clone()).await.unwrap();
+        let row = db
+            .websub_request(
+                Database::SUBSCRIPTION_1_FEED,
+                "https://hub.example.com/",
+                Database::SUBSCRIPTION_1_FEED,
+                SECRET,
+            )
+            .await
+            .unwrap();
+
+        let app = test_app(pool, |router| {
+            router.route("/websub/:websub_id", get(super::verify).post(super::push))
+        })
+        .await
+        .expect("failed to setup app");
+
+        (app, db, row)
+    }
+
+    fn push_request(id: i64, signature: String) -> Request<Body> {
+        Request::builder()
+            .method(Method::POST)
+            .uri(format!("/websub/{}", id))
+            .header("X-Hub-Signature", signature)
+            .body(Body::from(FEED))
+            .expect("Failed to build request")
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn verify(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let (app, db, row) = setup(pool).await;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(format!(
+                "/websub/{}?hub.mode=subscribe&hub.topic={}&hub.challenge=abc123&hub.lease_seconds=3600",
+                row.id.0,
+                Database::SUBSCRIPTION_1_FEED,
+            ))
+            .body(Body::empty())
+            .expect("Failed to build request");
+
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+
+        assert_eq!(&body[..], b"abc123");
+
+        let row = db.websub_get_by_id(row.id).await.unwrap().unwrap();
+
+        assert_eq!(row.status, WebSubStatus::Verified);
+        assert!(row.lease_expires.is_some());
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn verify_wrong_topic(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let (app, _, row) = setup(pool).await;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(format!(
+                "/websub/{}?hub.mode=subscribe&hub.topic=http://other.example.com/&hub.challenge=abc123",
+                row.id.0,
+            ))
+            .body(Body::empty())
+            .expect("Failed to build request");
+
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn verify_lease(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let (app, db, row) = setup(pool).await;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(format!(
+                "/websub/{}?hub.mode=subscribe&hub.topic={}&hub.challenge=abc123&hub.lease_seconds={}",
+                row.id.0,
+                Database::SUBSCRIPTION_1_FEED,
+                i64::MAX,
+            ))
+            .body(Body::empty())
+            .expect("Failed to build request");
+
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let row = db.websub_get_by_id(row.id).await.unwrap().unwrap();
+        let lease_expires = row.lease_expires.expect("Lease was not recorded");
+
+        assert!(lease_expires <= OffsetDateTime::now_utc() + time::Duration::days(30));
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn verify_not_pending(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let (app, db, row) = setup(pool).await;
+        db.websub_deny(row.id).await.unwrap();
+
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(format!(
+                "/websub/{}?hub.mode=subscribe&hub.topic={}&hub.challenge=abc123",
+                row.id.0,
+                Database::SUBSCRIPTION_1_FEED,
+            ))
+            .body(Body::empty())
+            .expect("Failed to build request");
+
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let row = db.websub_get_by_id(row.id).await.unwrap().unwrap();
+
+        assert_eq!(row.status, WebSubStatus::Denied);
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn deny(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let (app, db, row) = setup(pool).await;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(format!(
+                "/websub/{}?hub.mode=denied&hub.topic={}&hub.reason=spam",
+                row.id.0,
+                Database::SUBSCRIPTION_1_FEED,
+            ))
+            .body(Body::empty())
+            .expect("Failed to build request");
+
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let row = db.websub_get_by_id(row.id).await.unwrap().unwrap();
+
+        assert_eq!(row.status, WebSubStatus::Denied);
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn deny_wrong_topic(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let (app, db, row) = setup(pool).await;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(format!(
+                "/websub/{}?hub.mode=denied&hub.topic=http://other.example.com/",
+                row.id.0,
+            ))
+            .body(Body::empty())
+            .expect("Failed to build request");
+
+        let response = app.oneshot(request).await.expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let row = db.websub_get_by_id(row.id).await.unwrap().unwrap();
+
+        assert_eq!(row.status, WebSubStatus::Pending);
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn push(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let (app, db, row) = setup(pool).await;
+        db.websub_verify(row.id, None).await.unwrap();
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(SECRET.as_bytes()).unwrap();
+        mac.update(FEED.as_bytes());
+        let signature = format!("sha256={}", HEXLOWER.encode(&mac.finalize().into_bytes()));
+
+        let response = app
+            .oneshot(push_request(row.id.0, signature))
+            .await
+            .expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+        let podcast = db
+            .podcast_get_by_feed(&Url::parse(Database::SUBSCRIPTION_1_FEED).unwrap())
+            .await
+            .unwrap()
+            .expect("Pushed feed was not stored");
+
+        assert_eq!(podcast.title, "Pushed");
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn push_invalid_signature(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let (app, db, row) = setup(pool).await;
+        db.websub_verify(row.id, None).await.unwrap();
+
+        let response = app
+            .oneshot(push_request(row.id.0, "sha256=00".to_string()))
+            .await
+            .expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+        let podcast = db
+            .podcast_get_by_feed(&Url::parse(Database::SUBSCRIPTION_1_FEED).unwrap())
+            .await
+            .unwrap();
+
+        assert!(podcast.is_none());
+    }
+
+    #[sqlx::test(fixtures("../../../fixtures/dummy.sql"))]
+    async fn push_unverified(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let (app, db, row) = setup(pool).await;
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(SECRET.as_bytes()).unwrap();
+        mac.update(FEED.as_bytes());
+        let signature = format!("sha256={}", HEXLOWER.encode(&mac.finalize().into_bytes()));
+
+        let response = app
+            .clone()
+            .oneshot(push_request(row.id.0, signature.clone()))
+            .await
+            .expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+        let podcast = db
+            .podcast_get_by_feed(&Url::parse(Database::SUBSCRIPTION_1_FEED).unwrap())
+            .await
+            .unwrap();
+
+        assert!(podcast.is_none());
+
+        let response = app
+            .oneshot(push_request(row.id.0 + 1, signature))
+            .await
+            .expect("Failed to run request");
+
+        assert_eq!(response.status(), StatusCode::GONE);
+    }
+}
@@ -0,0 +1,10 @@
+pub mod callback;
+
+use axum::routing;
+
+#[rustfmt::skip]
+pub fn app() -> axum::Router<crate::SyncState> {
+    axum::Router::new()
+        // WebSub
+        .route("/websub/:websub_id", routing::get(callback::verify).post(callback::push))
+}
@@ -4,39 +4,50 @@ mod models;
 mod tasks;
 mod utils;
 
+mod cli;
 mod config;
 mod database;
 
-use std::{sync::Arc, time::Duration};
+use std::{path::Path, sync::Arc, time::Duration};
 
 use axum::{extract::FromRef, Router};
 use axum_extra::extract::cookie::Key;
 use axum_prometheus::PrometheusMetricLayer;
+use clap::Parser as _;
 use metrics_exporter_prometheus::PrometheusHandle;
 use tokio::net::TcpListener;
 use tower_http::{timeout::TimeoutLayer, trace::TraceLayer};
 use tracing_subscriber::prelude::*;
 
-use crate::{config::Config, database::Database, tasks::Task};
+use crate::{
+    cli::{Cli, Command},
+    config::Config,
+    database::Database,
+    tasks::Tasks,
+    utils::http::HttpClient,
+};
 
 #[derive(Clone)]
 struct SyncState {
     key: Key,
     pub(crate) db: Database,
     pub(crate) cfg: Arc<Config>,
+    pub(crate) http: HttpClient,
 }
 
 impl SyncState {
-    async fn new() -> anyhow::Result<Self> {
-        let config = Config::load().await?;
+    async fn new(path: Option<&Path>) -> anyhow::Result<Self> {
+        let config = Config::load(path).await?;
         let config = Arc::new(config);
 
-        let db = Database::new().await?;
+        let db = Database::new(&config.database).await?;
+        let http = HttpClient::new(&config.outbound)?;
 
         Ok(Self {
             key: Key::from(&config.cookie_key()?),
             db,
             cfg: config.clone(),
+            http,
         })
     }
 
@@ -48,6 +59,7 @@ impl SyncState {
             key: Key::from(&config.cookie_key()?),
             db: Database::new_test(pool).await?,
             cfg: config.clone(),
+            http: HttpClient::new(&config.outbound)?,
         })
     }
 }
@@ -64,7 +76,36 @@ async fn main() -> anyhow::Result<()> {
         .with(tracing_subscriber::fmt::layer().without_time())
         .init();
 
-    let state = SyncState::new().await?;
+    let cli = Cli::parse();
+
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve(cli.config.as_deref()).await,
+        Command::Backup => {
+            let config = Config::load(cli.config.as_deref()).await?;
+            let db = Database::new(&config.database).await?;
+
+            let backup = db.backup(&config.backup).await?;
+            tracing::info!(name = %backup.name, size = backup.size, "database backed up");
+
+            db.shutdown().await
+        }
+        Command::Restore { backup } => {
+            let config = Config::load(cli.config.as_deref()).await?;
+
+            let path = database::backup::restore(&config.database, &backup).await?;
+            tracing::info!(path = %path.display(), "database restored");
+
+            Ok(())
+        }
+        Command::Migrate(command) => cli::migrate(cli.config.as_deref(), command).await,
+        Command::Config(command) => cli::config(cli.config.as_deref(), command).await,
+        Command::User(command) => cli::user(cli.config.as_deref(), command).await,
+        Command::Session(command) => cli::session(cli.config.as_deref(), command).await,
+    }
+}
+
+async fn serve(path: Option<&Path>) -> anyhow::Result<()> {
+    let state = SyncState::new(path).await?;
 
     let (prometheus_layer, metric_handle) = PrometheusMetricLayer::pair();
 
@@ -92,8 +133,14 @@ async fn start_public_server(
             TimeoutLayer::new(Duration::from_secs(10)),
         ));
 
-    let deletion_handle = Task::spawn(tasks::deletion);
-    let identification_handle = Task::spawn(tasks::identification);
+    let mut background = Tasks::new();
+    let refresh_state = state.clone();
+    background.spawn(move |status| tasks::refresh(refresh_state, status));
+    let websub_state = state.clone();
+    background.spawn(move |status| tasks::websub(websub_state, status));
+    let cleanup_state = state.clone();
+    background.spawn(move |status| tasks::cleanup(cleanup_state, status));
+    background.spawn_workers(&state);
 
     let listener = TcpListener::bind(addr).await?;
 
@@ -103,8 +150,7 @@ async fn start_public_server(
 
     tracing::info!("waiting for tasks to finish");
 
-    deletion_handle.await.expect("async task panicked");
-    identification_handle.await.expect("async task panicked");
+    background.shutdown().await;
 
     state.db.shutdown().await?;
 
@@ -118,7 +164,9 @@ async fn start_private_server(state: SyncState, metric_handle: PrometheusHandle)
 
     let addr = state.cfg.private_address;
 
-    let app = Router::new().route("/metrics", get(move || ready(metric_handle.render())));
+    let app = Router::new()
+        .route("/metrics", get(move || ready(metric_handle.render())))
+        .merge(handlers::private_app(state.clone()));
 
     let listener = TcpListener::bind(addr).await?;
 
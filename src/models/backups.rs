@@ -0,0 +1,34 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use time::OffsetDateTime;
+
+use crate::utils::json::Json;
+
+/// A copy of the database in the backup directory.
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Backup {
+    /// The file name, restored with `pod-sync restore <directory>/<name>`.
+    pub name: String,
+    pub size: u64,
+    pub created: OffsetDateTime,
+}
+
+impl IntoResponse for Backup {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Backups {
+    /// Newest first.
+    pub backups: Vec<Backup>,
+}
+
+impl IntoResponse for Backups {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
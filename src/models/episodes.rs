@@ -0,0 +1,15 @@
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum EpisodeActionKind {
+    #[serde(alias = "DOWNLOAD")]
+    Download,
+    #[serde(alias = "DELETE")]
+    Delete,
+    #[serde(alias = "PLAY")]
+    Play,
+    #[serde(alias = "NEW")]
+    New,
+    #[serde(alias = "FLATTR")]
+    Flattr,
+}
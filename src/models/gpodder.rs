@@ -0,0 +1,150 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use time::{
+    format_description::{well_known::Rfc3339, BorrowedFormatItem},
+    macros::format_description,
+    OffsetDateTime, PrimitiveDateTime,
+};
+
+use crate::{
+    database::{device::RowDevice, episode::RowEpisodeAction},
+    models::episodes::EpisodeActionKind,
+    utils::json::Json,
+};
+
+/// gpodder.net sends and expects episode action timestamps without an offset, they are always UTC.
+const TIMESTAMP: &[BorrowedFormatItem<'static>] =
+    format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]");
+
+pub fn parse_timestamp(timestamp: &str) -> Option<OffsetDateTime> {
+    PrimitiveDateTime::parse(timestamp, TIMESTAMP)
+        .map(PrimitiveDateTime::assume_utc)
+        .or_else(|_| OffsetDateTime::parse(timestamp, &Rfc3339))
+        .ok()
+}
+
+pub fn format_timestamp(timestamp: OffsetDateTime) -> String {
+    let timestamp = timestamp.to_offset(time::UtcOffset::UTC);
+
+    PrimitiveDateTime::new(timestamp.date(), timestamp.time())
+        .format(TIMESTAMP)
+        .unwrap_or_default()
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Device {
+    pub id: String,
+    pub caption: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub subscriptions: i64,
+}
+
+impl Device {
+    pub fn from_row(row: RowDevice, subscriptions: i64) -> Self {
+        Self {
+            id: row.identifier,
+            caption: row.caption,
+            kind: row.kind,
+            subscriptions,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Devices(pub Vec<Device>);
+
+impl IntoResponse for Devices {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct DeviceUpdate {
+    pub caption: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SubscriptionChanges {
+    pub add: Vec<String>,
+    pub remove: Vec<String>,
+    pub timestamp: i64,
+}
+
+impl IntoResponse for SubscriptionChanges {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct SubscriptionUpload {
+    #[serde(default)]
+    pub add: Vec<String>,
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct UpdateUrls {
+    pub timestamp: i64,
+    pub update_urls: Vec<(String, String)>,
+}
+
+impl IntoResponse for UpdateUrls {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct EpisodeAction {
+    pub podcast: String,
+    pub episode: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    pub action: EpisodeActionKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+}
+
+impl From<RowEpisodeAction> for EpisodeAction {
+    fn from(row: RowEpisodeAction) -> Self {
+        Self {
+            podcast: row.podcast,
+            episode: row.episode,
+            guid: row.guid,
+            device: row.device,
+            action: row.action,
+            timestamp: Some(format_timestamp(row.timestamp)),
+            started: row.started,
+            position: row.position,
+            total: row.total,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct EpisodeActions {
+    pub actions: Vec<EpisodeAction>,
+    pub timestamp: i64,
+}
+
+impl IntoResponse for EpisodeActions {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
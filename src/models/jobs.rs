@@ -0,0 +1,131 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use time::OffsetDateTime;
+
+use crate::{database::jobs::RowJob, utils::json::Json};
+
+/// The kinds of background jobs, each has its own workers.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "job_kind")]
+#[sqlx(rename_all = "snake_case")]
+pub enum JobKind {
+    /// Purges a deleted subscription once its grace period is over.
+    Deletion,
+    /// Looks for the GUID a new subscription's feed gives itself.
+    Identification,
+    /// Fetches a feed and stores its podcast and episodes.
+    Refresh,
+    /// Sends an email through the configured SMTP server, like the confirmation that an account
+    /// was deleted.
+    Email,
+    /// Builds the takeout archive of a user's data.
+    Export,
+    /// Removes a deleted account and everything that belonged to it.
+    AccountDeletion,
+}
+
+impl JobKind {
+    pub const ALL: [JobKind; 6] = [
+        JobKind::Deletion,
+        JobKind::Identification,
+        JobKind::Refresh,
+        JobKind::Email,
+        JobKind::Export,
+        JobKind::AccountDeletion,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "job_status")]
+#[sqlx(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    /// The job failed on every attempt and won't be tried again.
+    Dead,
+    /// An operator dropped the job before it ran.
+    Cancelled,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Job {
+    pub id: i64,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    /// What the job works on, like the deletion or the feed.
+    pub payload: serde_json::Value,
+    pub attempts: i64,
+    pub max_attempts: i64,
+    pub run_after: OffsetDateTime,
+    pub leased_until: Option<OffsetDateTime>,
+    /// The error of the last failed attempt, kept once the job succeeds.
+    pub last_error: Option<String>,
+    pub created: OffsetDateTime,
+    pub updated: Option<OffsetDateTime>,
+}
+
+impl From<RowJob> for Job {
+    fn from(row: RowJob) -> Self {
+        Self {
+            id: row.id.0,
+            kind: row.kind,
+            status: row.status,
+            payload: serde_json::from_str(&row.payload).unwrap_or(serde_json::Value::Null),
+            attempts: row.attempts,
+            max_attempts: row.max_attempts,
+            run_after: row.run_after,
+            leased_until: row.leased_until,
+            last_error: row.last_error,
+            created: row.created,
+            updated: row.updated,
+        }
+    }
+}
+
+impl IntoResponse for Job {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Jobs {
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+    pub jobs: Vec<Job>,
+}
+
+impl IntoResponse for Jobs {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+/// The workers of a kind of job and how far behind they are.
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Worker {
+    pub kind: JobKind,
+    pub workers: usize,
+    /// Paused workers finish the job they are running but don't start new ones.
+    pub paused: bool,
+    pub queued: i64,
+    pub running: i64,
+    pub dead: i64,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Workers {
+    pub workers: Vec<Worker>,
+}
+
+impl IntoResponse for Workers {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
@@ -1,4 +1,15 @@
+pub mod backups;
+pub mod episodes;
+pub mod gpodder;
+pub mod jobs;
+pub mod nextcloud;
+pub mod opml;
+pub mod podcasts;
+pub mod rss;
 pub mod subscriptions;
+pub mod tags;
+pub mod takeout;
+pub mod users;
 
 use axum::{
     http::StatusCode,
@@ -35,6 +46,13 @@ impl ApiError {
         }
     }
 
+    pub fn conflict() -> Self {
+        Self {
+            code: 409,
+            message: "Resource is not in a state that allows this".to_string(),
+        }
+    }
+
     pub fn gone() -> Self {
         Self {
             code: 410,
@@ -75,6 +93,14 @@ impl IntoResponse for Validation {
     }
 }
 
+pub struct Conflict;
+
+impl IntoResponse for Conflict {
+    fn into_response(self) -> Response {
+        (StatusCode::CONFLICT, Json(ApiError::conflict())).into_response()
+    }
+}
+
 pub struct Gone;
 
 impl IntoResponse for Gone {
@@ -0,0 +1,50 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+
+use crate::utils::json::Json;
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct LoginFlow {
+    pub poll: LoginFlowPoll,
+    pub login: String,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct LoginFlowPoll {
+    pub token: String,
+    pub endpoint: String,
+}
+
+impl IntoResponse for LoginFlow {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct LoginCredentials {
+    pub server: String,
+    #[serde(rename = "loginName")]
+    pub login_name: String,
+    #[serde(rename = "appPassword")]
+    pub app_password: String,
+}
+
+impl IntoResponse for LoginCredentials {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Timestamp {
+    pub timestamp: i64,
+}
+
+impl IntoResponse for Timestamp {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
@@ -0,0 +1,222 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use time::{format_description::well_known::Rfc2822, OffsetDateTime};
+
+use crate::{
+    database::{user::User, Database},
+    models::subscriptions::Subscription,
+    utils::opml::OpmlDocument,
+};
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename = "opml")]
+pub struct Opml {
+    #[serde(rename = "@version")]
+    pub version: String,
+    #[serde(default)]
+    pub head: Head,
+    pub body: Body,
+}
+
+#[derive(Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Head {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(
+        rename = "dateCreated",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub date_created: Option<String>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Body {
+    #[serde(rename = "outline", default)]
+    pub outlines: Vec<Outline>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Outline {
+    #[serde(rename = "@text", default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(rename = "@title", default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(rename = "@type", default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(
+        rename = "@xmlUrl",
+        alias = "@xmlurl",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub xml_url: Option<String>,
+    #[serde(
+        rename = "@htmlUrl",
+        alias = "@htmlurl",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub html_url: Option<String>,
+    /// Comma separated list of slash delimited categories.
+    #[serde(rename = "@category", default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(rename = "outline", default, skip_serializing_if = "Vec::is_empty")]
+    pub outlines: Vec<Outline>,
+}
+
+impl Outline {
+    /// Returns the human readable name of the outline, OPML 1.0 files tend to only set `title`.
+    pub fn name(&self) -> Option<&str> {
+        self.text.as_deref().or(self.title.as_deref())
+    }
+
+    /// Returns the categories of the outline without their leading slash.
+    pub fn categories(&self) -> Vec<&str> {
+        self.category
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(|category| category.trim().trim_matches('/'))
+            .filter(|category| !category.is_empty())
+            .collect()
+    }
+}
+
+/// An outline that describes a feed along with the tags (folders and categories) it was in.
+pub struct OpmlFeed<'o> {
+    pub outline: &'o Outline,
+    pub tags: Vec<String>,
+}
+
+impl Opml {
+    /// Renders the feeds the user is currently subscribed to.
+    pub async fn export(db: &Database, user: &User) -> anyhow::Result<Self> {
+        let subscriptions = db
+            .subscriptions_get_all_unpaged(user)
+            .await?
+            .into_iter()
+            .filter(|subscription| subscription.is_subscribed)
+            .collect();
+
+        Ok(Self::from_subscriptions(
+            format!("{}'s subscriptions", user.username),
+            subscriptions,
+        ))
+    }
+
+    /// Builds the document from the user's subscriptions, subscriptions are put into a folder for
+    /// their first tag and have all of their tags listed as categories.
+    pub fn from_subscriptions(title: String, subscriptions: Vec<Subscription>) -> Self {
+        let mut outlines = Vec::new();
+        let mut folders: Vec<Outline> = Vec::new();
+
+        for subscription in subscriptions {
+            let outline = Outline {
+                text: Some(subscription.feed_url.to_string()),
+                title: None,
+                kind: Some("rss".to_string()),
+                xml_url: Some(subscription.feed_url.to_string()),
+                html_url: None,
+                category: (!subscription.tags.is_empty()).then(|| {
+                    subscription
+                        .tags
+                        .iter()
+                        .map(|tag| format!("/{}", tag))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                }),
+                outlines: vec![],
+            };
+
+            let Some(tag) = subscription.tags.first() else {
+                outlines.push(outline);
+
+                continue;
+            };
+
+            match folders
+                .iter_mut()
+                .find(|folder| folder.text.as_deref() == Some(tag.as_str()))
+            {
+                Some(folder) => folder.outlines.push(outline),
+                None => folders.push(Outline {
+                    text: Some(tag.clone()),
+                    title: Some(tag.clone()),
+                    kind: None,
+                    xml_url: None,
+                    html_url: None,
+                    category: None,
+                    outlines: vec![outline],
+                }),
+            }
+        }
+
+        folders.extend(outlines);
+
+        Self {
+            version: "2.0".to_string(),
+            head: Head {
+                title: Some(title),
+                date_created: OffsetDateTime::now_utc().format(&Rfc2822).ok(),
+            },
+            body: Body { outlines: folders },
+        }
+    }
+
+    /// Flattens the outline tree into the outlines that describe a feed.
+    ///
+    /// Outlines that only group other outlines (categories/folders) are skipped, anything else
+    /// without children is returned even if it's missing a feed URL so it can be reported. The
+    /// folders a feed is nested in are returned as its tags, along with its categories.
+    pub fn feeds(&self) -> Vec<OpmlFeed<'_>> {
+        fn walk<'o>(
+            outlines: &'o [Outline],
+            folders: &mut Vec<&'o str>,
+            feeds: &mut Vec<OpmlFeed<'o>>,
+        ) {
+            for outline in outlines {
+                if outline.xml_url.is_some() || outline.outlines.is_empty() {
+                    let mut tags = folders
+                        .iter()
+                        .copied()
+                        .chain(outline.categories())
+                        .map(str::to_string)
+                        .collect::<Vec<_>>();
+                    tags.sort();
+                    tags.dedup();
+
+                    feeds.push(OpmlFeed { outline, tags });
+                }
+
+                let folder = match outline.xml_url {
+                    Some(_) => None,
+                    None => outline.name(),
+                };
+                if let Some(folder) = folder {
+                    folders.push(folder);
+                }
+
+                walk(&outline.outlines, folders, feeds);
+
+                if folder.is_some() {
+                    folders.pop();
+                }
+            }
+        }
+
+        let mut feeds = Vec::new();
+
+        walk(&self.body.outlines, &mut Vec::new(), &mut feeds);
+
+        feeds
+    }
+}
+
+impl IntoResponse for Opml {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, OpmlDocument(self)).into_response()
+    }
+}
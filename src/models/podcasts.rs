@@ -0,0 +1,287 @@
+use crate::database::{
+    namespace::{RowFunding, RowPerson, RowTranscript, RowValue, RowValueRecipient},
+    podcast::{RowPodcast, RowPodcastEpisode},
+    search::RowSearchHit,
+};
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Podcast {
+    pub id: i64,
+    pub title: String,
+    pub feed_urls: Vec<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub language: Option<String>,
+    pub link: Option<String>,
+    pub copyright: Option<String>,
+    pub author: Option<String>,
+    pub category: Option<String>,
+    pub subcategory: Option<String>,
+    pub owner_name: Option<String>,
+    pub owner_email: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub summary: Option<String>,
+    /// Whether the owner has asked for the feed not to be imported by other platforms.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locked: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locked_owner: Option<String>,
+    /// Only included when requesting a single podcast.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub funding: Vec<Funding>,
+    /// Only included when requesting a single podcast.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub persons: Vec<Person>,
+    /// Only included when requesting a single podcast.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+impl Podcast {
+    pub fn from_row(row: RowPodcast, feed_urls: Vec<String>) -> Self {
+        Self {
+            id: row.id.0,
+            title: row.title,
+            feed_urls,
+            description: row.description,
+            image: row.image,
+            language: row.language,
+            link: row.link,
+            copyright: row.copyright,
+            author: row.itunes_author,
+            category: row.itunes_category,
+            subcategory: row.itunes_subcategory,
+            owner_name: row.itunes_owner_name,
+            owner_email: row.itunes_owner_email,
+            kind: row.itunes_type,
+            summary: row.itunes_summary,
+            locked: row.podcast_locked,
+            locked_owner: row.podcast_locked_owner,
+            funding: vec![],
+            persons: vec![],
+            value: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Podcasts {
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+    pub podcasts: Vec<Podcast>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Episode {
+    pub id: i64,
+    pub podcast_id: i64,
+    pub title: Option<String>,
+    pub enclosure: Option<String>,
+    pub guid: Option<String>,
+    pub published: Option<String>,
+    pub description: Option<String>,
+    pub season: Option<i64>,
+    pub episode: Option<i64>,
+    pub duration: Option<String>,
+    pub image: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub subtitle: Option<String>,
+    pub summary: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub season_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chapters: Option<Chapters>,
+    /// Only included when requesting a single episode.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transcripts: Vec<Transcript>,
+    /// Only included when requesting a single episode.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub persons: Vec<Person>,
+    /// Only included when requesting a single episode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+impl From<RowPodcastEpisode> for Episode {
+    fn from(row: RowPodcastEpisode) -> Self {
+        Self {
+            id: row.id.0,
+            podcast_id: row.podcast_id.0,
+            title: row.title,
+            enclosure: row.enclosure,
+            guid: row.guid,
+            published: row.published,
+            description: row.description,
+            season: row.itunes_season.or(row.podcast_season),
+            episode: row.itunes_episode,
+            duration: row.itunes_duration,
+            image: row.itunes_image,
+            kind: row.itunes_type,
+            subtitle: row.itunes_subtitle,
+            summary: row.itunes_summary,
+            season_name: row.podcast_season_name,
+            chapters: row.podcast_chapters.map(|url| Chapters {
+                url,
+                kind: row.podcast_chapters_type,
+            }),
+            transcripts: vec![],
+            persons: vec![],
+            value: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Episodes {
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+    pub episodes: Vec<Episode>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SearchResult {
+    pub podcast_id: i64,
+    pub podcast_title: String,
+    /// Missing when the podcast itself matched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub episode_id: Option<i64>,
+    pub title: Option<String>,
+    pub snippet: String,
+    /// Higher is better, only comparable within the same search.
+    pub score: f64,
+}
+
+impl From<RowSearchHit> for SearchResult {
+    fn from(row: RowSearchHit) -> Self {
+        Self {
+            podcast_id: row.podcast_id.0,
+            podcast_title: row.podcast_title,
+            episode_id: row.episode_id.map(|id| id.0),
+            title: row.title,
+            snippet: row.snippet,
+            score: -row.rank,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SearchResults {
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+    pub results: Vec<SearchResult>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Transcript {
+    pub url: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub language: Option<String>,
+    pub rel: Option<String>,
+}
+
+impl From<RowTranscript> for Transcript {
+    fn from(row: RowTranscript) -> Self {
+        Self {
+            url: row.url,
+            kind: row.kind,
+            language: row.language,
+            rel: row.rel,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Chapters {
+    pub url: String,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Funding {
+    pub url: String,
+    pub message: Option<String>,
+}
+
+impl From<RowFunding> for Funding {
+    fn from(row: RowFunding) -> Self {
+        Self {
+            url: row.url,
+            message: row.message,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Person {
+    pub name: String,
+    pub role: Option<String>,
+    pub group: Option<String>,
+    pub img: Option<String>,
+    pub href: Option<String>,
+}
+
+impl From<RowPerson> for Person {
+    fn from(row: RowPerson) -> Self {
+        Self {
+            name: row.name,
+            role: row.role,
+            group: row.group_name,
+            img: row.img,
+            href: row.href,
+        }
+    }
+}
+
+/// How listeners can send value (usually sats) to the podcast and how it is split.
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Value {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub method: String,
+    pub suggested: Option<String>,
+    pub recipients: Vec<ValueRecipient>,
+}
+
+impl Value {
+    pub fn from_rows(row: RowValue, recipients: Vec<RowValueRecipient>) -> Self {
+        Self {
+            kind: row.kind,
+            method: row.method,
+            suggested: row.suggested,
+            recipients: recipients.into_iter().map(ValueRecipient::from).collect(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ValueRecipient {
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub address: String,
+    pub split: i64,
+    pub fee: bool,
+    pub custom_key: Option<String>,
+    pub custom_value: Option<String>,
+}
+
+impl From<RowValueRecipient> for ValueRecipient {
+    fn from(row: RowValueRecipient) -> Self {
+        Self {
+            name: row.name,
+            kind: row.kind,
+            address: row.address,
+            split: row.split,
+            fee: row.fee,
+            custom_key: row.custom_key,
+            custom_value: row.custom_value,
+        }
+    }
+}
@@ -0,0 +1,409 @@
+//! The parts of an RSS feed (and the iTunes and Podcasting 2.0 namespaces) that are stored for
+//! podcasts.
+
+use time::{format_description::well_known::Rfc2822, OffsetDateTime};
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+#[serde(rename = "rss")]
+pub struct Rss {
+    pub channel: Channel,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+pub struct Channel {
+    pub title: String,
+    #[serde(default)]
+    pub link: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub copyright: Option<String>,
+    #[serde(default)]
+    pub image: Option<Image>,
+    #[serde(rename = "itunes:image", default)]
+    pub itunes_image: Option<ItunesImage>,
+    #[serde(rename = "itunes:author", default)]
+    pub itunes_author: Option<String>,
+    #[serde(rename = "itunes:category", default)]
+    pub itunes_categories: Vec<ItunesCategory>,
+    #[serde(rename = "itunes:owner", default)]
+    pub itunes_owner: Option<ItunesOwner>,
+    #[serde(rename = "itunes:type", default)]
+    pub itunes_type: Option<String>,
+    #[serde(rename = "itunes:summary", default)]
+    pub itunes_summary: Option<String>,
+    #[serde(rename = "atom:link", default)]
+    pub atom_links: Vec<AtomLink>,
+    /// The feed's own identifier from the Podcasting 2.0 namespace, a UUIDv5 of its URL.
+    #[serde(rename = "podcast:guid", default)]
+    pub podcast_guid: Option<String>,
+    #[serde(rename = "podcast:locked", default)]
+    pub podcast_locked: Option<PodcastLocked>,
+    #[serde(rename = "podcast:funding", default)]
+    pub podcast_fundings: Vec<PodcastFunding>,
+    #[serde(rename = "podcast:person", default)]
+    pub podcast_persons: Vec<PodcastPerson>,
+    #[serde(rename = "podcast:value", default)]
+    pub podcast_value: Option<PodcastValue>,
+    #[serde(rename = "item", default)]
+    pub items: Vec<Item>,
+}
+
+impl Channel {
+    /// Prefers the iTunes artwork as it tends to be larger.
+    pub fn image(&self) -> Option<&str> {
+        self.itunes_image
+            .as_ref()
+            .map(|image| image.href.as_str())
+            .or(self.image.as_ref().map(|image| image.url.as_str()))
+    }
+
+    /// The WebSub hub the feed publishes updates to.
+    pub fn hub(&self) -> Option<&str> {
+        self.atom_link("hub")
+    }
+
+    /// The feed's canonical URL, which is the topic to subscribe to at its hub.
+    pub fn self_link(&self) -> Option<&str> {
+        self.atom_link("self")
+    }
+
+    fn atom_link(&self, rel: &str) -> Option<&str> {
+        self.atom_links
+            .iter()
+            .find(|link| link.rel.as_deref() == Some(rel))
+            .map(|link| link.href.as_str())
+    }
+
+    pub fn category(&self) -> Option<&str> {
+        self.itunes_categories
+            .first()
+            .map(|category| category.text.as_str())
+    }
+
+    pub fn subcategory(&self) -> Option<&str> {
+        self.itunes_categories
+            .first()
+            .and_then(|category| category.subcategory.as_ref())
+            .map(|category| category.text.as_str())
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+pub struct Image {
+    pub url: String,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+pub struct AtomLink {
+    #[serde(rename = "@rel", default)]
+    pub rel: Option<String>,
+    #[serde(rename = "@href")]
+    pub href: String,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+pub struct ItunesImage {
+    #[serde(rename = "@href")]
+    pub href: String,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+pub struct ItunesCategory {
+    #[serde(rename = "@text")]
+    pub text: String,
+    #[serde(rename = "itunes:category", default)]
+    pub subcategory: Option<Box<ItunesCategory>>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+pub struct ItunesOwner {
+    #[serde(rename = "itunes:name", default)]
+    pub name: Option<String>,
+    #[serde(rename = "itunes:email", default)]
+    pub email: Option<String>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+pub struct Item {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub enclosure: Option<Enclosure>,
+    #[serde(default)]
+    pub guid: Option<Guid>,
+    #[serde(rename = "pubDate", default)]
+    pub pub_date: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(rename = "itunes:season", default)]
+    pub itunes_season: Option<String>,
+    #[serde(rename = "itunes:episode", default)]
+    pub itunes_episode: Option<String>,
+    #[serde(rename = "itunes:duration", default)]
+    pub itunes_duration: Option<String>,
+    #[serde(rename = "itunes:image", default)]
+    pub itunes_image: Option<ItunesImage>,
+    #[serde(rename = "itunes:episodeType", default)]
+    pub itunes_episode_type: Option<String>,
+    #[serde(rename = "itunes:subtitle", default)]
+    pub itunes_subtitle: Option<String>,
+    #[serde(rename = "itunes:summary", default)]
+    pub itunes_summary: Option<String>,
+    #[serde(rename = "podcast:transcript", default)]
+    pub podcast_transcripts: Vec<PodcastTranscript>,
+    #[serde(rename = "podcast:chapters", default)]
+    pub podcast_chapters: Option<PodcastChapters>,
+    #[serde(rename = "podcast:season", default)]
+    pub podcast_season: Option<PodcastSeason>,
+    #[serde(rename = "podcast:person", default)]
+    pub podcast_persons: Vec<PodcastPerson>,
+    #[serde(rename = "podcast:value", default)]
+    pub podcast_value: Option<PodcastValue>,
+}
+
+impl Item {
+    /// Items without a GUID are identified by their enclosure, which is what most clients do.
+    pub fn id(&self) -> Option<&str> {
+        self.guid
+            .as_ref()
+            .map(|guid| guid.value.trim())
+            .filter(|guid| !guid.is_empty())
+            .or(self
+                .enclosure
+                .as_ref()
+                .map(|enclosure| enclosure.url.as_str()))
+    }
+
+    pub fn published(&self) -> Option<OffsetDateTime> {
+        self.pub_date
+            .as_deref()
+            .and_then(|date| OffsetDateTime::parse(date.trim(), &Rfc2822).ok())
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+pub struct Enclosure {
+    #[serde(rename = "@url")]
+    pub url: String,
+    #[serde(rename = "@type", default)]
+    pub kind: Option<String>,
+    #[serde(rename = "@length", default)]
+    pub length: Option<String>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+pub struct Guid {
+    #[serde(rename = "$text", default)]
+    pub value: String,
+}
+
+/// Whether the feed may be imported by other platforms, `yes` or `no`.
+#[derive(Debug, PartialEq, serde::Deserialize)]
+pub struct PodcastLocked {
+    #[serde(rename = "@owner", default)]
+    pub owner: Option<String>,
+    #[serde(rename = "$text", default)]
+    pub value: String,
+}
+
+impl PodcastLocked {
+    pub fn is_locked(&self) -> bool {
+        self.value.trim().eq_ignore_ascii_case("yes")
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+pub struct PodcastFunding {
+    #[serde(rename = "@url")]
+    pub url: String,
+    #[serde(rename = "$text", default)]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+pub struct PodcastPerson {
+    #[serde(rename = "@role", default)]
+    pub role: Option<String>,
+    #[serde(rename = "@group", default)]
+    pub group: Option<String>,
+    #[serde(rename = "@img", default)]
+    pub img: Option<String>,
+    #[serde(rename = "@href", default)]
+    pub href: Option<String>,
+    #[serde(rename = "$text", default)]
+    pub name: String,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+pub struct PodcastValue {
+    #[serde(rename = "@type")]
+    pub kind: String,
+    #[serde(rename = "@method")]
+    pub method: String,
+    #[serde(rename = "@suggested", default)]
+    pub suggested: Option<String>,
+    #[serde(rename = "podcast:valueRecipient", default)]
+    pub recipients: Vec<PodcastValueRecipient>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+pub struct PodcastValueRecipient {
+    #[serde(rename = "@name", default)]
+    pub name: Option<String>,
+    #[serde(rename = "@type")]
+    pub kind: String,
+    #[serde(rename = "@address")]
+    pub address: String,
+    #[serde(rename = "@split")]
+    pub split: String,
+    #[serde(rename = "@fee", default)]
+    pub fee: Option<String>,
+    #[serde(rename = "@customKey", default)]
+    pub custom_key: Option<String>,
+    #[serde(rename = "@customValue", default)]
+    pub custom_value: Option<String>,
+}
+
+impl PodcastValueRecipient {
+    pub fn split(&self) -> i64 {
+        self.split.trim().parse().unwrap_or(0)
+    }
+
+    pub fn is_fee(&self) -> bool {
+        self.fee
+            .as_deref()
+            .is_some_and(|fee| fee.trim().eq_ignore_ascii_case("true"))
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+pub struct PodcastTranscript {
+    #[serde(rename = "@url")]
+    pub url: String,
+    #[serde(rename = "@type")]
+    pub kind: String,
+    #[serde(rename = "@language", default)]
+    pub language: Option<String>,
+    #[serde(rename = "@rel", default)]
+    pub rel: Option<String>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+pub struct PodcastChapters {
+    #[serde(rename = "@url")]
+    pub url: String,
+    #[serde(rename = "@type", default)]
+    pub kind: Option<String>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+pub struct PodcastSeason {
+    #[serde(rename = "@name", default)]
+    pub name: Option<String>,
+    #[serde(rename = "$text", default)]
+    pub number: String,
+}
+
+impl PodcastSeason {
+    pub fn number(&self) -> Option<i64> {
+        self.number.trim().parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::Rss;
+
+    #[test]
+    fn parse() {
+        let feed = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <rss version="2.0" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd" xmlns:atom="http://www.w3.org/2005/Atom">
+                <channel>
+                    <title>Example</title>
+                    <atom:link rel="self" href="http://example.com/feed.rss" type="application/rss+xml" />
+                    <atom:link rel="hub" href="https://pubsubhubbub.appspot.com/" />
+                    <itunes:image href="http://example.com/cover.png" />
+                    <itunes:category text="Technology">
+                        <itunes:category text="Software" />
+                    </itunes:category>
+                    <item>
+                        <title>Episode 1</title>
+                        <enclosure url="http://example.com/1.mp3" type="audio/mpeg" length="1" />
+                        <pubDate>Mon, 21 Oct 2024 10:00:00 +0000</pubDate>
+                        <itunes:episode>1</itunes:episode>
+                    </item>
+                </channel>
+            </rss>"#;
+
+        let rss: Rss = quick_xml::de::from_str(feed).expect("Failed to parse feed");
+
+        assert_eq!(rss.channel.title, "Example");
+        assert_eq!(rss.channel.image(), Some("http://example.com/cover.png"));
+        assert_eq!(rss.channel.category(), Some("Technology"));
+        assert_eq!(rss.channel.subcategory(), Some("Software"));
+        assert_eq!(rss.channel.self_link(), Some("http://example.com/feed.rss"));
+        assert_eq!(rss.channel.hub(), Some("https://pubsubhubbub.appspot.com/"));
+        assert_eq!(rss.channel.items.len(), 1);
+        assert_eq!(rss.channel.items[0].id(), Some("http://example.com/1.mp3"));
+        assert!(rss.channel.items[0].published().is_some());
+    }
+
+    #[test]
+    fn parse_podcast_namespace() {
+        let feed = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <rss version="2.0" xmlns:podcast="https://podcastindex.org/namespace/1.0">
+                <channel>
+                    <title>Example</title>
+                    <podcast:locked owner="owner@example.com">yes</podcast:locked>
+                    <podcast:funding url="https://example.com/donate">Support the show</podcast:funding>
+                    <podcast:person role="host" img="https://example.com/host.png">Jane Doe</podcast:person>
+                    <podcast:value type="lightning" method="keysend" suggested="0.00000005000">
+                        <podcast:valueRecipient name="Host" type="node" address="02d5c1" split="90" />
+                        <podcast:valueRecipient name="App" type="node" address="03ae9f" split="10" fee="true" />
+                    </podcast:value>
+                    <item>
+                        <title>Episode 1</title>
+                        <guid>episode-1</guid>
+                        <podcast:transcript url="https://example.com/1.vtt" type="text/vtt" language="en" />
+                        <podcast:transcript url="https://example.com/1.srt" type="application/srt" rel="captions" />
+                        <podcast:chapters url="https://example.com/1.json" type="application/json+chapters" />
+                        <podcast:season name="The Beginning">1</podcast:season>
+                        <podcast:person role="guest">John Doe</podcast:person>
+                    </item>
+                </channel>
+            </rss>"#;
+
+        let rss: Rss = quick_xml::de::from_str(feed).expect("Failed to parse feed");
+        let channel = &rss.channel;
+
+        let locked = channel.podcast_locked.as_ref().expect("Missing locked");
+        assert!(locked.is_locked());
+        assert_eq!(locked.owner.as_deref(), Some("owner@example.com"));
+        assert_eq!(channel.podcast_fundings.len(), 1);
+        assert_eq!(channel.podcast_persons[0].name, "Jane Doe");
+
+        let value = channel.podcast_value.as_ref().expect("Missing value");
+        assert_eq!(value.recipients.len(), 2);
+        assert_eq!(value.recipients[0].split(), 90);
+        assert!(value.recipients[1].is_fee());
+
+        let item = &channel.items[0];
+        assert_eq!(item.podcast_transcripts.len(), 2);
+        assert_eq!(item.podcast_transcripts[0].language.as_deref(), Some("en"));
+        assert_eq!(
+            item.podcast_chapters
+                .as_ref()
+                .map(|chapters| chapters.url.as_str()),
+            Some("https://example.com/1.json")
+        );
+        let season = item.podcast_season.as_ref().expect("Missing season");
+        assert_eq!(season.number(), Some(1));
+        assert_eq!(season.name.as_deref(), Some("The Beginning"));
+        assert_eq!(item.podcast_persons[0].role.as_deref(), Some("guest"));
+    }
+}
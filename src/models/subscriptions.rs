@@ -6,7 +6,7 @@ use time::OffsetDateTime;
 use url::Url;
 use uuid::Uuid;
 
-use crate::utils::json::Json;
+use crate::{database::subscription::history::RowSubscriptionEvent, utils::json::Json};
 
 #[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct Subscription {
@@ -17,6 +17,12 @@ pub struct Subscription {
     pub new_guid: Option<Uuid>,
     pub guid_changed: Option<OffsetDateTime>,
     pub deleted: Option<OffsetDateTime>,
+    /// The names of the user's tags (folders) the subscription is in.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// How fetching the subscription's feed has been going, only included when asked for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health: Option<FeedHealth>,
 }
 
 impl IntoResponse for Subscription {
@@ -25,6 +31,30 @@ impl IntoResponse for Subscription {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "feed_status")]
+#[sqlx(rename_all = "snake_case")]
+pub enum FeedStatus {
+    Success,
+    NotModified,
+    Failure,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct FeedHealth {
+    /// The result of the last fetch, missing if the feed hasn't been fetched yet.
+    pub status: Option<FeedStatus>,
+    pub http_code: Option<u16>,
+    pub error: Option<String>,
+    pub consecutive_failures: i64,
+    pub last_fetch: Option<OffsetDateTime>,
+    pub last_success: Option<OffsetDateTime>,
+    pub next_fetch: Option<OffsetDateTime>,
+    /// When polling was stopped after too many failures in a row.
+    pub suspended: Option<OffsetDateTime>,
+}
+
 #[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct Subscriptions {
     pub total: i64,
@@ -54,7 +84,7 @@ impl IntoResponse for Subscriptions {
     }
 }
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct NewSubscription {
     pub feed_url: Url,
     pub guid: Uuid,
@@ -62,13 +92,13 @@ pub struct NewSubscription {
     pub subscription_changed: OffsetDateTime,
 }
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct FailedSubscription {
-    pub feed_url: Url,
+    pub feed_url: String,
     pub message: String,
 }
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct NewSubscriptions {
     pub success: Vec<NewSubscription>,
     pub failure: Vec<FailedSubscription>,
@@ -80,7 +110,7 @@ impl IntoResponse for NewSubscriptions {
     }
 }
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct SubscriptionUpdate {
     pub new_feed_url: Url,
     pub guid: Uuid,
@@ -162,7 +192,7 @@ impl Deletion {
     pub fn failure(deletion_id: i64) -> Self {
         Self {
             deletion_id,
-            status: DeletionStatus::Success,
+            status: DeletionStatus::Failure,
             message: "The deletion process encountered an error and was rolled back".to_string(),
         }
     }
@@ -173,3 +203,70 @@ impl IntoResponse for Deletion {
         (StatusCode::OK, Json(self)).into_response()
     }
 }
+
+/// The API a change to a subscription came through.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "subscription_client")]
+#[sqlx(rename_all = "snake_case")]
+pub enum Client {
+    Api,
+    Gpodder,
+    Nextcloud,
+    Web,
+    /// Changes the server made by itself, like identifying a feed.
+    Server,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "subscription_event")]
+#[sqlx(rename_all = "snake_case")]
+pub enum EventKind {
+    Subscribed,
+    Unsubscribed,
+    FeedMoved,
+    GuidChanged,
+    /// The user asked for the subscription to be deleted.
+    Deleted,
+    /// The user undid the deletion before the subscription was purged.
+    Restored,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SubscriptionEvent {
+    pub kind: EventKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feed_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guid: Option<Uuid>,
+    /// Missing for changes made before clients were recorded.
+    pub client: Option<Client>,
+    /// The identifier of the device that made the change, if it was made by one.
+    pub device: Option<String>,
+    pub timestamp: OffsetDateTime,
+}
+
+impl From<RowSubscriptionEvent> for SubscriptionEvent {
+    fn from(row: RowSubscriptionEvent) -> Self {
+        Self {
+            kind: row.kind,
+            feed_url: row.feed,
+            guid: row.guid,
+            client: row.client,
+            device: row.device_identifier,
+            timestamp: row.created,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SubscriptionHistory {
+    pub events: Vec<SubscriptionEvent>,
+}
+
+impl IntoResponse for SubscriptionHistory {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
@@ -0,0 +1,63 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+
+use crate::{database::tag::RowTag, utils::json::Json};
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+}
+
+impl From<RowTag> for Tag {
+    fn from(row: RowTag) -> Self {
+        Self {
+            id: row.id.0,
+            name: row.name,
+        }
+    }
+}
+
+impl IntoResponse for Tag {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Tags {
+    pub tags: Vec<Tag>,
+}
+
+impl IntoResponse for Tags {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct TagName {
+    pub name: String,
+}
+
+impl TagName {
+    /// Tag names are trimmed and must not be empty or longer than 64 characters.
+    pub fn name(&self) -> Option<&str> {
+        let name = self.name.trim();
+
+        (!name.is_empty() && name.chars().count() <= 64).then_some(name)
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SubscriptionTags {
+    pub tags: Vec<String>,
+}
+
+impl IntoResponse for SubscriptionTags {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
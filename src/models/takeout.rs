@@ -0,0 +1,211 @@
+//! The takeout archive: everything the server holds about a user, as `takeout.json` along with
+//! their subscriptions as `subscriptions.opml`.
+
+use std::io::{Cursor, Read as _, Write as _};
+
+use anyhow::Context as _;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use time::OffsetDateTime;
+use uuid::Uuid;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{
+    models::{
+        episodes::EpisodeActionKind,
+        subscriptions::{DeletionStatus, FailedSubscription, SubscriptionEvent},
+    },
+    utils::json::Json,
+};
+
+/// Bumped whenever the format changes in a way older importers can't read.
+pub const TAKEOUT_VERSION: u32 = 1;
+
+pub const TAKEOUT_JSON: &str = "takeout.json";
+pub const TAKEOUT_OPML: &str = "subscriptions.opml";
+
+/// The largest archive accepted for an import.
+pub const TAKEOUT_LIMIT: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Takeout {
+    pub version: u32,
+    pub exported: OffsetDateTime,
+    pub profile: TakeoutProfile,
+    pub devices: Vec<TakeoutDevice>,
+    /// Sessions and app passwords, their tokens are left out.
+    pub sessions: Vec<TakeoutSession>,
+    pub tags: Vec<TakeoutTag>,
+    pub subscriptions: Vec<TakeoutSubscription>,
+    pub deletions: Vec<TakeoutDeletion>,
+    pub episode_actions: Vec<TakeoutEpisodeAction>,
+}
+
+impl Takeout {
+    /// Writes the archive, `opml` is stored next to the takeout for other podcast apps.
+    pub fn to_archive(&self, opml: &str) -> anyhow::Result<Vec<u8>> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+
+        zip.start_file(TAKEOUT_JSON, options)?;
+        zip.write_all(&serde_json::to_vec_pretty(self)?)?;
+
+        zip.start_file(TAKEOUT_OPML, options)?;
+        zip.write_all(opml.as_bytes())?;
+
+        Ok(zip.finish()?.into_inner())
+    }
+
+    /// Reads the takeout out of an archive, refusing ones written by a newer version or that
+    /// decompress to more than [`TAKEOUT_LIMIT`].
+    pub fn from_archive(bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut zip = ZipArchive::new(Cursor::new(bytes)).context("Archive is not a zip file")?;
+
+        let mut json = Vec::new();
+        zip.by_name(TAKEOUT_JSON)
+            .context("Archive is missing the takeout")?
+            .take(TAKEOUT_LIMIT as u64 + 1)
+            .read_to_end(&mut json)?;
+        if json.len() > TAKEOUT_LIMIT {
+            anyhow::bail!("Takeout is larger than {} bytes", TAKEOUT_LIMIT);
+        }
+
+        let takeout: Takeout = serde_json::from_slice(&json).context("Takeout is invalid")?;
+        if takeout.version > TAKEOUT_VERSION {
+            anyhow::bail!("Takeout version {} is not supported", takeout.version);
+        }
+
+        Ok(takeout)
+    }
+}
+
+/// The user's account, without the password hash.
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct TakeoutProfile {
+    pub username: String,
+    pub email: String,
+    pub created: OffsetDateTime,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct TakeoutDevice {
+    pub identifier: String,
+    pub caption: String,
+    pub kind: String,
+    pub created: OffsetDateTime,
+    pub last_seen: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct TakeoutSession {
+    pub expires: OffsetDateTime,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct TakeoutTag {
+    pub name: String,
+    pub created: OffsetDateTime,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct TakeoutSubscription {
+    pub guid: Uuid,
+    pub feed_url: String,
+    pub is_subscribed: bool,
+    pub subscription_changed: Option<OffsetDateTime>,
+    pub tags: Vec<String>,
+    /// Every feed the subscription had, oldest first.
+    pub feeds: Vec<TakeoutFeed>,
+    /// Every GUID the subscription had, oldest first.
+    pub guids: Vec<TakeoutGuid>,
+    pub history: Vec<SubscriptionEvent>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct TakeoutFeed {
+    pub feed: String,
+    pub created: OffsetDateTime,
+    pub deleted: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct TakeoutGuid {
+    pub guid: Uuid,
+    pub created: OffsetDateTime,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct TakeoutDeletion {
+    pub id: i64,
+    /// Missing if the subscription has no GUID left.
+    pub guid: Option<Uuid>,
+    pub status: DeletionStatus,
+    pub created: OffsetDateTime,
+    pub purge_after: Option<OffsetDateTime>,
+    pub restored: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct TakeoutEpisodeAction {
+    pub podcast: String,
+    pub episode: String,
+    pub guid: Option<String>,
+    pub device: Option<String>,
+    pub action: EpisodeActionKind,
+    pub timestamp: OffsetDateTime,
+    pub started: Option<i64>,
+    pub position: Option<i64>,
+    pub total: Option<i64>,
+}
+
+/// What importing a takeout did, subscriptions are matched by their GUIDs (then feeds).
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct TakeoutImport {
+    /// Subscriptions the user didn't have, along with their history.
+    pub imported: Vec<Uuid>,
+    /// Subscriptions the user already had, only the feeds, GUIDs and tags they were missing
+    /// were added.
+    pub merged: Vec<Uuid>,
+    /// Tags that were created for the user.
+    pub tags: Vec<String>,
+    pub failure: Vec<FailedSubscription>,
+}
+
+impl IntoResponse for TakeoutImport {
+    fn into_response(self) -> Response {
+        (StatusCode::OK, Json(self)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write as _};
+
+    use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+    use super::{Takeout, TAKEOUT_JSON, TAKEOUT_LIMIT};
+
+    #[test]
+    fn oversized() {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file(TAKEOUT_JSON, options)
+            .expect("Failed to start takeout");
+
+        // Whitespace is valid JSON padding and compresses down to next to nothing.
+        let chunk = vec![b' '; 1024 * 1024];
+        for _ in 0..=(TAKEOUT_LIMIT / chunk.len()) {
+            zip.write_all(&chunk).expect("Failed to write takeout");
+        }
+
+        let archive = zip.finish().expect("Failed to finish archive").into_inner();
+        assert!(archive.len() < TAKEOUT_LIMIT);
+
+        let err = Takeout::from_archive(&archive).expect_err("Oversized takeout was read");
+        assert!(err.to_string().contains("larger than"));
+    }
+}
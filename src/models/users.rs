@@ -0,0 +1,39 @@
+/// The details of a new account, checked the same way whether it signs up or is created from the
+/// command line.
+#[derive(Debug, validator::Validate, serde::Deserialize)]
+pub struct NewUser {
+    #[validate(length(min = 6, max = 23))]
+    pub username: String,
+    #[validate(email)]
+    pub email: String,
+    #[validate(length(min = 8, max = 64))]
+    pub password: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use validator::Validate as _;
+
+    use super::NewUser;
+
+    #[test]
+    fn validate() {
+        let user = NewUser {
+            username: "example".to_string(),
+            email: "example@example.com".to_string(),
+            password: "correct horse".to_string(),
+        };
+        assert!(user.validate().is_ok());
+
+        let user = NewUser {
+            username: "ex".to_string(),
+            email: "example".to_string(),
+            password: "short".to_string(),
+        };
+        let errors = user.validate().unwrap_err();
+        let fields = errors.field_errors();
+        assert!(fields.contains_key("username"));
+        assert!(fields.contains_key("email"));
+        assert!(fields.contains_key("password"));
+    }
+}
@@ -0,0 +1,130 @@
+//! Removes accounts their users asked to delete, see [`Database::user_delete_request`].
+//!
+//! [`Database::user_delete_request`]: crate::database::Database::user_delete_request
+
+use crate::{
+    database::{jobs::Job, user::UserId},
+    SyncState,
+};
+
+pub async fn run(sync: &SyncState, user_id: UserId) -> anyhow::Result<()> {
+    // Already purged by an earlier attempt, or disabled by an operator which keeps everything.
+    let Some(user) = sync.db.user_get_by_id(user_id.0).await? else {
+        return Ok(());
+    };
+
+    // Emails would only pile up as dead jobs otherwise.
+    let confirmation = sync.cfg.email.smtp_url.is_some().then(|| Job::Email {
+        to: user.email.clone(),
+        subject: "Your pod-sync account was deleted".to_string(),
+        body: format!(
+            "Hi {},\n\nyour account has been deleted, along with your devices, subscriptions, tags \
+             and episode actions.\n",
+            user.username
+        ),
+    });
+
+    sync.db.user_purge(&user, confirmation.as_ref()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        database::{jobs::Job, Database},
+        models::jobs::JobKind,
+        SyncState,
+    };
+
+    #[sqlx::test(fixtures("../../fixtures/dummy.sql"))]
+    async fn delete(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let sync = SyncState::new_test(pool).await.unwrap();
+        let db = &sync.db;
+        let user = db
+            .user_get_by_id(Database::USER_ID)
+            .await
+            .unwrap()
+            .expect("Test user is missing");
+
+        db.user_delete_request(&user).await.unwrap();
+
+        // Logged out and unable to log back in straight away.
+        assert!(db
+            .session_get_by_token(Database::TOKEN)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(db
+            .user_get_by_username(&user.username)
+            .await
+            .unwrap()
+            .is_none());
+
+        // Not the user's, nobody references it but it's left alone.
+        sqlx::query!(
+            r#"--sql
+                INSERT INTO subscriptions (id) VALUES (11111)
+            "#,
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            r#"--sql
+                INSERT INTO subscription_feeds (subscription_id, feed)
+                VALUES (11111, 'http://orphan.example.com/feed.rss')
+            "#,
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+        db.websub_request(
+            Database::SUBSCRIPTION_1_FEED,
+            "http://hub.example.com/",
+            Database::SUBSCRIPTION_1_FEED,
+            "secret",
+        )
+        .await
+        .unwrap();
+
+        let row = db
+            .job_lease(JobKind::AccountDeletion, time::Duration::minutes(1))
+            .await
+            .unwrap()
+            .expect("Account deletion was not queued");
+        let Ok(Job::AccountDeletion { user_id }) = row.job() else {
+            panic!("Unexpected job {:?}", row.job());
+        };
+
+        super::run(&sync, user_id).await.unwrap();
+
+        assert!(db.user_get_by_id(user_id.0).await.unwrap().is_none());
+        // Nobody else was subscribed, so the shared subscriptions go too.
+        assert!(db
+            .subscription_get_id_by_guid(Database::SUBSCRIPTION_1_GUID)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(db
+            .subscription_get_id_by_guid(Database::SUBSCRIPTION_3_GUID_NEW)
+            .await
+            .unwrap()
+            .is_none());
+
+        assert!(db
+            .websub_get_by_feed(Database::SUBSCRIPTION_1_FEED)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(db
+            .subscription_get_id_by_feed("http://orphan.example.com/feed.rss")
+            .await
+            .unwrap()
+            .is_some());
+
+        // Running it again finds nothing left to do.
+        super::run(&sync, user_id).await.unwrap();
+    }
+}
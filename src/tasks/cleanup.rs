@@ -0,0 +1,97 @@
+//! Removes rows that are only kept around for a while, so the database doesn't keep growing.
+
+use std::time::Duration;
+
+use time::OffsetDateTime;
+
+use crate::{tasks::TaskStatus, SyncState};
+
+/// How often to clean up.
+const TICK: Duration = Duration::from_secs(60 * 60);
+
+/// How long finished jobs are kept for operators to look at.
+const JOB_RETENTION: time::Duration = time::Duration::days(14);
+
+pub async fn cleanup(sync: SyncState, status: TaskStatus) {
+    while status.is_active() {
+        let now = OffsetDateTime::now_utc();
+
+        match sync.db.jobs_prune(now - JOB_RETENTION).await {
+            Ok(pruned) => tracing::debug!(pruned, "Pruned finished jobs"),
+            Err(err) => tracing::error!(err = ?err, "Failed to prune finished jobs"),
+        }
+
+        match sync.db.exports_prune().await {
+            Ok(pruned) => tracing::debug!(pruned, "Pruned expired exports"),
+            Err(err) => tracing::error!(err = ?err, "Failed to prune expired exports"),
+        }
+
+        match sync.db.sessions_prune().await {
+            Ok(pruned) => tracing::debug!(pruned, "Pruned expired sessions"),
+            Err(err) => tracing::error!(err = ?err, "Failed to prune expired sessions"),
+        }
+
+        status.sleep(TICK).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+
+    use crate::{
+        database::jobs::Job,
+        models::jobs::{JobKind, JobStatus},
+        SyncState,
+    };
+
+    #[sqlx::test(fixtures("../../fixtures/dummy.sql"))]
+    async fn prune_jobs(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let sync = SyncState::new_test(pool.clone()).await.unwrap();
+        let now = OffsetDateTime::now_utc();
+
+        for feed in [
+            "http://one.example.com/feed.rss",
+            "http://two.example.com/feed.rss",
+        ] {
+            let job = Job::Refresh {
+                feed: feed.to_string(),
+            };
+
+            sync.db.job_enqueue(&job, now).await.unwrap();
+        }
+
+        let row = sync
+            .db
+            .job_lease(JobKind::Refresh, time::Duration::minutes(10))
+            .await
+            .unwrap()
+            .expect("Job was not queued");
+        sync.db.job_complete(&row).await.unwrap();
+
+        // Only finished jobs are removed, and only once they are old enough.
+        assert_eq!(sync.db.jobs_prune(now).await.unwrap(), 0);
+        assert_eq!(
+            sync.db
+                .jobs_prune(now + time::Duration::minutes(1))
+                .await
+                .unwrap(),
+            1
+        );
+
+        let statuses = sqlx::query!(
+            r#"--sql
+                SELECT status as "status: JobStatus"
+                FROM jobs
+                WHERE kind = 'refresh'
+            "#
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].status, JobStatus::Queued);
+    }
+}
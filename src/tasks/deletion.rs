@@ -1,5 +1,225 @@
-use crate::tasks::TaskStatus;
+//! Purges deleted subscriptions once their grace period is over.
+//!
+//! The job is queued to run after the grace period, until then the deletion can be undone, see
+//! [`Database::deletion_restore`].
+//!
+//! [`Database::deletion_restore`]: crate::database::Database::deletion_restore
 
-pub async fn deletion(status: TaskStatus) {
-    while status.is_active() {}
+use crate::{database::tasks::DeletionId, models::subscriptions::DeletionStatus, SyncState};
+
+pub async fn run(sync: &SyncState, id: DeletionId) -> anyhow::Result<()> {
+    let Some(deletion) = sync.db.deletion_get_row(id).await? else {
+        return Ok(());
+    };
+
+    // Restored or already purged.
+    if deletion.deleted.is_some() || deletion.status != DeletionStatus::Pending {
+        return Ok(());
+    }
+
+    // Subscribed to again without the deletion being undone.
+    if sync.db.deletion_is_resubscribed(&deletion).await? {
+        tracing::debug!("Skipping the purge of a subscription that was subscribed to again");
+
+        return Ok(());
+    }
+
+    sync.db.deletion_purge(&deletion).await
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use crate::{
+        database::{jobs::Job, subscription::history::Origin, tasks::DeletionId, Database},
+        models::{
+            jobs::JobKind,
+            subscriptions::{Client, DeletionStatus},
+        },
+        SyncState,
+    };
+
+    #[sqlx::test(fixtures("../../fixtures/dummy.sql"))]
+    async fn grace_period(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let sync = SyncState::new_test(pool).await.unwrap();
+        let db = &sync.db;
+        let lease = time::Duration::minutes(1);
+        let user = db
+            .user_get_by_id(Database::USER_ID)
+            .await
+            .unwrap()
+            .expect("Test user is missing");
+        let origin = Origin::new(Client::Api);
+
+        // The fixtures only have a failed deletion for this one, so a new one gets queued.
+        let id = db
+            .deletion_create(
+                &user,
+                origin,
+                Database::SUBSCRIPTION_3_GUID_NEW,
+                time::Duration::days(30),
+            )
+            .await
+            .unwrap()
+            .expect("Deletion was not created");
+
+        assert!(db
+            .job_lease(JobKind::Deletion, lease)
+            .await
+            .unwrap()
+            .is_none());
+
+        let subscription = db
+            .subscription_get_by_guid(&user, Database::SUBSCRIPTION_3_GUID_NEW)
+            .await
+            .unwrap()
+            .expect("Subscription was purged during its grace period");
+        assert!(!subscription.is_subscribed);
+
+        let restored = db
+            .deletion_restore(&user, origin, Database::SUBSCRIPTION_3_ID.into())
+            .await
+            .unwrap();
+        assert!(restored);
+
+        let subscription = db
+            .subscription_get_by_guid(&user, Database::SUBSCRIPTION_3_GUID_NEW)
+            .await
+            .unwrap()
+            .expect("Subscription was not restored");
+        assert!(subscription.is_subscribed);
+        assert!(db
+            .deletion_get(&user, DeletionId(id))
+            .await
+            .unwrap()
+            .is_none());
+
+        let id = db
+            .deletion_create(
+                &user,
+                origin,
+                Database::SUBSCRIPTION_3_GUID_NEW,
+                time::Duration::ZERO,
+            )
+            .await
+            .unwrap()
+            .expect("Deletion was not created");
+
+        let job = db
+            .job_lease(JobKind::Deletion, lease)
+            .await
+            .unwrap()
+            .expect("Deletion was not queued");
+        assert!(matches!(
+            job.job().unwrap(),
+            Job::Deletion { deletion_id } if deletion_id.0 == id
+        ));
+
+        super::run(&sync, DeletionId(id)).await.unwrap();
+
+        assert!(db
+            .subscription_get_by_guid(&user, Database::SUBSCRIPTION_3_GUID_NEW)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            db.deletion_get(&user, DeletionId(id))
+                .await
+                .unwrap()
+                .map(|deletion| deletion.status),
+            Some(DeletionStatus::Success)
+        );
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/dummy.sql"))]
+    async fn resubscribe(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let sync = SyncState::new_test(pool.clone()).await.unwrap();
+        let db = &sync.db;
+        let lease = time::Duration::minutes(1);
+        let user = db
+            .user_get_by_id(Database::USER_ID)
+            .await
+            .unwrap()
+            .expect("Test user is missing");
+        let origin = Origin::new(Client::Api);
+
+        let id = db
+            .deletion_create(
+                &user,
+                origin,
+                Database::SUBSCRIPTION_3_GUID_NEW,
+                time::Duration::ZERO,
+            )
+            .await
+            .unwrap()
+            .expect("Deletion was not created");
+
+        db.subscription_create(
+            &user,
+            origin,
+            &Url::parse(Database::SUBSCRIPTION_3_FEED).unwrap(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        // Subscribing again undoes the deletion and drops its job.
+        assert!(db
+            .job_lease(JobKind::Deletion, lease)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(db
+            .deletion_get(&user, DeletionId(id))
+            .await
+            .unwrap()
+            .is_none());
+
+        super::run(&sync, DeletionId(id)).await.unwrap();
+
+        let subscription = db
+            .subscription_get_by_guid(&user, Database::SUBSCRIPTION_3_GUID_NEW)
+            .await
+            .unwrap()
+            .expect("Subscription was purged after being subscribed to again");
+        assert!(subscription.is_subscribed);
+
+        // Even when the deletion is still pending, a subscription that is subscribed to isn't
+        // purged.
+        let id = db
+            .deletion_create(
+                &user,
+                origin,
+                Database::SUBSCRIPTION_3_GUID_NEW,
+                time::Duration::ZERO,
+            )
+            .await
+            .unwrap()
+            .expect("Deletion was not created");
+        sqlx::query("UPDATE user_subscriptions SET deleted = NULL WHERE subscription_id = ?1")
+            .bind(Database::SUBSCRIPTION_3_ID)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        super::run(&sync, DeletionId(id)).await.unwrap();
+
+        assert!(db
+            .subscription_get_by_guid(&user, Database::SUBSCRIPTION_3_GUID_NEW)
+            .await
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            db.deletion_get(&user, DeletionId(id))
+                .await
+                .unwrap()
+                .map(|deletion| deletion.status),
+            Some(DeletionStatus::Pending)
+        );
+    }
 }
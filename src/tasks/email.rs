@@ -0,0 +1,30 @@
+//! Sends emails through the configured SMTP server.
+
+use anyhow::Context as _;
+use lettre::{AsyncSmtpTransport, AsyncTransport as _, Message, Tokio1Executor};
+
+use crate::SyncState;
+
+pub async fn run(sync: &SyncState, to: &str, subject: &str, body: &str) -> anyhow::Result<()> {
+    let (Some(url), Some(from)) = (&sync.cfg.email.smtp_url, &sync.cfg.email.from) else {
+        anyhow::bail!("Email is not configured, `email.smtp-url` and `email.from` must be set");
+    };
+
+    let message = Message::builder()
+        .from(from.parse().context("Invalid sender address")?)
+        .to(to.parse().context("Invalid recipient address")?)
+        .subject(subject)
+        .body(body.to_string())
+        .context("Failed to build email")?;
+
+    let transport = AsyncSmtpTransport::<Tokio1Executor>::from_url(url)
+        .context("Invalid SMTP URL")?
+        .build();
+
+    transport
+        .send(message)
+        .await
+        .context("Failed to send email")?;
+
+    Ok(())
+}
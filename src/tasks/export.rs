@@ -0,0 +1,290 @@
+//! Builds a user's takeout archive, a zip holding everything the server has about them.
+
+use anyhow::Context as _;
+use time::OffsetDateTime;
+
+use crate::{
+    database::{episode::EpisodeActionFilter, export::ExportId, user::User},
+    models::{
+        opml::Opml,
+        subscriptions::SubscriptionEvent,
+        takeout::{
+            Takeout, TakeoutDeletion, TakeoutDevice, TakeoutEpisodeAction, TakeoutFeed,
+            TakeoutGuid, TakeoutProfile, TakeoutSession, TakeoutSubscription, TakeoutTag,
+            TAKEOUT_VERSION,
+        },
+    },
+    utils::opml::OpmlDocument,
+    SyncState,
+};
+
+pub async fn run(sync: &SyncState, id: ExportId) -> anyhow::Result<()> {
+    // Expired exports get removed, leaving their job nothing to do.
+    let Some(export) = sync.db.export_get(id).await? else {
+        return Ok(());
+    };
+    let Some(user) = sync.db.user_get_by_id(export.user_id).await? else {
+        return Ok(());
+    };
+
+    let takeout = takeout(sync, &user).await?;
+    let opml = OpmlDocument(Opml::export(&sync.db, &user).await?).encode()?;
+
+    let archive = takeout
+        .to_archive(&opml)
+        .context("Failed to write takeout archive")?;
+
+    sync.db.export_complete(id, &archive).await
+}
+
+async fn takeout(sync: &SyncState, user: &User) -> anyhow::Result<Takeout> {
+    let profile = sync.db.user_get_profile(user).await?;
+
+    let devices = sync
+        .db
+        .devices_get_all(user)
+        .await?
+        .into_iter()
+        .map(|device| TakeoutDevice {
+            identifier: device.identifier,
+            caption: device.caption,
+            kind: device.kind,
+            created: device.created,
+            last_seen: device.last_seen,
+        })
+        .collect();
+
+    let sessions = sync
+        .db
+        .sessions_get_all(user)
+        .await?
+        .into_iter()
+        .map(|session| TakeoutSession {
+            expires: session.expires,
+        })
+        .collect();
+
+    let tags = sync
+        .db
+        .tags_get_all(user)
+        .await?
+        .into_iter()
+        .map(|tag| TakeoutTag {
+            name: tag.name,
+            created: tag.created,
+        })
+        .collect();
+
+    let mut subscriptions = Vec::new();
+    for subscription in sync.db.subscriptions_get_all_unpaged(user).await? {
+        let Some(row) = sync
+            .db
+            .subscription_get_id_by_guid(subscription.guid)
+            .await?
+        else {
+            continue;
+        };
+        let id = row.subscription_id;
+
+        let feeds = sync
+            .db
+            .subscription_get_feeds(id)
+            .await?
+            .into_iter()
+            .map(|feed| TakeoutFeed {
+                feed: feed.feed,
+                created: feed.created,
+                deleted: feed.deleted,
+            })
+            .collect();
+        let guids = sync
+            .db
+            .subscription_get_guids(id)
+            .await?
+            .into_iter()
+            .map(|guid| TakeoutGuid {
+                guid: guid.guid,
+                created: guid.created,
+            })
+            .collect();
+        let history = sync
+            .db
+            .subscription_history_get(user, id)
+            .await?
+            .into_iter()
+            .map(SubscriptionEvent::from)
+            .collect();
+
+        subscriptions.push(TakeoutSubscription {
+            guid: subscription.guid,
+            feed_url: subscription.feed_url.to_string(),
+            is_subscribed: subscription.is_subscribed,
+            subscription_changed: subscription.subscription_changed,
+            tags: subscription.tags,
+            feeds,
+            guids,
+            history,
+        });
+    }
+
+    let mut deletions = Vec::new();
+    for deletion in sync.db.deletions_get_all(user).await? {
+        let guid = sync
+            .db
+            .subscription_get_guids(deletion.subscription_id)
+            .await?
+            .into_iter()
+            .next()
+            .map(|guid| guid.guid);
+
+        deletions.push(TakeoutDeletion {
+            id: deletion.id.0,
+            guid,
+            status: deletion.status,
+            created: deletion.created,
+            purge_after: deletion.purge_after,
+            restored: deletion.deleted,
+        });
+    }
+
+    let episode_actions = sync
+        .db
+        .episode_actions_get(user, EpisodeActionFilter::default())
+        .await?
+        .into_iter()
+        .map(|action| TakeoutEpisodeAction {
+            podcast: action.podcast,
+            episode: action.episode,
+            guid: action.guid,
+            device: action.device,
+            action: action.action,
+            timestamp: action.timestamp,
+            started: action.started,
+            position: action.position,
+            total: action.total,
+        })
+        .collect();
+
+    Ok(Takeout {
+        version: TAKEOUT_VERSION,
+        exported: OffsetDateTime::now_utc(),
+        profile: TakeoutProfile {
+            username: profile.username,
+            email: profile.email,
+            created: profile.created,
+        },
+        devices,
+        sessions,
+        tags,
+        subscriptions,
+        deletions,
+        episode_actions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read as _};
+
+    use zip::ZipArchive;
+
+    use crate::{
+        database::Database,
+        models::takeout::{Takeout, TAKEOUT_JSON, TAKEOUT_OPML},
+        SyncState,
+    };
+
+    #[sqlx::test(fixtures("../../fixtures/dummy.sql"))]
+    async fn export(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let sync = SyncState::new_test(pool).await.unwrap();
+        let user = sync
+            .db
+            .user_get_by_id(Database::USER_ID)
+            .await
+            .unwrap()
+            .expect("Test user is missing");
+
+        let export = sync
+            .db
+            .export_create(&user, time::Duration::days(1))
+            .await
+            .unwrap()
+            .expect("Export was not created");
+        assert!(sync
+            .db
+            .export_get_archive(&user, &export.token)
+            .await
+            .unwrap()
+            .is_none());
+
+        super::run(&sync, export.id).await.unwrap();
+
+        let archive = sync
+            .db
+            .export_get_archive(&user, &export.token)
+            .await
+            .unwrap()
+            .expect("Export was not completed");
+        let mut archive = ZipArchive::new(Cursor::new(archive)).unwrap();
+
+        let mut json = String::new();
+        archive
+            .by_name(TAKEOUT_JSON)
+            .unwrap()
+            .read_to_string(&mut json)
+            .unwrap();
+        let takeout: Takeout = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(takeout.profile.username, user.username);
+        assert_eq!(takeout.subscriptions.len(), 3);
+        assert_eq!(takeout.deletions.len(), 3);
+        assert!(takeout
+            .subscriptions
+            .iter()
+            .any(|subscription| subscription.guids.len() == 2));
+
+        assert!(archive.by_name(TAKEOUT_OPML).is_ok());
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/dummy.sql"))]
+    async fn limit(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let sync = SyncState::new_test(pool).await.unwrap();
+        let user = sync
+            .db
+            .user_get_by_id(Database::USER_ID)
+            .await
+            .unwrap()
+            .expect("Test user is missing");
+
+        // Expired exports don't count towards the limit.
+        let expired = sync
+            .db
+            .export_create(&user, time::Duration::days(-1))
+            .await
+            .unwrap();
+        assert!(expired.is_some());
+
+        for _ in 0..3 {
+            let export = sync
+                .db
+                .export_create(&user, time::Duration::days(1))
+                .await
+                .unwrap();
+            assert!(export.is_some());
+        }
+
+        let export = sync
+            .db
+            .export_create(&user, time::Duration::days(1))
+            .await
+            .unwrap();
+        assert!(export.is_none());
+
+        assert_eq!(sync.db.exports_prune().await.unwrap(), 1);
+        assert_eq!(sync.db.exports_get_all(&user).await.unwrap().len(), 3);
+    }
+}
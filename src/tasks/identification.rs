@@ -1,5 +1,40 @@
-use crate::tasks::TaskStatus;
+//! Fetches the feeds of new subscriptions to find the GUID they give themselves.
+//!
+//! Feeds that use the Podcasting 2.0 `podcast:guid` tag get it recorded as the subscription's new
+//! GUID, which clients pick up through `new_guid`.
 
-pub async fn identification(status: TaskStatus) {
-    while status.is_active() {}
+use anyhow::Context as _;
+use url::Url;
+use uuid::Uuid;
+
+use crate::{
+    database::subscription::SubscriptionId, models::rss::Rss, utils::http::HttpClient, SyncState,
+};
+
+pub async fn run(sync: &SyncState, subscription_id: SubscriptionId) -> anyhow::Result<()> {
+    let feeds = sync.db.subscription_get_feeds(subscription_id).await?;
+
+    // Nothing left to identify.
+    let Some(feed) = feeds.iter().rev().find(|feed| feed.deleted.is_none()) else {
+        return Ok(());
+    };
+
+    let guid = fetch_guid(&sync.http, &feed.feed)
+        .await
+        .context("Failed to identify subscription feed")?;
+
+    sync.db.identification_complete(subscription_id, guid).await
+}
+
+async fn fetch_guid(client: &HttpClient, feed: &str) -> anyhow::Result<Option<Uuid>> {
+    let url = Url::parse(feed)?;
+
+    let response = client.get(&url)?.send().await?.error_for_status()?;
+    let body = client.text(response).await?;
+    let rss: Rss = quick_xml::de::from_str(&body)?;
+
+    Ok(rss
+        .channel
+        .podcast_guid
+        .and_then(|guid| Uuid::parse_str(guid.trim()).ok()))
 }
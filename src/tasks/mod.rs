@@ -1,72 +1,90 @@
+pub mod account;
+pub mod cleanup;
 pub mod deletion;
+pub mod email;
+pub mod export;
 pub mod identification;
+pub mod refresh;
+pub mod websub;
+pub mod worker;
 
-use std::{
-    future::Future,
-    pin::Pin,
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
-    task::{Context, Poll},
-};
+use std::{future::Future, time::Duration};
 
-use tokio::task::{self, JoinHandle};
+use tokio::{sync::watch, task::JoinSet};
 
-pub use self::{deletion::deletion, identification::identification};
+use crate::{models::jobs::JobKind, SyncState};
+
+pub use self::{cleanup::cleanup, refresh::refresh, websub::websub, worker::worker};
 
 #[derive(Clone)]
 pub struct TaskStatus {
-    active: Arc<AtomicBool>,
+    stopped: watch::Receiver<bool>,
 }
 
 impl TaskStatus {
-    fn new() -> Self {
-        Self {
-            active: Arc::new(AtomicBool::new(true)),
-        }
-    }
-
     pub fn is_active(&self) -> bool {
-        self.active.load(Ordering::Acquire)
+        !*self.stopped.borrow()
     }
 
-    pub fn stop(&self) {
-        self.active.store(false, Ordering::Release);
+    /// Sleeps for the duration, waking up early if the task is stopped.
+    pub async fn sleep(&self, duration: Duration) {
+        let mut stopped = self.stopped.clone();
+
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => {},
+            _ = stopped.wait_for(|stopped| *stopped) => {},
+        }
     }
 }
 
-pin_project_lite::pin_project! {
-    pub struct Task {
-        status: TaskStatus,
-        #[pin]
-        handle: JoinHandle<()>,
-    }
+/// The server's background tasks, they are all stopped together when the server shuts down.
+pub struct Tasks {
+    stop: watch::Sender<bool>,
+    set: JoinSet<()>,
 }
 
-impl Task {
-    pub fn spawn<T, F>(task: T) -> Self
+impl Tasks {
+    pub fn new() -> Self {
+        let (stop, _) = watch::channel(false);
+
+        Self {
+            stop,
+            set: JoinSet::new(),
+        }
+    }
+
+    pub fn spawn<T, F>(&mut self, task: T)
     where
-        T: FnOnce(TaskStatus) -> F + 'static,
+        T: FnOnce(TaskStatus) -> F,
         F: Future<Output = ()> + Send + 'static,
     {
-        let status = TaskStatus::new();
+        let status = TaskStatus {
+            stopped: self.stop.subscribe(),
+        };
 
-        Self {
-            status: status.clone(),
-            handle: task::spawn(task(status)),
-        }
+        self.set.spawn(task(status));
     }
-}
 
-impl Future for Task {
-    type Output = <JoinHandle<()> as Future>::Output;
+    /// Starts the configured number of workers for each kind of job.
+    pub fn spawn_workers(&mut self, sync: &SyncState) {
+        for kind in JobKind::ALL {
+            for _ in 0..sync.cfg.jobs.workers(kind) {
+                let sync = sync.clone();
 
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        self.status.stop();
+                self.spawn(move |status| worker(sync, kind, status));
+            }
+        }
+    }
 
-        let this = self.project();
+    /// Stops every task and waits for them to finish what they are doing, jobs that are cut
+    /// short are picked up again once their lease runs out.
+    pub async fn shutdown(mut self) {
+        self.stop.send_replace(true);
 
-        this.handle.poll(cx)
+        while let Some(result) = self.set.join_next().await {
+            if let Err(err) = result {
+                tracing::error!(err = ?err, "Background task panicked");
+            }
+        }
     }
 }
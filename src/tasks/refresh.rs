@@ -0,0 +1,323 @@
+//! Periodically fetches subscribed feeds and stores their podcast and episode metadata.
+//!
+//! Each feed has its own interval, based on how often it publishes episodes, with some jitter so
+//! feeds that were added together don't keep getting fetched together. Fetches are conditional
+//! (`ETag` and `Last-Modified`) so unchanged feeds are cheap for everyone.
+
+use std::time::Duration;
+
+use rand::Rng as _;
+use reqwest::{header, StatusCode};
+use time::OffsetDateTime;
+use url::Url;
+
+use crate::{
+    database::{
+        feed::{FeedFetchResult, RowFeedFetch},
+        jobs::Job,
+    },
+    models::{
+        rss::{Item, Rss},
+        subscriptions::FeedStatus,
+    },
+    tasks::{websub, TaskStatus},
+    utils::http::{HttpClient, HttpError},
+    SyncState,
+};
+
+/// How often to check for due feeds.
+const TICK: Duration = Duration::from_secs(30);
+
+/// How many due feeds are queued per tick.
+const BATCH: i64 = 100;
+
+const MIN_INTERVAL: i64 = 15 * 60;
+const DEFAULT_INTERVAL: i64 = 60 * 60;
+const MAX_INTERVAL: i64 = 24 * 60 * 60;
+
+/// Feeds are suspended after failing this many times in a row.
+const MAX_FAILURES: i64 = 10;
+
+/// How much of the interval is randomly added or removed.
+const JITTER: f64 = 0.1;
+
+/// Queues a refresh job for every due feed, the refresh workers do the fetching.
+pub async fn refresh(sync: SyncState, status: TaskStatus) {
+    while status.is_active() {
+        if let Err(err) = sync.db.feed_fetches_enqueue(DEFAULT_INTERVAL).await {
+            tracing::error!(err = ?err, "Failed to enqueue new feeds");
+        }
+
+        let due = match sync.db.feed_fetches_get_due(BATCH).await {
+            Ok(due) => due,
+            Err(err) => {
+                tracing::error!(err = ?err, "Failed to retrieve due feeds");
+
+                vec![]
+            }
+        };
+
+        let now = OffsetDateTime::now_utc();
+
+        for fetch in due {
+            // Feeds that are still waiting for a worker are skipped.
+            if let Err(err) = sync
+                .db
+                .job_enqueue(&Job::Refresh { feed: fetch.feed }, now)
+                .await
+            {
+                tracing::error!(err = ?err, "Failed to queue feed refresh");
+            }
+        }
+
+        status.sleep(TICK).await;
+    }
+}
+
+pub async fn run(sync: &SyncState, feed: &str) -> anyhow::Result<()> {
+    // The feed was suspended or lost its last subscriber since it was queued.
+    let Some(fetch) = sync.db.feed_fetch_get(feed).await? else {
+        return Ok(());
+    };
+
+    refresh_feed(sync, fetch).await;
+
+    Ok(())
+}
+
+#[tracing::instrument(skip_all, fields(feed = %fetch.feed))]
+async fn refresh_feed(sync: &SyncState, fetch: RowFeedFetch) {
+    let fetched = fetch_feed(sync, &fetch).await;
+
+    let (result, interval, delay) = match &fetched {
+        Ok(Fetched::NotModified) => {
+            tracing::debug!("Feed has not been modified");
+
+            let interval = (fetch.interval * 5 / 4).min(MAX_INTERVAL);
+            let result = FeedFetchResult {
+                status: FeedStatus::NotModified,
+                http_code: Some(StatusCode::NOT_MODIFIED.as_u16()),
+                error: None,
+                etag: None,
+                last_modified: None,
+            };
+
+            (result, interval, interval)
+        }
+        Ok(Fetched::Updated {
+            http_code,
+            etag,
+            last_modified,
+            interval,
+            created,
+        }) => {
+            tracing::debug!(created, "Feed has been refreshed");
+
+            let interval = interval.unwrap_or(fetch.interval);
+            let result = FeedFetchResult {
+                status: FeedStatus::Success,
+                http_code: Some(*http_code),
+                error: None,
+                etag: etag.as_deref(),
+                last_modified: last_modified.as_deref(),
+            };
+
+            (result, interval, interval)
+        }
+        Err((http_code, err)) => {
+            tracing::warn!(err = ?err, "Failed to refresh feed");
+
+            // Back off exponentially while the feed keeps failing, without forgetting its
+            // interval for when it recovers.
+            let delay =
+                (fetch.interval << fetch.consecutive_failures.clamp(0, 6)).min(MAX_INTERVAL);
+            let result = FeedFetchResult {
+                status: FeedStatus::Failure,
+                http_code: *http_code,
+                error: Some(err.as_str()),
+                etag: None,
+                last_modified: None,
+            };
+
+            (result, fetch.interval, delay)
+        }
+    };
+
+    let next_fetch = OffsetDateTime::now_utc() + jitter(delay);
+
+    match sync
+        .db
+        .feed_fetch_record(fetch.id, &result, interval, next_fetch, MAX_FAILURES)
+        .await
+    {
+        Ok(true) => tracing::warn!("Feed has been suspended after failing too many times"),
+        Ok(false) => {}
+        Err(err) => tracing::error!(err = ?err, "Failed to record feed fetch"),
+    }
+}
+
+enum Fetched {
+    NotModified,
+    Updated {
+        http_code: u16,
+        etag: Option<String>,
+        last_modified: Option<String>,
+        interval: Option<i64>,
+        created: usize,
+    },
+}
+
+/// Fetches the feed, failures come with the response's status code (if there was one) and a
+/// message that can be shown to the user.
+async fn fetch_feed(
+    sync: &SyncState,
+    fetch: &RowFeedFetch,
+) -> Result<Fetched, (Option<u16>, String)> {
+    let response = request_feed(&sync.http, fetch).await.map_err(|err| {
+        let http_code = match &err {
+            HttpError::Request(err) => err.status().map(|status| status.as_u16()),
+            _ => None,
+        };
+
+        (http_code, err.to_string())
+    })?;
+
+    let Some(response) = response else {
+        return Ok(Fetched::NotModified);
+    };
+
+    let http_code = response.status().as_u16();
+    let failed = |err: String| (Some(http_code), err);
+
+    let header = |name: header::HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let etag = header(header::ETAG);
+    let last_modified = header(header::LAST_MODIFIED);
+
+    let body = sync
+        .http
+        .text(response)
+        .await
+        .map_err(|err| failed(err.to_string()))?;
+    let rss: Rss = quick_xml::de::from_str(&body)
+        .map_err(|err| failed(format!("Feed could not be parsed: {}", err)))?;
+
+    let created = sync
+        .db
+        .feed_store(&fetch.feed, &rss.channel)
+        .await
+        .map_err(|err| {
+            tracing::error!(err = ?err, "Failed to store feed");
+
+            failed("Feed could not be stored".to_string())
+        })?;
+
+    websub::subscribe_channel(sync, &fetch.feed, &rss.channel).await;
+
+    Ok(Fetched::Updated {
+        http_code,
+        etag,
+        last_modified,
+        interval: adaptive_interval(&rss.channel.items),
+        created,
+    })
+}
+
+/// Sends a conditional request for the feed, `None` if it hasn't changed since the last fetch.
+async fn request_feed(
+    client: &HttpClient,
+    fetch: &RowFeedFetch,
+) -> Result<Option<reqwest::Response>, HttpError> {
+    let url = Url::parse(&fetch.feed)?;
+
+    let mut request = client.get(&url)?;
+    if let Some(etag) = &fetch.etag {
+        request = request.header(header::IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = &fetch.last_modified {
+        request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+    }
+
+    let response = request.send().await?;
+
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(None);
+    }
+
+    Ok(Some(response.error_for_status()?))
+}
+
+/// Picks an interval of about a quarter of the average time between the latest episodes, so a
+/// daily show is checked every few hours and a monthly one about once a day.
+fn adaptive_interval(items: &[Item]) -> Option<i64> {
+    let mut published = items.iter().filter_map(Item::published).collect::<Vec<_>>();
+    published.sort_unstable_by(|a, b| b.cmp(a));
+    published.truncate(10);
+
+    if published.len() < 2 {
+        return None;
+    }
+
+    let newest = published.first()?;
+    let oldest = published.last()?;
+    let average = (*newest - *oldest).whole_seconds() / (published.len() as i64 - 1);
+
+    Some((average / 4).clamp(MIN_INTERVAL, MAX_INTERVAL))
+}
+
+fn jitter(interval: i64) -> time::Duration {
+    let factor = rand::thread_rng().gen_range((1.0 - JITTER)..=(1.0 + JITTER));
+
+    time::Duration::seconds((interval as f64 * factor) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::rss::Item;
+
+    use super::{adaptive_interval, MAX_INTERVAL, MIN_INTERVAL};
+
+    fn items(dates: &[&str]) -> Vec<Item> {
+        dates
+            .iter()
+            .map(|date| {
+                quick_xml::de::from_str(&format!("<item><pubDate>{}</pubDate></item>", date))
+                    .expect("Failed to parse item")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn adaptive() {
+        assert_eq!(adaptive_interval(&items(&[])), None);
+        assert_eq!(
+            adaptive_interval(&items(&["Mon, 21 Oct 2024 10:00:00 +0000", "not a date"])),
+            None
+        );
+
+        // Daily episodes are checked four times a day.
+        let daily = items(&[
+            "Mon, 21 Oct 2024 10:00:00 +0000",
+            "Wed, 23 Oct 2024 10:00:00 +0000",
+            "Tue, 22 Oct 2024 10:00:00 +0000",
+        ]);
+        assert_eq!(adaptive_interval(&daily), Some(6 * 60 * 60));
+
+        let frequent = items(&[
+            "Mon, 21 Oct 2024 10:00:00 +0000",
+            "Mon, 21 Oct 2024 10:01:00 +0000",
+        ]);
+        assert_eq!(adaptive_interval(&frequent), Some(MIN_INTERVAL));
+
+        let monthly = items(&[
+            "Tue, 01 Oct 2024 10:00:00 +0000",
+            "Fri, 01 Nov 2024 10:00:00 +0000",
+        ]);
+        assert_eq!(adaptive_interval(&monthly), Some(MAX_INTERVAL));
+    }
+}
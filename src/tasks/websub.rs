@@ -0,0 +1,307 @@
+//! Subscribes to the WebSub hubs feeds advertise, so new episodes are pushed to us instead of
+//! waiting for the next refresh, and keeps those subscriptions from expiring.
+//!
+//! Hubs can only reach us if the server knows its public URL, without one nothing is
+//! subscribed and feeds are only polled.
+
+use std::time::Duration;
+
+use data_encoding::HEXLOWER;
+use rand::RngCore as _;
+use reqwest::StatusCode;
+use url::Url;
+
+use crate::{
+    database::{websub::RowWebSub, Database},
+    models::rss::Channel,
+    tasks::TaskStatus,
+    utils::http::HttpClient,
+    SyncState,
+};
+
+/// How often to check for subscriptions to renew.
+const TICK: Duration = Duration::from_secs(60);
+
+/// How many subscriptions are renewed per tick.
+const BATCH: i64 = 25;
+
+/// The lease we ask hubs for, they are free to grant a different one.
+const LEASE_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+/// Subscriptions are renewed this long before their lease runs out.
+const RENEW_BEFORE: time::Duration = time::Duration::days(1);
+
+/// Subscriptions the hub never verified are requested again after this long.
+const PENDING_TIMEOUT: time::Duration = time::Duration::days(1);
+
+pub async fn websub(sync: SyncState, status: TaskStatus) {
+    let Some(public_url) = sync.cfg.public_url.clone() else {
+        tracing::info!("No public URL configured, not subscribing to WebSub hubs");
+
+        return;
+    };
+
+    while status.is_active() {
+        let now = time::OffsetDateTime::now_utc();
+
+        let renewable = match sync
+            .db
+            .websubs_get_renewable(now + RENEW_BEFORE, now - PENDING_TIMEOUT, BATCH)
+            .await
+        {
+            Ok(renewable) => renewable,
+            Err(err) => {
+                tracing::error!(err = ?err, "Failed to retrieve renewable websub subscriptions");
+
+                vec![]
+            }
+        };
+
+        for row in renewable {
+            if !status.is_active() {
+                break;
+            }
+
+            let RowWebSub {
+                feed,
+                hub,
+                topic,
+                secret,
+                ..
+            } = row;
+
+            if let Err(err) = subscribe(
+                &sync.db,
+                &sync.http,
+                &public_url,
+                &feed,
+                &hub,
+                &topic,
+                Some(&secret),
+            )
+            .await
+            {
+                tracing::warn!(err = ?err, feed = %feed, "Failed to renew websub subscription");
+            }
+        }
+
+        status.sleep(TICK).await;
+    }
+}
+
+/// Subscribes to the hub the channel advertises, unless we are already subscribed to it or it
+/// refused us before.
+pub async fn subscribe_channel(sync: &SyncState, feed: &str, channel: &Channel) {
+    let Some(public_url) = &sync.cfg.public_url else {
+        return;
+    };
+    let (Some(hub), Some(topic)) = (channel.hub(), channel.self_link()) else {
+        return;
+    };
+
+    let existing = match sync.db.websub_get_by_feed(feed).await {
+        Ok(existing) => existing,
+        Err(err) => {
+            tracing::error!(err = ?err, "Failed to retrieve websub subscription");
+
+            return;
+        }
+    };
+
+    // Renewals are handled by the websub task.
+    if existing
+        .as_ref()
+        .is_some_and(|existing| existing.hub == hub && existing.topic == topic)
+    {
+        return;
+    }
+
+    if let Err(err) = subscribe(&sync.db, &sync.http, public_url, feed, hub, topic, None).await {
+        tracing::warn!(err = ?err, feed = %feed, hub = %hub, "Failed to subscribe to websub hub");
+    }
+}
+
+/// Asks the hub to send updates of the topic to our callback, the hub then verifies the request
+/// through the callback before the subscription is active.
+pub async fn subscribe(
+    db: &Database,
+    http: &HttpClient,
+    public_url: &Url,
+    feed: &str,
+    hub: &str,
+    topic: &str,
+    secret: Option<&str>,
+) -> anyhow::Result<()> {
+    let hub = Url::parse(hub)?;
+
+    let secret = match secret {
+        Some(secret) => secret.to_string(),
+        None => {
+            let mut secret = [0; 32];
+            rand::rngs::OsRng.fill_bytes(&mut secret);
+
+            HEXLOWER.encode(&secret)
+        }
+    };
+
+    let row = db
+        .websub_request(feed, hub.as_str(), topic, &secret)
+        .await?;
+    let callback = public_url.join(&format!("websub/{}", row.id.0))?;
+
+    let response = http
+        .post(&hub)?
+        .form(&[
+            ("hub.mode", "subscribe"),
+            ("hub.topic", topic),
+            ("hub.callback", callback.as_str()),
+            ("hub.secret", &secret),
+            ("hub.lease_seconds", &LEASE_SECONDS.to_string()),
+        ])
+        .send()
+        .await?;
+
+    let status = response.status();
+
+    // Only a client error means the hub refused us, anything else is retried later.
+    if status.is_client_error() && status != StatusCode::TOO_MANY_REQUESTS {
+        db.websub_deny(row.id).await?;
+
+        anyhow::bail!("Hub refused subscription with {}", status);
+    }
+    if !status.is_success() {
+        anyhow::bail!("Hub failed subscription with {}", status);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use axum::{extract::State, http::StatusCode, routing::post, Form, Router};
+    use tokio::net::TcpListener;
+    use url::Url;
+
+    use crate::{
+        config::OutboundConfig,
+        database::{websub::WebSubStatus, Database},
+        utils::http::HttpClient,
+    };
+
+    type Requests = Arc<Mutex<Vec<HashMap<String, String>>>>;
+
+    async fn hub(
+        State(requests): State<Requests>,
+        Form(form): Form<HashMap<String, String>>,
+    ) -> StatusCode {
+        requests.lock().unwrap().push(form);
+
+        StatusCode::ACCEPTED
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/dummy.sql"))]
+    async fn subscribe(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let requests = Requests::default();
+        let app = Router::new()
+            .route("/hub", post(hub))
+            .with_state(requests.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let db = Database::new_test(pool).await.unwrap();
+        let http = HttpClient::new(&OutboundConfig {
+            allowed_hosts: vec!["127.0.0.1".to_string()],
+            ..OutboundConfig::default()
+        })
+        .unwrap();
+        let public_url = Url::parse("https://sync.example.com/").unwrap();
+        let hub_url = format!("http://{}/hub", addr);
+
+        super::subscribe(
+            &db,
+            &http,
+            &public_url,
+            Database::SUBSCRIPTION_1_FEED,
+            &hub_url,
+            Database::SUBSCRIPTION_1_FEED,
+            None,
+        )
+        .await
+        .expect("Failed to subscribe");
+
+        let row = db
+            .websub_get_by_feed(Database::SUBSCRIPTION_1_FEED)
+            .await
+            .unwrap()
+            .expect("Subscription was not recorded");
+
+        assert_eq!(row.status, WebSubStatus::Pending);
+
+        let requests = requests.lock().unwrap();
+        let request = requests.first().expect("Hub was not called");
+
+        assert_eq!(request["hub.mode"], "subscribe");
+        assert_eq!(request["hub.topic"], Database::SUBSCRIPTION_1_FEED);
+        assert_eq!(
+            request["hub.callback"],
+            format!("https://sync.example.com/websub/{}", row.id.0)
+        );
+        assert_eq!(request["hub.secret"], row.secret);
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/dummy.sql"))]
+    async fn subscribe_unavailable(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let app = Router::new()
+            .route(
+                "/unavailable",
+                post(|| async { StatusCode::SERVICE_UNAVAILABLE }),
+            )
+            .route("/refused", post(|| async { StatusCode::FORBIDDEN }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let db = Database::new_test(pool).await.unwrap();
+        let http = HttpClient::new(&OutboundConfig {
+            allowed_hosts: vec!["127.0.0.1".to_string()],
+            ..OutboundConfig::default()
+        })
+        .unwrap();
+        let public_url = Url::parse("https://sync.example.com/").unwrap();
+
+        for (path, expected) in [
+            ("unavailable", WebSubStatus::Pending),
+            ("refused", WebSubStatus::Denied),
+        ] {
+            super::subscribe(
+                &db,
+                &http,
+                &public_url,
+                Database::SUBSCRIPTION_1_FEED,
+                &format!("http://{}/{}", addr, path),
+                Database::SUBSCRIPTION_1_FEED,
+                None,
+            )
+            .await
+            .expect_err("Subscribing should fail");
+
+            let row = db
+                .websub_get_by_feed(Database::SUBSCRIPTION_1_FEED)
+                .await
+                .unwrap()
+                .expect("Subscription was not recorded");
+
+            assert_eq!(row.status, expected, "{}", path);
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//! Runs the jobs in the queue, each worker only handles one kind of job and one job at a time.
+//!
+//! Failed jobs are retried with an exponential backoff until they run out of attempts, then they
+//! are dead-lettered: kept in the queue with their last error but never run again.
+
+use std::time::Duration;
+
+use time::OffsetDateTime;
+
+use crate::{
+    database::jobs::{Job, RowJob},
+    models::jobs::JobKind,
+    tasks::{account, deletion, email, export, identification, refresh, TaskStatus},
+    SyncState,
+};
+
+/// How long an idle worker waits before checking the queue again.
+const IDLE: Duration = Duration::from_secs(5);
+
+/// How long a worker has to finish a job before it is handed to another worker.
+const LEASE: time::Duration = time::Duration::minutes(10);
+
+/// The delay before the first retry, in seconds, it doubles with each attempt.
+const RETRY_DELAY: i64 = 30;
+const MAX_RETRY_DELAY: i64 = 6 * 60 * 60;
+
+pub async fn worker(sync: SyncState, kind: JobKind, status: TaskStatus) {
+    while status.is_active() {
+        match sync.db.job_lease(kind, LEASE).await {
+            Ok(Some(job)) => run(&sync, job).await,
+            Ok(None) => status.sleep(IDLE).await,
+            Err(err) => {
+                tracing::error!(err = ?err, "Failed to lease job");
+
+                status.sleep(IDLE).await;
+            }
+        }
+    }
+}
+
+#[tracing::instrument(skip_all, fields(job_id = row.id.0, kind = ?row.kind, attempt = row.attempts))]
+async fn run(sync: &SyncState, row: RowJob) {
+    let result = match row.job() {
+        // The worker running the last attempt died before it could finish.
+        Ok(_) if row.attempts > row.max_attempts => {
+            dead(sync, &row, "Lease ran out on the last attempt").await
+        }
+        Ok(job) => match perform(sync, &job).await {
+            Ok(()) => sync.db.job_complete(&row).await,
+            Err(err) if row.attempts >= row.max_attempts => {
+                tracing::error!(err = ?err, "Job failed on its last attempt");
+
+                dead(sync, &row, &format!("{:#}", err)).await
+            }
+            Err(err) => {
+                tracing::warn!(err = ?err, "Job failed, retrying later");
+
+                let run_after = OffsetDateTime::now_utc() + backoff(row.attempts);
+
+                sync.db
+                    .job_retry(&row, &format!("{:#}", err), run_after)
+                    .await
+            }
+        },
+        // Retrying won't make the payload any more readable.
+        Err(err) => {
+            tracing::error!(err = ?err, "Job payload is invalid");
+
+            dead(sync, &row, &format!("{:#}", err)).await
+        }
+    };
+
+    if let Err(err) = result {
+        tracing::error!(err = ?err, "Failed to update job");
+    }
+}
+
+async fn perform(sync: &SyncState, job: &Job) -> anyhow::Result<()> {
+    match job {
+        Job::Deletion { deletion_id } => deletion::run(sync, *deletion_id).await,
+        Job::Identification { subscription_id } => {
+            identification::run(sync, *subscription_id).await
+        }
+        Job::Refresh { feed } => refresh::run(sync, feed).await,
+        Job::Email { to, subject, body } => email::run(sync, to, subject, body).await,
+        Job::Export { export_id } => export::run(sync, *export_id).await,
+        Job::AccountDeletion { user_id } => account::run(sync, *user_id).await,
+    }
+}
+
+async fn dead(sync: &SyncState, row: &RowJob, error: &str) -> anyhow::Result<()> {
+    sync.db.job_dead(row, error).await?;
+
+    // Let the user know their deletion or export didn't go through.
+    match row.job() {
+        Ok(Job::Deletion { deletion_id }) => sync.db.deletion_fail(deletion_id).await?,
+        Ok(Job::Export { export_id }) => sync.db.export_fail(export_id).await?,
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn backoff(attempts: i64) -> time::Duration {
+    let delay = RETRY_DELAY << (attempts - 1).clamp(0, 16);
+
+    time::Duration::seconds(delay.min(MAX_RETRY_DELAY))
+}
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+
+    use crate::{
+        database::jobs::Job,
+        models::jobs::{JobKind, JobStatus},
+        SyncState,
+    };
+
+    use super::LEASE;
+
+    #[sqlx::test(fixtures("../../fixtures/dummy.sql"))]
+    async fn deduplicate(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let sync = SyncState::new_test(pool).await.unwrap();
+        let now = OffsetDateTime::now_utc();
+        let job = Job::Refresh {
+            feed: "http://one.example.com/feed.rss".to_string(),
+        };
+
+        assert!(sync.db.job_enqueue(&job, now).await.unwrap());
+        assert!(!sync.db.job_enqueue(&job, now).await.unwrap());
+
+        let row = sync
+            .db
+            .job_lease(JobKind::Refresh, LEASE)
+            .await
+            .unwrap()
+            .expect("Job was not queued");
+        assert!(!sync.db.job_enqueue(&job, now).await.unwrap());
+
+        sync.db.job_complete(&row).await.unwrap();
+        assert!(sync.db.job_enqueue(&job, now).await.unwrap());
+    }
+
+    #[sqlx::test(fixtures("../../fixtures/dummy.sql"))]
+    async fn retry(pool: sqlx::SqlitePool) {
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+        let sync = SyncState::new_test(pool.clone()).await.unwrap();
+        // Email isn't configured in tests, so sending always fails.
+        let job = Job::Email {
+            to: "example@example.com".to_string(),
+            subject: "Hello".to_string(),
+            body: "Hello".to_string(),
+        };
+
+        sync.db
+            .job_enqueue(&job, OffsetDateTime::now_utc())
+            .await
+            .unwrap();
+
+        let row = sync
+            .db
+            .job_lease(JobKind::Email, LEASE)
+            .await
+            .unwrap()
+            .expect("Job was not queued");
+        assert_eq!(row.attempts, 1);
+
+        super::run(&sync, row).await;
+
+        // Backing off, not retried straight away.
+        assert!(sync
+            .db
+            .job_lease(JobKind::Email, LEASE)
+            .await
+            .unwrap()
+            .is_none());
+
+        let row = sqlx::query!(
+            r#"--sql
+                SELECT status as "status: JobStatus", last_error
+                FROM jobs
+            "#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(row.status, JobStatus::Queued);
+        assert!(row.last_error.is_some());
+
+        let past = OffsetDateTime::now_utc() - time::Duration::minutes(1);
+        sqlx::query!(
+            r#"--sql
+                UPDATE jobs
+                SET attempts = max_attempts - 1, run_after = ?1
+            "#,
+            past,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let row = sync
+            .db
+            .job_lease(JobKind::Email, LEASE)
+            .await
+            .unwrap()
+            .expect("Job was not retried");
+        assert_eq!(row.attempts, row.max_attempts);
+
+        super::run(&sync, row).await;
+
+        let row = sqlx::query!(
+            r#"--sql
+                SELECT status as "status: JobStatus"
+                FROM jobs
+            "#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(row.status, JobStatus::Dead);
+    }
+}
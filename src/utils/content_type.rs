@@ -3,10 +3,12 @@ use std::fmt;
 use axum::http::header;
 use headers::{Error, Header, HeaderName, HeaderValue};
 use mediatype::{
-    names::{APPLICATION, JSON, XML},
-    MediaTypeBuf,
+    names::{APPLICATION, JSON, TEXT, XML},
+    MediaTypeBuf, Name,
 };
 
+const X_OPML: Name<'static> = Name::new_unchecked("x-opml");
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct ContentType(pub MediaTypeBuf);
 
@@ -20,6 +22,11 @@ impl ContentType {
     pub fn xml() -> ContentType {
         ContentType(MediaTypeBuf::new(APPLICATION, XML))
     }
+
+    #[inline]
+    pub fn opml() -> ContentType {
+        ContentType(MediaTypeBuf::new(TEXT, X_OPML))
+    }
 }
 
 impl Header for ContentType {
@@ -0,0 +1,340 @@
+//! The HTTP client used for every outbound request to a user supplied URL.
+//!
+//! Feed URLs come from users, so without care the server could be used to reach services that
+//! are only meant to be reachable from inside its network. Hosts are resolved before connecting
+//! and any private, loopback or link-local address is refused, this happens for every redirect
+//! as well. Hosts listed in the outbound allow-list skip the check, which is what the tests use
+//! to reach local stubs.
+
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use bytes::{Bytes, BytesMut};
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    redirect, Method, RequestBuilder, Response,
+};
+use url::{Host, Url};
+
+use crate::config::OutboundConfig;
+
+#[derive(Debug, thiserror::Error)]
+pub enum HttpError {
+    #[error("Only http and https URLs can be fetched, not {0}")]
+    Scheme(String),
+    #[error("URL is not valid: {0}")]
+    Url(#[from] url::ParseError),
+    #[error("URL has no host")]
+    MissingHost,
+    #[error("Refusing to connect to {0}, it is not a public address")]
+    Blocked(String),
+    #[error("Response is larger than {0} bytes")]
+    TooLarge(usize),
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+}
+
+#[derive(Clone)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    allowed_hosts: Arc<[String]>,
+    max_body_size: usize,
+}
+
+impl HttpClient {
+    pub fn new(cfg: &OutboundConfig) -> anyhow::Result<Self> {
+        let allowed_hosts: Arc<[String]> = cfg
+            .allowed_hosts
+            .iter()
+            .map(|host| host.to_ascii_lowercase())
+            .collect();
+        let max_redirects = cfg.max_redirects;
+
+        let policy = {
+            let allowed_hosts = allowed_hosts.clone();
+
+            redirect::Policy::custom(move |attempt| {
+                if attempt.previous().len() >= max_redirects {
+                    return attempt.error(format!("Stopped after {} redirects", max_redirects));
+                }
+
+                match check_url(&allowed_hosts, attempt.url()) {
+                    Ok(()) => attempt.follow(),
+                    Err(err) => attempt.error(err),
+                }
+            })
+        };
+
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("pod-sync/", env!("CARGO_PKG_VERSION")))
+            .timeout(Duration::from_secs(cfg.timeout))
+            .connect_timeout(Duration::from_secs(cfg.timeout.min(10)))
+            .redirect(policy)
+            // A proxy would resolve the host itself, skipping the address checks.
+            .no_proxy()
+            .dns_resolver(Arc::new(PublicResolver {
+                allowed_hosts: allowed_hosts.clone(),
+            }))
+            .build()?;
+
+        Ok(Self {
+            client,
+            allowed_hosts,
+            max_body_size: cfg.max_body_size,
+        })
+    }
+
+    /// Starts a request to the URL, as long as it is one we are allowed to fetch.
+    pub fn request(&self, method: Method, url: &Url) -> Result<RequestBuilder, HttpError> {
+        check_url(&self.allowed_hosts, url)?;
+
+        Ok(self.client.request(method, url.clone()))
+    }
+
+    pub fn get(&self, url: &Url) -> Result<RequestBuilder, HttpError> {
+        self.request(Method::GET, url)
+    }
+
+    pub fn post(&self, url: &Url) -> Result<RequestBuilder, HttpError> {
+        self.request(Method::POST, url)
+    }
+
+    /// Reads the whole response body, giving up once it grows past the size limit.
+    pub async fn bytes(&self, mut response: Response) -> Result<Bytes, HttpError> {
+        if response
+            .content_length()
+            .is_some_and(|length| length > self.max_body_size as u64)
+        {
+            return Err(HttpError::TooLarge(self.max_body_size));
+        }
+
+        let mut body = BytesMut::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > self.max_body_size {
+                return Err(HttpError::TooLarge(self.max_body_size));
+            }
+
+            body.extend_from_slice(&chunk);
+        }
+
+        Ok(body.freeze())
+    }
+
+    /// Reads the whole response body as text, see [`HttpClient::bytes`].
+    pub async fn text(&self, response: Response) -> Result<String, HttpError> {
+        let body = self.bytes(response).await?;
+
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+}
+
+fn check_url(allowed_hosts: &[String], url: &Url) -> Result<(), HttpError> {
+    match url.scheme() {
+        "http" | "https" => {}
+        scheme => return Err(HttpError::Scheme(scheme.to_string())),
+    }
+
+    let host = url.host().ok_or(HttpError::MissingHost)?;
+    let ip = match host {
+        // Domains are checked once they are resolved.
+        Host::Domain(_) => return Ok(()),
+        Host::Ipv4(ip) => IpAddr::V4(ip),
+        Host::Ipv6(ip) => IpAddr::V6(ip),
+    };
+
+    if is_allowed(allowed_hosts, &host.to_string()) || is_public(ip) {
+        Ok(())
+    } else {
+        Err(HttpError::Blocked(ip.to_string()))
+    }
+}
+
+fn is_allowed(allowed_hosts: &[String], host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    allowed_hosts
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(host))
+}
+
+/// Whether the address can be reached by anyone on the internet.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match embedded_v4(ip) {
+            Some(ip) => is_public_v4(ip),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+/// Returns the IPv4 address that ends up being reached through the IPv6 one, if any.
+fn embedded_v4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    let octets = ip.octets();
+
+    match ip.segments() {
+        // ::ffff:0:0/96, IPv4-mapped
+        [0, 0, 0, 0, 0, 0xffff, ..]
+        // ::/96, IPv4-compatible (deprecated), which includes :: and ::1
+        | [0, 0, 0, 0, 0, 0, ..]
+        // 64:ff9b::/96, NAT64
+        | [0x64, 0xff9b, 0, 0, 0, 0, ..] => {
+            Some(Ipv4Addr::new(octets[12], octets[13], octets[14], octets[15]))
+        }
+        // 2002::/16, 6to4
+        [0x2002, ..] => Some(Ipv4Addr::new(octets[2], octets[3], octets[4], octets[5])),
+        _ => None,
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // 0.0.0.0/8, "this network"
+        || a == 0
+        // 100.64.0.0/10, carrier-grade NAT
+        || (a == 100 && (b & 0b1100_0000) == 64)
+        // 192.0.0.0/24, protocol assignments
+        || (a == 192 && b == 0 && ip.octets()[2] == 0)
+        // 198.18.0.0/15, benchmarking
+        || (a == 198 && (b & 0b1111_1110) == 18)
+        // 240.0.0.0/4, reserved
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let [first, ..] = ip.segments();
+
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // fc00::/7, unique local
+        || (first & 0xfe00) == 0xfc00
+        // fe80::/10, link-local
+        || (first & 0xffc0) == 0xfe80
+        // 2001:db8::/32, documentation
+        || (first == 0x2001 && ip.segments()[1] == 0xdb8)
+        // 64:ff9b:1::/48, local-use NAT64
+        || (first == 0x64 && ip.segments()[1] == 0xff9b && ip.segments()[2] == 1))
+}
+
+/// Resolves hosts with the system resolver, dropping every address that isn't public.
+struct PublicResolver {
+    allowed_hosts: Arc<[String]>,
+}
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let allowed = is_allowed(&self.allowed_hosts, name.as_str());
+
+        Box::pin(resolve_public(name, allowed))
+    }
+}
+
+async fn resolve_public(
+    name: Name,
+    allowed: bool,
+) -> Result<Addrs, Box<dyn std::error::Error + Send + Sync>> {
+    let host = name.as_str();
+    let addrs = tokio::net::lookup_host((host, 0))
+        .await?
+        .filter(|addr| allowed || is_public(addr.ip()))
+        .collect::<Vec<SocketAddr>>();
+
+    if addrs.is_empty() {
+        return Err(HttpError::Blocked(host.to_string()).into());
+    }
+
+    Ok(Box::new(addrs.into_iter()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use url::Url;
+
+    use super::{check_url, is_public, HttpClient, HttpError};
+    use crate::config::OutboundConfig;
+
+    #[test]
+    fn public_addresses() {
+        let cases = [
+            ("93.184.215.14", true),
+            ("2606:2800:21f:cb07:6820:80da:af6b:8b2c", true),
+            ("10.0.0.1", false),
+            ("172.16.5.4", false),
+            ("192.168.1.1", false),
+            ("127.0.0.1", false),
+            ("169.254.169.254", false),
+            ("100.64.0.1", false),
+            ("0.0.0.0", false),
+            ("::1", false),
+            ("fd00::1", false),
+            ("fe80::1", false),
+            ("::ffff:127.0.0.1", false),
+            ("::ffff:93.184.215.14", true),
+            ("::127.0.0.1", false),
+            ("::10.0.0.1", false),
+            ("64:ff9b::a9fe:a9fe", false),
+            ("64:ff9b::93.184.215.14", true),
+            ("64:ff9b:1::a00:1", false),
+            ("2002:7f00:1::", false),
+            ("2002:c0a8:101::1", false),
+            ("2002:5db8:d70e::1", true),
+        ];
+
+        for (ip, expected) in cases {
+            let ip: IpAddr = ip.parse().unwrap();
+
+            assert_eq!(is_public(ip), expected, "{}", ip);
+        }
+    }
+
+    #[test]
+    fn urls() {
+        let allowed = ["127.0.0.1".to_string()];
+
+        let check = |url: &str, allowed: &[String]| check_url(allowed, &Url::parse(url).unwrap());
+
+        assert!(check("https://example.com/feed.rss", &[]).is_ok());
+        assert!(matches!(
+            check("file:///etc/passwd", &[]),
+            Err(HttpError::Scheme(_))
+        ));
+        assert!(matches!(
+            check("http://127.0.0.1:8080/feed.rss", &[]),
+            Err(HttpError::Blocked(_))
+        ));
+        assert!(matches!(
+            check("http://[::1]/feed.rss", &[]),
+            Err(HttpError::Blocked(_))
+        ));
+        assert!(check("http://127.0.0.1:8080/feed.rss", &allowed).is_ok());
+    }
+
+    #[tokio::test]
+    async fn resolved_private_address() {
+        let client = HttpClient::new(&OutboundConfig::default()).unwrap();
+        let url = Url::parse("http://localhost:9/feed.rss").unwrap();
+
+        let err = client
+            .get(&url)
+            .unwrap()
+            .send()
+            .await
+            .expect_err("Request to localhost was sent");
+
+        assert!(format!("{:?}", err).contains("not a public address"));
+    }
+}
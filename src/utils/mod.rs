@@ -1,5 +1,7 @@
 pub mod content_type;
+pub mod http;
 pub mod json;
+pub mod opml;
 pub mod serde;
 #[cfg(test)]
 pub mod test;
@@ -0,0 +1,109 @@
+use axum::{
+    extract::{FromRequest, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use axum_extra::TypedHeader;
+use bytes::Bytes;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::utils::content_type::ContentType;
+
+static XML_DECLARATION: &str = r#"<?xml version="1.0" encoding="UTF-8" ?>"#;
+static BAD_REQUEST: &str = r#"<?xml version="1.0" encoding="UTF-8" ?><error><code>400</code><message>OPML document failed to decode</message></error>"#;
+static INTERNAL_ERROR: &str = r#"<?xml version="1.0" encoding="UTF-8" ?><error><code>500</code><message>Internal error</message></error>"#;
+
+pub struct OpmlRejection;
+
+impl IntoResponse for OpmlRejection {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::BAD_REQUEST,
+            TypedHeader(ContentType::xml()),
+            BAD_REQUEST,
+        )
+            .into_response()
+    }
+}
+
+/// An OPML document, (de)serialized with `quick-xml` the same way as [`Xml`](crate::utils::xml::Xml)
+/// but served as `text/x-opml` with an XML declaration.
+#[derive(Debug, Clone, Copy, Default)]
+#[must_use]
+pub struct OpmlDocument<T>(pub T);
+
+impl<T> OpmlDocument<T>
+where
+    T: DeserializeOwned,
+{
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, OpmlRejection> {
+        match quick_xml::de::from_reader(bytes) {
+            Ok(value) => Ok(Self(value)),
+            Err(err) => {
+                tracing::error!(err = %err, "Failed to deserialize OPML document");
+
+                Err(OpmlRejection)
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<T, S> FromRequest<S> for OpmlDocument<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = OpmlRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let bytes = match Bytes::from_request(req, state).await {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                tracing::error!(err = %err, "Failed to read body");
+
+                return Err(OpmlRejection);
+            }
+        };
+
+        Self::from_bytes(&bytes)
+    }
+}
+
+impl<T> OpmlDocument<T>
+where
+    T: Serialize,
+{
+    /// Encodes the document, along with the XML declaration.
+    pub fn encode(&self) -> anyhow::Result<String> {
+        let mut buf = String::with_capacity(128);
+        buf.push_str(XML_DECLARATION);
+
+        quick_xml::se::to_writer(&mut buf, &self.0)?;
+
+        Ok(buf)
+    }
+}
+
+impl<T> IntoResponse for OpmlDocument<T>
+where
+    T: Serialize,
+{
+    fn into_response(self) -> Response {
+        let buf = match self.encode() {
+            Ok(buf) => buf,
+            Err(err) => {
+                tracing::error!(err = %err, "Failed to encode OPML response");
+
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    TypedHeader(ContentType::xml()),
+                    INTERNAL_ERROR,
+                )
+                    .into_response();
+            }
+        };
+
+        (TypedHeader(ContentType::opml()), buf).into_response()
+    }
+}
@@ -1,5 +1,8 @@
+use std::convert::Infallible;
+
 use axum::{
-    extract::{FromRequest, Request},
+    extract::{FromRequest, FromRequestParts, Request},
+    http::request::Parts,
     response::{IntoResponse, Response},
     RequestExt,
 };
@@ -126,6 +129,29 @@ where
     }
 }
 
+/// Negotiates the response encoding from the `Accept` header, falling back to JSON.
+pub struct Accepts(pub EncodingType);
+
+#[async_trait::async_trait]
+impl<S> FromRequestParts<S> for Accepts
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let encoding_type = match TypedHeader::<Accept>::from_request_parts(parts, state).await {
+            Ok(TypedHeader(header)) => match header.negotiate(SUPPORTED_MEDIA_TYPES) {
+                Some(typ) if is_xml(&typ.clone().into()) => EncodingType::Xml,
+                _ => EncodingType::Json,
+            },
+            Err(_) => EncodingType::Json,
+        };
+
+        Ok(Self(encoding_type))
+    }
+}
+
 pub struct Serializable<T>(pub EncodingType, pub T)
 where
     T: serde::Serialize;